@@ -0,0 +1,14 @@
+module pad # (
+    parameter WIDTH = 32'd8
+) (
+    oe,
+    a,
+    pad,
+    y
+);
+    input wire oe;
+    input wire [7:0] a;
+    inout wire [7:0] pad;
+    output reg [7:0] y;
+    assign pad = oe ? a : 8'hzz;
+endmodule
@@ -0,0 +1,33 @@
+module function_task (
+    input wire clock,
+    input wire [7:0] a,
+    output reg [7:0] y
+);
+    function automatic [7:0] add(input reg [7:0] x, input reg [7:0] z);
+        begin
+            add = x + z;
+        end
+    endfunction
+    task wait_cycles(input reg [7:0] n, output reg done, inout reg [7:0] acc);
+        reg [7:0] tmp;
+        begin
+            done = 1'b0;
+            repeat(n) begin
+                @(posedge clock);
+            end
+            #10;
+            tmp = acc + 8'd1;
+            acc = tmp;
+            done = 1'b1;
+        end
+    endtask
+    task tick;
+        begin
+            #(a * 2);
+        end
+    endtask
+    always @(posedge clock) begin
+        y <= add(a, y);
+        tick;
+    end
+endmodule
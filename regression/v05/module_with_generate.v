@@ -0,0 +1,22 @@
+module module_with_generate # (
+    parameter N = 32'd4
+) (
+    input wire [3:0] a,
+    output wire [3:0] y
+);
+    genvar i;
+    for(i = 0; i < N; i = i + 1) begin : gen_bit
+        wire t;
+        prim buf_i (
+            .port_a(a[i]),
+            .port_y(y[i])
+        );
+    end
+    if(N > 8) begin : wide
+        wire [7:0] w;
+    end else if(N == 4) begin
+        wire [3:0] n;
+    end else begin : other
+        wire [1:0] o;
+    end
+endmodule
@@ -0,0 +1,9 @@
+module module_with_positional_instance (
+    input wire [3:0] a,
+    output wire [3:0] y
+);
+    prim i0 (
+        a,
+        y
+    );
+endmodule
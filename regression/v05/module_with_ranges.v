@@ -0,0 +1,11 @@
+module ranges # (
+    parameter WIDTH = 32'd8
+) (
+    input wire [WIDTH - 1:0] a,
+    input wire [0:7] b,
+    output wire [WIDTH - 1:0] y
+);
+    reg [3:0] lsb;
+    reg [WIDTH - 1:0] mem [3:0];
+    assign y = a + b;
+endmodule
@@ -0,0 +1,10 @@
+module mac (
+    input wire signed [7:0] a,
+    input wire signed [7:0] b,
+    input wire signed c,
+    output wire signed [15:0] y
+);
+    reg signed [15:0] acc;
+    wire signed sign;
+    assign y = a * b + acc;
+endmodule
@@ -0,0 +1,14 @@
+module regfile (
+    input logic clock,
+    input logic [3:0] addr,
+    input logic [1:0][7:0] wdata,
+    input logic [7:0] init [4],
+    output logic [7:0] rdata
+);
+    logic [1:0][7:0] mem [16];
+    int count [0:3];
+    always_ff @(posedge clock) begin
+        mem[addr] <= wdata;
+    end
+    assign rdata = mem[addr][1];
+endmodule
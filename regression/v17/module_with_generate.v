@@ -0,0 +1,13 @@
+module module_with_generate (
+    input logic [7:0] a,
+    output logic [7:0] y
+);
+    genvar i;
+    for(i = 0; i < 8; i = i + 1) begin : gen_rev
+        assign y[i] = a[7 - i];
+    end
+    if(a == 0) begin
+        logic zero;
+    end else begin : nonzero
+    end
+endmodule
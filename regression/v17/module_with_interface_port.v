@@ -0,0 +1,10 @@
+module producer (
+    input logic clk,
+    bus_if.master m
+);
+    always_ff @(posedge clk) begin
+        if(m.ready) begin
+            m.send(m.data + 8'd1);
+        end
+    end
+endmodule
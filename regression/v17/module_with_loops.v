@@ -0,0 +1,23 @@
+module module_with_loops ();
+    logic [7:0] mem;
+    int count;
+    initial begin
+        for(int i = 0; i < 8; i = i + 1) begin
+            mem[i] = 0;
+        end
+        foreach(mem[j]) begin
+            mem[j] = 1;
+        end
+        do begin
+            count = count + 1;
+        end while(count < 8);
+        while(count > 0) begin
+            count = count - 1;
+        end
+        repeat(4) begin
+            count = count + 1;
+        end
+        forever begin
+        end
+    end
+endmodule
@@ -0,0 +1,14 @@
+module module_with_positional_instance (
+    input logic clk,
+    input logic [7:0] a,
+    output logic [7:0] y
+);
+    fifo # (
+        .WIDTH(32'd8),
+        .DEPTH(32'd4)
+    ) f0 (
+        clk,
+        a,
+        y
+    );
+endmodule
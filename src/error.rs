@@ -0,0 +1,144 @@
+use crate::subset::ast::Id;
+use crate::subset::bitvec::BitVecError;
+use crate::subset::eval::EvalError;
+use crate::subset::parser::ParseError;
+use crate::subset::width::WidthError;
+use std::error;
+use std::fmt;
+use std::io;
+
+/// Errors reported by the fallible parts of the library.
+#[derive(Debug)]
+pub enum Error {
+    /// A node that can not be printed as valid Verilog.
+    Invalid(String),
+    /// A name declared more than once.
+    Duplicate(Id),
+    /// A design unit that uses a module, interface or package that is not
+    /// defined.
+    Undefined {
+        unit: Id,
+        name: Id,
+    },
+    /// An instance that connects a port the module does not have.
+    UnknownPort {
+        instance: Id,
+        module: Id,
+        port: Id,
+    },
+    /// An instance that leaves an input of the module unconnected.
+    Unconnected {
+        instance: Id,
+        module: Id,
+        port: Id,
+    },
+    /// An instance that sets a parameter the module does not have.
+    UnknownParam {
+        instance: Id,
+        module: Id,
+        param: Id,
+    },
+    Parse(ParseError),
+    BitVec(BitVecError),
+    Eval(EvalError),
+    Width(WidthError),
+    Io(io::Error),
+}
+
+impl Error {
+    pub fn invalid(msg: &str) -> Error {
+        Error::Invalid(msg.to_string())
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Invalid(msg) => write!(f, "{}", msg),
+            Error::Duplicate(id) => write!(f, "`{}` is declared more than once", id),
+            Error::Undefined { unit, name } => {
+                write!(f, "`{}` uses `{}`, which is not defined", unit, name)
+            }
+            Error::UnknownPort {
+                instance,
+                module,
+                port,
+            } => write!(
+                f,
+                "instance `{}` of `{}` connects `{}`, which is not a port",
+                instance, module, port
+            ),
+            Error::Unconnected {
+                instance,
+                module,
+                port,
+            } => write!(
+                f,
+                "instance `{}` of `{}` leaves input `{}` unconnected",
+                instance, module, port
+            ),
+            Error::UnknownParam {
+                instance,
+                module,
+                param,
+            } => write!(
+                f,
+                "instance `{}` of `{}` sets `{}`, which is not a parameter",
+                instance, module, param
+            ),
+            Error::Parse(err) => write!(f, "{}", err),
+            Error::BitVec(err) => write!(f, "{}", err),
+            Error::Eval(err) => write!(f, "{}", err),
+            Error::Width(err) => write!(f, "{}", err),
+            Error::Io(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Invalid(_)
+            | Error::Duplicate(_)
+            | Error::Undefined { .. }
+            | Error::UnknownPort { .. }
+            | Error::Unconnected { .. }
+            | Error::UnknownParam { .. } => None,
+            Error::Parse(err) => Some(err),
+            Error::BitVec(err) => Some(err),
+            Error::Eval(err) => Some(err),
+            Error::Width(err) => Some(err),
+            Error::Io(err) => Some(err),
+        }
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(err: ParseError) -> Self {
+        Error::Parse(err)
+    }
+}
+
+impl From<BitVecError> for Error {
+    fn from(err: BitVecError) -> Self {
+        Error::BitVec(err)
+    }
+}
+
+impl From<EvalError> for Error {
+    fn from(err: EvalError) -> Self {
+        Error::Eval(err)
+    }
+}
+
+impl From<WidthError> for Error {
+    fn from(err: WidthError) -> Self {
+        Error::Width(err)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
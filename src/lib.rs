@@ -1,4 +1,7 @@
+mod error;
 mod subset;
 pub mod util;
 pub mod v05;
 pub mod v17;
+
+pub use error::Error;
@@ -1,10 +1,23 @@
-use std::collections::HashMap;
+use crate::subset::bitvec::BitVec;
 use std::rc::Rc;
 
 pub type Id = String;
-pub type Map = HashMap<Id, Expr>;
 
-/// Unary reduction operators.
+/// Named connections of an instance, in the order they were added. A name
+/// can be left open, i.e. `.a()`, which only shows in `names`, `open` and
+/// `len`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Map {
+    pub(crate) entries: Vec<(Id, Option<Expr>)>,
+}
+
+/// Iterator over the names of a `Map` that have a value, in order.
+#[derive(Clone, Debug)]
+pub struct MapIter<'a> {
+    pub(crate) entries: std::slice::Iter<'a, (Id, Option<Expr>)>,
+}
+
+/// Unary operators.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Unop {
     LogNot,
@@ -15,6 +28,8 @@ pub enum Unop {
     Nor,
     Xor,
     Xnor,
+    /// Arithmetic negation, `-a`.
+    Neg,
 }
 
 /// Binary operators.
@@ -25,16 +40,26 @@ pub enum Binop {
     Add,
     Sub,
     Mul,
+    Div,
+    Mod,
+    Pow,
     Gt,
     Lt,
     Geq,
     Leq,
     Equal,
     NotEqual,
+    CaseEqual,
+    CaseNotEqual,
     IndexBit,
     BitAnd,
     BitOr,
+    BitXor,
+    BitXnor,
     ShiftLeft,
+    ShiftRight,
+    ArithShiftLeft,
+    ArithShiftRight,
 }
 
 /// Ternaray operations
@@ -45,14 +70,14 @@ pub enum Terop {
     IndexSlice,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Radix {
     Dec,
     Bin,
     Hex,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub struct InstancePath {
     pub path: Vec<Id>,
 }
@@ -67,7 +92,7 @@ pub enum Expr {
     X,
     Ref(Id),
     Int(i32),
-    ULit(u32, Radix, String),
+    ULit(BitVec, Radix),
     Str(String),
     Signed(Rc<Expr>),
     IPath(InstancePath, Option<Rc<Expr>>),
@@ -92,7 +117,7 @@ pub struct Attribute {
 }
 
 /// Event type for a task.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum EventTy {
     /// A positive edge triggered event.
     Posedge,
@@ -100,8 +125,18 @@ pub enum EventTy {
     Negedge,
 }
 
+/// Port connections of an instance, an instance connects its ports either
+/// by name or by position.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Connections {
+    /// Ports connected by name, i.e. `.a(x)`.
+    Named(Map),
+    /// Ports connected in the order of the module ports, i.e. `(x, y)`.
+    Positional(Vec<Expr>),
+}
+
 /// Instance of a module.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct Instance {
     /// Name of this instance.
     pub id: Id,
@@ -110,13 +145,13 @@ pub struct Instance {
     /// Values for the parameters.
     pub params: Map,
     /// Wires for the ports.
-    pub ports: Map,
+    pub ports: Connections,
     /// Attributes for the instance.
     pub attr: Attribute,
 }
 
 /// The type of assignment.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum AssignTy {
     /// A blocking assignment.
     Blocking,
@@ -126,7 +161,7 @@ pub enum AssignTy {
 
 /// Representation for the case statement
 // T ~> Sequential type
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct GenericCaseBranch<T> {
     /// The conditional guard for this case.
     pub cond: Expr,
@@ -136,14 +171,14 @@ pub struct GenericCaseBranch<T> {
 
 /// Representation for the default case in a case statement.
 // T ~> Sequential type
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct GenericCaseDefault<T> {
     pub body: Vec<T>,
 }
 
 /// A case expression.
 // T ~> Sequential type
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct GenericCase<T> {
     /// The condition of the case expression.
     pub cond: Expr,
@@ -155,26 +190,29 @@ pub struct GenericCase<T> {
 
 /// A port of a module or a function.
 // T ~> Declaration type
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum GenericPort<T> {
     /// An input port.
     Input(T),
     /// An output port.
     Output(T),
+    /// A bidirectional port.
+    Inout(T),
 }
 
 // F ~> Functiom type
 // T ~> Declaration type
 // U ~> Sequential type
 // V ~> Data Type
-#[derive(Clone, Debug)]
-pub struct GenericFunction<F, T, U, V> {
+// P ~> Port type
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GenericFunction<F, T, U, V, P = GenericPort<T>> {
     /// Function type (DPI-C).
     pub ty: F,
     /// Name of the function.
     pub name: Id,
     /// Ports of the function.
-    pub ports: Vec<GenericPort<T>>,
+    pub ports: Vec<P>,
     /// Declarations in this function.
     pub decls: Vec<T>,
     /// Body of this function.
@@ -183,9 +221,51 @@ pub struct GenericFunction<F, T, U, V> {
     pub ret: V,
 }
 
+/// A `begin ... end` block of module items inside a generate construct.
 // T ~> Declaration type
 // U ~> Parallel type
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GenericGenerateBlock<T, U> {
+    /// Name of the generate scope, printed as `begin : label`.
+    pub label: Option<Id>,
+    /// Items of this block.
+    pub body: Vec<GenericStmt<T, U>>,
+}
+
+/// A generate loop, `for (var = init; cond; var = step)`.
+// T ~> Declaration type
+// U ~> Parallel type
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GenericGenerateFor<T, U> {
+    /// The genvar driving the loop.
+    pub var: Id,
+    /// Initial value of the genvar.
+    pub init: Expr,
+    /// The loop runs while this condition holds.
+    pub cond: Expr,
+    /// Next value of the genvar.
+    pub step: Expr,
+    /// The block replicated for every iteration.
+    pub block: GenericGenerateBlock<T, U>,
+}
+
+/// A generate conditional.
+// T ~> Declaration type
+// U ~> Parallel type
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GenericGenerateIf<T, U> {
+    /// The constant condition.
+    pub cond: Expr,
+    /// The block generated when the condition holds.
+    pub block: GenericGenerateBlock<T, U>,
+    /// The block generated otherwise, an unlabeled block that only holds
+    /// another conditional is printed as `else if`.
+    pub else_branch: Option<GenericGenerateBlock<T, U>>,
+}
+
+// T ~> Declaration type
+// U ~> Parallel type
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum GenericStmt<T, U> {
     /// A declaration parameterized on the verilog standard.
     Decl(T),
@@ -193,18 +273,23 @@ pub enum GenericStmt<T, U> {
     Parallel(U),
     /// A raw string that represents a valid Verilog statement
     RawStr(String),
+    /// A generate loop.
+    GenFor(GenericGenerateFor<T, U>),
+    /// A generate conditional.
+    GenIf(GenericGenerateIf<T, U>),
 }
 
 // T ~> Declaration type
 // U ~> Parallel type
-#[derive(Clone, Debug)]
-pub struct GenericModule<T, U> {
+// P ~> Port type
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GenericModule<T, U, P = GenericPort<T>> {
     /// Name of the module.
     pub name: String,
     /// Parameters for the module.
     pub params: Vec<T>,
     /// Ports of this module.
-    pub ports: Vec<GenericPort<T>>,
+    pub ports: Vec<P>,
     /// Body of this module.
     pub body: Vec<GenericStmt<T, U>>,
     /// Attributes for this module.
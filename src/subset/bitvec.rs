@@ -0,0 +1,299 @@
+use crate::subset::ast::Radix;
+use std::error;
+use std::fmt;
+
+/// A four-state bit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Bit {
+    Zero,
+    One,
+    X,
+    Z,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BitVecError {
+    /// A bit-vector must be at least one bit wide.
+    ZeroWidth,
+    /// A literal without digits.
+    Empty,
+    /// A digit that is not valid in the radix.
+    InvalidDigit(char, Radix),
+    /// A value that needs more bits than the declared width.
+    Overflow(u32),
+}
+
+impl fmt::Display for BitVecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BitVecError::ZeroWidth => write!(f, "width must be greater than zero"),
+            BitVecError::Empty => write!(f, "literal has no digits"),
+            BitVecError::InvalidDigit(c, radix) => {
+                write!(f, "invalid digit `{}` for radix `{}`", c, radix)
+            }
+            BitVecError::Overflow(width) => write!(f, "value does not fit in {} bits", width),
+        }
+    }
+}
+
+impl error::Error for BitVecError {}
+
+/// An arbitrary-width four-state bit-vector, the value behind sized
+/// literals.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BitVec {
+    // least significant bit first, always `width` bits long
+    bits: Vec<Bit>,
+}
+
+/// Bits of a binary or hexadecimal literal, least significant first.
+fn pow2_bits(digits: &[char], radix: Radix) -> Result<Vec<Bit>, BitVecError> {
+    let (base, n) = match radix {
+        Radix::Bin => (2, 1),
+        _ => (16, 4),
+    };
+    let mut bits = Vec::new();
+    for c in digits.iter().rev() {
+        match c {
+            'x' | 'X' => bits.extend(vec![Bit::X; n]),
+            'z' | 'Z' | '?' => bits.extend(vec![Bit::Z; n]),
+            _ => {
+                let d = c
+                    .to_digit(base)
+                    .ok_or(BitVecError::InvalidDigit(*c, radix))?;
+                bits.extend((0..n).map(|i| if d >> i & 1 == 1 { Bit::One } else { Bit::Zero }));
+            }
+        }
+    }
+    Ok(bits)
+}
+
+/// Bits of a decimal literal, least significant first.
+fn dec_bits(digits: &[char]) -> Result<Vec<Bit>, BitVecError> {
+    match digits {
+        ['x'] | ['X'] => return Ok(vec![Bit::X]),
+        ['z'] | ['Z'] | ['?'] => return Ok(vec![Bit::Z]),
+        _ => (),
+    }
+    // little endian 32-bit limbs
+    let mut limbs: Vec<u32> = vec![0];
+    for c in digits {
+        let d = c
+            .to_digit(10)
+            .ok_or(BitVecError::InvalidDigit(*c, Radix::Dec))?;
+        let mut carry = d as u64;
+        for limb in limbs.iter_mut() {
+            let v = *limb as u64 * 10 + carry;
+            *limb = v as u32;
+            carry = v >> 32;
+        }
+        if carry > 0 {
+            limbs.push(carry as u32);
+        }
+    }
+    Ok(limbs
+        .iter()
+        .flat_map(|limb| (0..32).map(move |i| limb >> i & 1 == 1))
+        .map(|b| if b { Bit::One } else { Bit::Zero })
+        .collect())
+}
+
+/// Strip leading zero digits as long as the literal keeps its meaning,
+/// a leading `x` or `z` digit would be extended to the full width.
+fn strip_zeros(digits: &str) -> String {
+    let trimmed = digits.trim_start_matches('0');
+    match trimmed.chars().next() {
+        None => "0".to_string(),
+        Some('x') | Some('z') if trimmed.len() < digits.len() => format!("0{}", trimmed),
+        _ => trimmed.to_string(),
+    }
+}
+
+impl BitVec {
+    /// Create a bit-vector from bits, least significant first. Missing bits
+    /// are filled with zeros, or extended if the most significant bit is `x`
+    /// or `z`, and extra bits must be such an extension.
+    pub fn from_bits(width: u32, mut bits: Vec<Bit>) -> Result<BitVec, BitVecError> {
+        if width == 0 {
+            return Err(BitVecError::ZeroWidth);
+        }
+        let width = width as usize;
+        let fill = match bits.last() {
+            Some(Bit::X) => Bit::X,
+            Some(Bit::Z) => Bit::Z,
+            _ => Bit::Zero,
+        };
+        if bits.len() > width {
+            let msb = bits[width - 1];
+            let extended = |b: &Bit| *b == Bit::Zero || (*b == msb && msb == fill);
+            if !bits[width..].iter().all(extended) {
+                return Err(BitVecError::Overflow(width as u32));
+            }
+        }
+        bits.resize(width, fill);
+        Ok(BitVec { bits })
+    }
+
+    pub fn from_u64(width: u32, value: u64) -> Result<BitVec, BitVecError> {
+        BitVec::from_u128(width, value as u128)
+    }
+
+    pub fn from_u128(width: u32, value: u128) -> Result<BitVec, BitVecError> {
+        BitVec::from_le_bytes(width, &value.to_le_bytes())
+    }
+
+    /// Create a bit-vector from bytes in little-endian order.
+    pub fn from_le_bytes(width: u32, bytes: &[u8]) -> Result<BitVec, BitVecError> {
+        let bits = bytes
+            .iter()
+            .flat_map(|byte| (0..8).map(move |i| byte >> i & 1 == 1))
+            .map(|b| if b { Bit::One } else { Bit::Zero })
+            .collect();
+        BitVec::from_bits(width, bits)
+    }
+
+    /// Parse the digits of a literal, `_` separators are ignored and `?` is
+    /// the same as `z`.
+    pub fn from_str_radix(width: u32, digits: &str, radix: Radix) -> Result<BitVec, BitVecError> {
+        let digits: Vec<char> = digits.chars().filter(|c| *c != '_').collect();
+        if digits.is_empty() {
+            return Err(BitVecError::Empty);
+        }
+        let bits = match radix {
+            Radix::Dec => dec_bits(&digits)?,
+            _ => pow2_bits(&digits, radix)?,
+        };
+        BitVec::from_bits(width, bits)
+    }
+
+    pub fn width(&self) -> u32 {
+        self.bits.len() as u32
+    }
+
+    /// The bit at `index`, the least significant bit has index zero.
+    pub fn get(&self, index: u32) -> Option<Bit> {
+        self.bits.get(index as usize).copied()
+    }
+
+    /// Bits, least significant first.
+    pub fn bits(&self) -> &[Bit] {
+        &self.bits
+    }
+
+    /// True if no bit is `x` or `z`.
+    pub fn is_known(&self) -> bool {
+        self.bits.iter().all(|b| *b == Bit::Zero || *b == Bit::One)
+    }
+
+    /// The value as an integer, if it is known and fits in 128 bits.
+    pub fn to_u128(&self) -> Option<u128> {
+        let mut value: u128 = 0;
+        for (i, bit) in self.bits.iter().enumerate() {
+            match bit {
+                Bit::Zero => (),
+                Bit::One if i < 128 => value |= 1 << i,
+                _ => return None,
+            }
+        }
+        Some(value)
+    }
+
+    /// The digits of the literal in `radix`, if every digit can be
+    /// represented in it.
+    pub fn digits(&self, radix: Radix) -> Option<String> {
+        match radix {
+            Radix::Bin => {
+                let digits: String = self.bits.iter().rev().map(char::from).collect();
+                Some(strip_zeros(&digits))
+            }
+            Radix::Hex => {
+                let fill = match self.bits.last() {
+                    Some(Bit::X) => Bit::X,
+                    Some(Bit::Z) => Bit::Z,
+                    _ => Bit::Zero,
+                };
+                let mut digits = String::new();
+                for nibble in self.bits.chunks(4).rev() {
+                    let mut nibble = nibble.to_vec();
+                    nibble.resize(4, fill);
+                    let digit = if nibble.iter().all(|b| *b == Bit::X) {
+                        'x'
+                    } else if nibble.iter().all(|b| *b == Bit::Z) {
+                        'z'
+                    } else {
+                        let value = BitVec { bits: nibble }.to_u128()?;
+                        std::char::from_digit(value as u32, 16)?
+                    };
+                    digits.push(digit);
+                }
+                Some(strip_zeros(&digits))
+            }
+            Radix::Dec => {
+                if self.bits.iter().all(|b| *b == Bit::X) {
+                    return Some("x".to_string());
+                } else if self.bits.iter().all(|b| *b == Bit::Z) {
+                    return Some("z".to_string());
+                } else if !self.is_known() {
+                    return None;
+                }
+                // little endian 32-bit limbs, divided by ten until zero
+                let mut limbs: Vec<u32> = self
+                    .bits
+                    .chunks(32)
+                    .map(|chunk| {
+                        chunk
+                            .iter()
+                            .enumerate()
+                            .fold(0, |acc, (i, b)| acc | ((*b == Bit::One) as u32) << i)
+                    })
+                    .collect();
+                let mut digits = Vec::new();
+                loop {
+                    let mut rem: u64 = 0;
+                    for limb in limbs.iter_mut().rev() {
+                        let v = rem << 32 | *limb as u64;
+                        *limb = (v / 10) as u32;
+                        rem = v % 10;
+                    }
+                    digits.push(std::char::from_digit(rem as u32, 10)?);
+                    if limbs.iter().all(|limb| *limb == 0) {
+                        break;
+                    }
+                }
+                Some(digits.iter().rev().collect())
+            }
+        }
+    }
+
+    /// Print as a sized literal in `radix`, falling back to binary when a
+    /// digit mixes known and unknown bits.
+    pub fn to_string_radix(&self, radix: Radix) -> String {
+        match self.digits(radix) {
+            Some(digits) => format!("{}'{}{}", self.width(), radix, digits),
+            None => self.to_string(),
+        }
+    }
+}
+
+impl From<Bit> for char {
+    fn from(bit: Bit) -> Self {
+        match bit {
+            Bit::Zero => '0',
+            Bit::One => '1',
+            Bit::X => 'x',
+            Bit::Z => 'z',
+        }
+    }
+}
+
+impl From<&Bit> for char {
+    fn from(bit: &Bit) -> Self {
+        char::from(*bit)
+    }
+}
+
+impl fmt::Display for BitVec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_string_radix(Radix::Bin))
+    }
+}
@@ -0,0 +1,366 @@
+use crate::subset::ast::*;
+use crate::subset::eval::Env;
+use crate::subset::instance::{check_instance, connections, Definition};
+use crate::subset::width::Scope;
+use crate::Error;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Separator of the parts of mangled and hierarchical names.
+pub const SEP: &str = "__";
+
+/// A parallel statement, which may be an instance of a module.
+pub trait AsInstance {
+    fn as_instance_mut(&mut self) -> Option<&mut Instance>;
+}
+
+/// A design as seen by elaboration, `T`, `U` and `P` are the declaration,
+/// parallel and port types of its modules.
+pub trait Hierarchy<T, U, P> {
+    fn modules(&self) -> &BTreeMap<Id, GenericModule<T, U, P>>;
+
+    /// Collect the scope of `module`, used to fold the values of its
+    /// parameters.
+    fn scope(&self, module: &GenericModule<T, U, P>) -> Scope;
+
+    /// Statements that replace `inst` of `def`, the names declared by `def`
+    /// are prefixed by the name of the instance.
+    fn inline(
+        &self,
+        inst: &Instance,
+        def: &GenericModule<T, U, P>,
+    ) -> Result<Vec<GenericStmt<T, U>>, Error>;
+}
+
+/// The value of a parameter set by an instance, folded in the scope of the
+/// module that holds the instance. `None` if it is not constant.
+fn constant(expr: &Expr, env: &Env) -> Option<Expr> {
+    match expr.simplify(env) {
+        Expr::Str(s) => Some(Expr::Str(s)),
+        value if value.eval(&Env::new()).is_ok() => Some(value),
+        _ => None,
+    }
+}
+
+/// A string value as part of a name, in quotes. Characters other than
+/// letters and digits are written as their code in hex between underscores,
+/// i.e. `"a-b"` is `_22_a_2d_b_22_`, so different strings give different
+/// names and none of them is a number.
+fn mangle_str(s: &str) -> String {
+    format!("\"{}\"", s)
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_string()
+            } else {
+                format!("_{:x}_", c as u32)
+            }
+        })
+        .collect()
+}
+
+/// Name of `module` specialized for the values of its parameters in `env`,
+/// i.e. `fifo__WIDTH_16__DEPTH_4`.
+fn mangle<D: Definition>(module: &D, env: &Env) -> Result<Id, Error> {
+    let mut name = module.name().clone();
+    for param in module.param_names() {
+        let not_constant = || {
+            Error::Invalid(format!(
+                "parameter `{}` of `{}` is not constant",
+                param,
+                module.name()
+            ))
+        };
+        let value = env.get(param).ok_or_else(not_constant)?;
+        let text = match value.simplify(env) {
+            Expr::Str(s) => mangle_str(&s),
+            value => match value.eval(env) {
+                Ok(v) if v.is_signed() && v.to_i128() < 0 => {
+                    format!("n{}", v.to_i128().unsigned_abs())
+                }
+                Ok(v) => v.to_u128().to_string(),
+                Err(_) => return Err(not_constant()),
+            },
+        };
+        name.push_str(&format!("{}{}_{}", SEP, param, text));
+    }
+    Ok(name)
+}
+
+/// The parameter values set by `inst` of `def`, folded in the scope `outer`
+/// of the module that holds the instance.
+fn values<D: Definition>(inst: &Instance, def: &D, outer: &Scope) -> Result<Map, Error> {
+    let names = def.param_names();
+    let mut values = Map::new();
+    for (param, expr) in inst.params.iter() {
+        if !names.contains(&param) {
+            return Err(Error::UnknownParam {
+                instance: inst.id.clone(),
+                module: def.name().clone(),
+                param: param.clone(),
+            });
+        }
+        let value = constant(expr, outer.env()).ok_or_else(|| {
+            Error::Invalid(format!(
+                "instance `{}` sets `{}` to `{}`, which is not constant",
+                inst.id, param, expr
+            ))
+        })?;
+        values.insert(param.clone(), value);
+    }
+    Ok(values)
+}
+
+/// Specialize the modules of a design below a top module.
+struct Elaborator<'a, T, U, P, H> {
+    design: &'a H,
+    // names of the modules specialized so far, or being specialized, with
+    // the names of the modules they specialize
+    started: BTreeMap<Id, Id>,
+    out: BTreeMap<Id, GenericModule<T, U, P>>,
+}
+
+impl<'a, T, U, P, H> Elaborator<'a, T, U, P, H>
+where
+    H: Hierarchy<T, U, P>,
+    U: AsInstance,
+    GenericModule<T, U, P>: Definition,
+{
+    /// Add `def` with the parameter `values` to the output and return its
+    /// name, a module with parameters gets a mangled name unless it is the
+    /// top module.
+    fn add(&mut self, def: &GenericModule<T, U, P>, values: &Map, top: bool) -> Result<Id, Error> {
+        let mut module = def.clone();
+        module.set_params(values);
+        let scope = self.design.scope(&module);
+        if !top && !module.param_names().is_empty() {
+            module.name = mangle(&module, scope.env())?;
+            if self.design.modules().contains_key(&module.name) {
+                return Err(Error::Invalid(format!(
+                    "`{}` is specialized as `{}`, which is already a module of the design",
+                    def.name, module.name
+                )));
+            }
+        }
+        match self.started.get(&module.name) {
+            Some(name) if *name == def.name => return Ok(module.name),
+            Some(name) => {
+                return Err(Error::Invalid(format!(
+                    "`{}` and `{}` are both specialized as `{}`",
+                    name, def.name, module.name
+                )))
+            }
+            None => {
+                self.started.insert(module.name.clone(), def.name.clone());
+            }
+        }
+        self.specialize(&mut module.body, &scope)?;
+        let name = module.name.clone();
+        self.out.insert(name.clone(), module);
+        Ok(name)
+    }
+
+    /// Point the instances in `body` to the specialized modules, `scope` is
+    /// the scope of the module that holds them.
+    fn specialize(&mut self, body: &mut [GenericStmt<T, U>], scope: &Scope) -> Result<(), Error> {
+        let design = self.design;
+        for stmt in body {
+            match stmt {
+                GenericStmt::Parallel(par) => {
+                    let inst = match par.as_instance_mut() {
+                        Some(inst) => inst,
+                        None => continue,
+                    };
+                    if let Some(def) = design.modules().get(&inst.prim) {
+                        let values = values(inst, def, scope)?;
+                        inst.prim = self.add(def, &values, false)?;
+                        inst.params = Map::new();
+                    }
+                }
+                GenericStmt::GenFor(gen) => self.specialize(&mut gen.block.body, scope)?,
+                GenericStmt::GenIf(gen) => {
+                    self.specialize(&mut gen.block.body, scope)?;
+                    if let Some(block) = &mut gen.else_branch {
+                        self.specialize(&mut block.body, scope)?;
+                    }
+                }
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Specialize the modules below `top` of `design`, with the parameters of
+/// `top` set to `params`. Returns `top` and the specialized modules.
+pub fn elaborate<T, U, P, H>(
+    design: &H,
+    top: &str,
+    params: &Map,
+) -> Result<BTreeMap<Id, GenericModule<T, U, P>>, Error>
+where
+    H: Hierarchy<T, U, P>,
+    U: AsInstance,
+    GenericModule<T, U, P>: Definition,
+{
+    let def = design
+        .modules()
+        .get(top)
+        .ok_or_else(|| Error::Invalid(format!("`{}` is not a module of the design", top)))?;
+    let mut elab = Elaborator {
+        design,
+        started: BTreeMap::new(),
+        out: BTreeMap::new(),
+    };
+    let mut inst = Instance::new(top, top);
+    inst.params = params.clone();
+    let values = values(&inst, def, &Scope::new())?;
+    elab.add(def, &values, true)?;
+    Ok(elab.out)
+}
+
+/// The connections of `inst` of `def` by port name. Errors of the instance
+/// are returned, but an input left unconnected is not driven once it is
+/// inlined.
+pub fn port_connections<D: Definition>(inst: &Instance, def: &D) -> Result<Map, Error> {
+    let mut errors = check_instance(inst, def).into_iter();
+    if let Some(err) = errors.find(|err| !matches!(err, Error::Unconnected { .. })) {
+        return Err(err);
+    }
+    Ok(connections(inst, Some(def))
+        .into_iter()
+        .filter_map(|(port, expr)| Some((port?.clone(), expr.clone())))
+        .collect())
+}
+
+/// The `names` declared by a module mapped to the names they get once the
+/// module is inlined as `inst`, i.e. `u0__x`.
+pub fn prefixed(inst: &Instance, names: BTreeSet<Id>) -> HashMap<Id, Id> {
+    names
+        .into_iter()
+        .map(|name| {
+            let prefixed = format!("{}{}{}", inst.id, SEP, name);
+            (name, prefixed)
+        })
+        .collect()
+}
+
+/// The parameter values set by `inst`, keyed by the names the parameters
+/// get once the module is inlined, see `prefixed`.
+pub fn inlined_params(inst: &Instance, renames: &HashMap<Id, Id>) -> Map {
+    inst.params
+        .iter()
+        .filter_map(|(param, expr)| Some((renames.get(param)?.clone(), expr.clone())))
+        .collect()
+}
+
+/// `def` with every instance of a module of `design` inlined, `stack` holds
+/// the modules being flattened.
+fn flat_module<T, U, P, H>(
+    design: &H,
+    def: &GenericModule<T, U, P>,
+    stack: &mut Vec<Id>,
+) -> Result<GenericModule<T, U, P>, Error>
+where
+    H: Hierarchy<T, U, P>,
+    U: AsInstance,
+    GenericModule<T, U, P>: Definition,
+{
+    if stack.contains(&def.name) {
+        return Err(Error::Invalid(format!(
+            "`{}` instantiates itself",
+            def.name
+        )));
+    }
+    stack.push(def.name.clone());
+    let mut module = def.clone();
+    inline_stmts(design, &mut module.body, None, stack)?;
+    stack.pop();
+    Ok(module)
+}
+
+/// Inline the instances in `body` of modules of `design`, or only the
+/// instance named `only`. Returns the number of instances inlined.
+fn inline_stmts<T, U, P, H>(
+    design: &H,
+    body: &mut Vec<GenericStmt<T, U>>,
+    only: Option<&str>,
+    stack: &mut Vec<Id>,
+) -> Result<usize, Error>
+where
+    H: Hierarchy<T, U, P>,
+    U: AsInstance,
+    GenericModule<T, U, P>: Definition,
+{
+    let mut count = 0;
+    let mut stmts = Vec::with_capacity(body.len());
+    for mut stmt in body.drain(..) {
+        match &mut stmt {
+            GenericStmt::Parallel(par) => {
+                let def = par
+                    .as_instance_mut()
+                    .filter(|inst| only.is_none_or(|id| id == inst.id))
+                    .and_then(|inst| Some((design.modules().get(&inst.prim)?, inst)));
+                if let Some((def, inst)) = def {
+                    let def = flat_module(design, def, stack)?;
+                    stmts.extend(design.inline(inst, &def)?);
+                    count += 1;
+                    continue;
+                }
+            }
+            GenericStmt::GenFor(gen) => {
+                count += inline_stmts(design, &mut gen.block.body, only, stack)?
+            }
+            GenericStmt::GenIf(gen) => {
+                count += inline_stmts(design, &mut gen.block.body, only, stack)?;
+                if let Some(block) = &mut gen.else_branch {
+                    count += inline_stmts(design, &mut block.body, only, stack)?;
+                }
+            }
+            _ => (),
+        }
+        stmts.push(stmt);
+    }
+    *body = stmts;
+    Ok(count)
+}
+
+/// `module` of `design` with its instance `inst` inlined, together with the
+/// hierarchy below it.
+pub fn flatten<T, U, P, H>(
+    design: &H,
+    module: &str,
+    inst: &str,
+) -> Result<GenericModule<T, U, P>, Error>
+where
+    H: Hierarchy<T, U, P>,
+    U: AsInstance,
+    GenericModule<T, U, P>: Definition,
+{
+    let def = design
+        .modules()
+        .get(module)
+        .ok_or_else(|| Error::Invalid(format!("`{}` is not a module of the design", module)))?;
+    let mut flat = def.clone();
+    let mut stack = vec![flat.name.clone()];
+    if inline_stmts(design, &mut flat.body, Some(inst), &mut stack)? == 0 {
+        return Err(Error::Invalid(format!(
+            "`{}` has no instance `{}` of a module of the design",
+            module, inst
+        )));
+    }
+    Ok(flat)
+}
+
+/// `module` of `design` with every instance of a module of `design` inlined.
+pub fn flatten_all<T, U, P, H>(design: &H, module: &str) -> Result<GenericModule<T, U, P>, Error>
+where
+    H: Hierarchy<T, U, P>,
+    U: AsInstance,
+    GenericModule<T, U, P>: Definition,
+{
+    let def = design
+        .modules()
+        .get(module)
+        .ok_or_else(|| Error::Invalid(format!("`{}` is not a module of the design", module)))?;
+    flat_module(design, def, &mut Vec::new())
+}
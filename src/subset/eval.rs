@@ -0,0 +1,570 @@
+use crate::subset::ast::*;
+use crate::subset::bitvec::BitVec;
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::rc::Rc;
+
+/// Maximum width in bits of an evaluated value.
+pub const MAX_WIDTH: u32 = 128;
+
+/// Values for the references used in an expression, i.e. module parameters.
+pub type Env = HashMap<Id, Expr>;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EvalError {
+    /// A reference without a value in the environment.
+    Unbound(Id),
+    /// A reference whose value depends on itself.
+    Cycle(Id),
+    /// A literal with `x` or `z` digits.
+    Unknown(Expr),
+    /// A value wider than `MAX_WIDTH` bits.
+    TooWide(u64),
+    /// An expression that can not be evaluated to a constant.
+    Unsupported(Expr),
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvalError::Unbound(id) => write!(f, "no value for `{}`", id),
+            EvalError::Cycle(id) => write!(f, "value of `{}` depends on itself", id),
+            EvalError::Unknown(expr) => write!(f, "`{}` has unknown bits", expr),
+            EvalError::TooWide(width) => {
+                write!(f, "width {} exceeds the maximum of {}", width, MAX_WIDTH)
+            }
+            EvalError::Unsupported(expr) => write!(f, "`{}` is not a constant expression", expr),
+        }
+    }
+}
+
+impl error::Error for EvalError {}
+
+/// A two-state constant with a Verilog width and signedness.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Value {
+    width: u32,
+    bits: u128,
+    signed: bool,
+}
+
+fn mask(width: u32) -> u128 {
+    if width >= 128 {
+        u128::MAX
+    } else {
+        (1u128 << width) - 1
+    }
+}
+
+impl Value {
+    /// Create an unsigned value, bits that do not fit in `width` are dropped.
+    pub fn new(width: u32, bits: u128) -> Value {
+        assert!(
+            width > 0 && width <= MAX_WIDTH,
+            "Error: width must be between 1 and 128"
+        );
+        Value {
+            width,
+            bits: bits & mask(width),
+            signed: false,
+        }
+    }
+
+    /// Create a signed value, bits that do not fit in `width` are dropped.
+    pub fn new_signed(width: u32, bits: u128) -> Value {
+        Value {
+            signed: true,
+            ..Value::new(width, bits)
+        }
+    }
+
+    fn from_bool(b: bool) -> Value {
+        Value::new(1, b as u128)
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn is_signed(&self) -> bool {
+        self.signed
+    }
+
+    /// The raw bits of the value.
+    pub fn to_u128(&self) -> u128 {
+        self.bits
+    }
+
+    /// The value interpreted according to its signedness.
+    pub fn to_i128(&self) -> i128 {
+        if self.signed && self.width < 128 && self.bits >> (self.width - 1) & 1 == 1 {
+            (self.bits | !mask(self.width)) as i128
+        } else {
+            self.bits as i128
+        }
+    }
+
+    pub fn is_true(&self) -> bool {
+        self.bits != 0
+    }
+
+    /// Extend or truncate to `width`, signed values are sign-extended.
+    pub fn resize(&self, width: u32) -> Value {
+        Value {
+            width,
+            bits: self.to_i128() as u128 & mask(width),
+            signed: self.signed,
+        }
+    }
+}
+
+/// Resize both operands to a common width. The operands of an expression
+/// share their signedness, see `Context`, so neither is sign-extended
+/// unless both are signed.
+fn unify(lhs: Value, rhs: Value) -> (Value, Value) {
+    let width = lhs.width.max(rhs.width);
+    (lhs.resize(width), rhs.resize(width))
+}
+
+fn arith(lhs: Value, rhs: Value, op: fn(u128, u128) -> u128) -> Value {
+    let (lhs, rhs) = unify(lhs, rhs);
+    Value {
+        bits: op(lhs.bits, rhs.bits) & mask(lhs.width),
+        ..lhs
+    }
+}
+
+fn compare(lhs: Value, rhs: Value, op: fn(i128, i128) -> bool) -> Value {
+    let (lhs, rhs) = unify(lhs, rhs);
+    if lhs.signed {
+        Value::from_bool(op(lhs.to_i128(), rhs.to_i128()))
+    } else {
+        // compare in the unsigned domain
+        let flip = |v: u128| (v ^ (1 << 127)) as i128;
+        Value::from_bool(op(flip(lhs.bits), flip(rhs.bits)))
+    }
+}
+
+/// Division and modulo, `None` when dividing by zero.
+fn divide(lhs: Value, rhs: Value, modulo: bool) -> Option<Value> {
+    let (lhs, rhs) = unify(lhs, rhs);
+    if rhs.bits == 0 {
+        return None;
+    }
+    let bits = match (lhs.signed, modulo) {
+        (true, false) => lhs.to_i128().wrapping_div(rhs.to_i128()) as u128,
+        (true, true) => lhs.to_i128().wrapping_rem(rhs.to_i128()) as u128,
+        (false, false) => lhs.bits / rhs.bits,
+        (false, true) => lhs.bits % rhs.bits,
+    };
+    Some(Value {
+        bits: bits & mask(lhs.width),
+        ..lhs
+    })
+}
+
+/// Power with the width and signedness of the base, `None` for zero to a
+/// negative power.
+fn power(lhs: Value, rhs: Value) -> Option<Value> {
+    let signed = lhs.signed;
+    let exp = rhs.to_i128();
+    let bits = if exp >= 0 {
+        lhs.bits.wrapping_pow(exp.min(u32::MAX as i128) as u32)
+    } else {
+        match lhs.to_i128() {
+            0 => return None,
+            1 => 1,
+            -1 if signed => (if exp % 2 == 0 { 1i128 } else { -1 }) as u128,
+            _ => 0,
+        }
+    };
+    Some(Value {
+        bits: bits & mask(lhs.width),
+        signed,
+        ..lhs
+    })
+}
+
+/// Shift with the width of the left operand, arithmetic right shifts of
+/// signed values fill with the sign bit.
+fn shift(lhs: Value, rhs: Value, left: bool, arith: bool) -> Value {
+    let amount = rhs.bits.min(127) as u32;
+    let bits = if left {
+        lhs.bits.checked_shl(rhs.bits.min(128) as u32).unwrap_or(0)
+    } else if arith && lhs.signed {
+        (lhs.to_i128() >> amount) as u128
+    } else {
+        lhs.bits.checked_shr(rhs.bits.min(128) as u32).unwrap_or(0)
+    };
+    Value {
+        bits: bits & mask(lhs.width),
+        ..lhs
+    }
+}
+
+fn concat(values: &[Value]) -> Result<Value, EvalError> {
+    let width: u64 = values.iter().map(|v| v.width as u64).sum();
+    if width > MAX_WIDTH as u64 {
+        return Err(EvalError::TooWide(width));
+    }
+    // the first value holds the least significant bits
+    let bits = values.iter().rev().fold(0u128, |acc, v| {
+        acc.checked_shl(v.width).unwrap_or(0) | v.bits
+    });
+    Ok(Value::new(width as u32, bits))
+}
+
+fn ulit(expr: &Expr, value: &BitVec) -> Result<Value, EvalError> {
+    if value.width() > MAX_WIDTH {
+        return Err(EvalError::TooWide(value.width() as u64));
+    }
+    match value.to_u128() {
+        Some(bits) => Ok(Value::new(value.width(), bits)),
+        None => Err(EvalError::Unknown(expr.clone())),
+    }
+}
+
+fn clog2(value: Value) -> Value {
+    let n = value.bits;
+    let log = if n <= 1 {
+        0
+    } else {
+        128 - (n - 1).leading_zeros()
+    };
+    Value::new_signed(32, log as u128)
+}
+
+/// The width and signedness an operand is evaluated with. An expression is
+/// signed only if all of its context-determined operands are signed, and
+/// the operands are extended to the width of the context only once that is
+/// known, i.e. `$signed(4'hf) + 8'd0` is 15.
+#[derive(Clone, Copy, Debug)]
+struct Context {
+    width: u32,
+    signed: bool,
+}
+
+impl Context {
+    /// A self-determined operand, with its own width and signedness.
+    const SELF: Context = Context {
+        width: 0,
+        signed: true,
+    };
+}
+
+impl Expr {
+    /// Evaluate a constant expression using the self-determined width of
+    /// every operand, references are resolved using `env`.
+    pub fn eval(&self, env: &Env) -> Result<Value, EvalError> {
+        self.eval_with(env, &mut Vec::new(), Context::SELF)
+    }
+
+    /// Evaluate a constant expression in a context of at least `width` bits,
+    /// i.e. the right hand side of an assignment to a `width` bit variable.
+    pub fn eval_at(&self, env: &Env, width: u32) -> Result<Value, EvalError> {
+        let context = Context {
+            width: width.min(MAX_WIDTH),
+            signed: true,
+        };
+        self.eval_with(env, &mut Vec::new(), context)
+    }
+
+    /// Whether the value of the expression is signed, operands that are
+    /// context-determined must all be signed.
+    fn is_signed(&self, env: &Env, stack: &mut Vec<Id>) -> bool {
+        match self {
+            Expr::Int(_) | Expr::Signed(_) => true,
+            Expr::Ref(id) => match env.get(id) {
+                Some(expr) if !stack.contains(id) => {
+                    stack.push(id.clone());
+                    let signed = expr.is_signed(env, stack);
+                    stack.pop();
+                    signed
+                }
+                _ => false,
+            },
+            Expr::Unop(Unop::Not | Unop::Neg, e) => e.is_signed(env, stack),
+            Expr::Binop(op, lhs, rhs) => match op {
+                Binop::IndexBit
+                | Binop::LogOr
+                | Binop::LogAnd
+                | Binop::Gt
+                | Binop::Lt
+                | Binop::Geq
+                | Binop::Leq
+                | Binop::Equal
+                | Binop::CaseEqual
+                | Binop::NotEqual
+                | Binop::CaseNotEqual => false,
+                Binop::ShiftLeft
+                | Binop::ArithShiftLeft
+                | Binop::ShiftRight
+                | Binop::ArithShiftRight
+                | Binop::Pow => lhs.is_signed(env, stack),
+                _ => lhs.is_signed(env, stack) && rhs.is_signed(env, stack),
+            },
+            Expr::Terop(Terop::Mux, _, tru, fal) => {
+                tru.is_signed(env, stack) && fal.is_signed(env, stack)
+            }
+            Expr::Call(name, args) => name == "$clog2" && args.len() == 1,
+            _ => false,
+        }
+    }
+
+    /// Evaluate in `context`: the value is signed only if the context and
+    /// the expression are, and it is then extended to the context width.
+    fn eval_with(
+        &self,
+        env: &Env,
+        stack: &mut Vec<Id>,
+        context: Context,
+    ) -> Result<Value, EvalError> {
+        let context = Context {
+            signed: context.signed && self.is_signed(env, stack),
+            ..context
+        };
+        let mut value = self.eval_node(env, stack, context)?;
+        value.signed = value.signed && context.signed;
+        if value.width < context.width {
+            Ok(value.resize(context.width))
+        } else {
+            Ok(value)
+        }
+    }
+
+    fn eval_node(
+        &self,
+        env: &Env,
+        stack: &mut Vec<Id>,
+        context: Context,
+    ) -> Result<Value, EvalError> {
+        let own = Context::SELF;
+        let unsupported = || EvalError::Unsupported(self.clone());
+        match self {
+            Expr::Int(n) => Ok(Value::new_signed(32, *n as u32 as u128)),
+            Expr::ULit(value, _) => ulit(self, value),
+            Expr::X => Err(EvalError::Unknown(self.clone())),
+            Expr::Ref(id) => {
+                if stack.contains(id) {
+                    return Err(EvalError::Cycle(id.clone()));
+                }
+                let expr = env.get(id).ok_or_else(|| EvalError::Unbound(id.clone()))?;
+                stack.push(id.clone());
+                let value = expr.eval_with(env, stack, own);
+                stack.pop();
+                value
+            }
+            Expr::Signed(e) => {
+                let value = e.eval_with(env, stack, own)?;
+                Ok(Value::new_signed(value.width, value.bits))
+            }
+            Expr::Unop(op @ (Unop::Not | Unop::Neg), e) => {
+                let v = e.eval_with(env, stack, context)?;
+                let ones = mask(v.width);
+                let bits = match op {
+                    Unop::Not => !v.bits,
+                    _ => v.bits.wrapping_neg(),
+                };
+                Ok(Value {
+                    bits: bits & ones,
+                    ..v
+                })
+            }
+            Expr::Unop(op, e) => {
+                let v = e.eval_with(env, stack, own)?;
+                let ones = mask(v.width);
+                let value = match op {
+                    Unop::LogNot => Value::from_bool(!v.is_true()),
+                    Unop::And => Value::from_bool(v.bits == ones),
+                    Unop::Nand => Value::from_bool(v.bits != ones),
+                    Unop::Or => Value::from_bool(v.bits != 0),
+                    Unop::Nor => Value::from_bool(v.bits == 0),
+                    Unop::Xor => Value::from_bool(v.bits.count_ones() % 2 == 1),
+                    Unop::Xnor => Value::from_bool(v.bits.count_ones() % 2 == 0),
+                    Unop::Not | Unop::Neg => unreachable!(),
+                };
+                Ok(value)
+            }
+            Expr::Binop(Binop::IndexBit, e, index) => {
+                let v = e.eval_with(env, stack, own)?;
+                let index = index.eval_with(env, stack, own)?.to_i128();
+                if index < 0 || index >= v.width as i128 {
+                    Err(EvalError::Unknown(self.clone()))
+                } else {
+                    Ok(Value::new(1, v.bits >> index))
+                }
+            }
+            Expr::Binop(op, lhs, rhs) => {
+                let (l, r) = match op {
+                    Binop::LogOr | Binop::LogAnd => (
+                        lhs.eval_with(env, stack, own)?,
+                        rhs.eval_with(env, stack, own)?,
+                    ),
+                    // the operands of a comparison share their widest width
+                    Binop::Gt
+                    | Binop::Lt
+                    | Binop::Geq
+                    | Binop::Leq
+                    | Binop::Equal
+                    | Binop::CaseEqual
+                    | Binop::NotEqual
+                    | Binop::CaseNotEqual => {
+                        let l = lhs.eval_with(env, stack, own)?;
+                        let r = rhs.eval_with(env, stack, own)?;
+                        let context = Context {
+                            width: l.width.max(r.width),
+                            signed: l.signed && r.signed,
+                        };
+                        (
+                            lhs.eval_with(env, stack, context)?,
+                            rhs.eval_with(env, stack, context)?,
+                        )
+                    }
+                    // the shift amount and the exponent are self-determined
+                    Binop::ShiftLeft
+                    | Binop::ArithShiftLeft
+                    | Binop::ShiftRight
+                    | Binop::ArithShiftRight
+                    | Binop::Pow => (
+                        lhs.eval_with(env, stack, context)?,
+                        rhs.eval_with(env, stack, own)?,
+                    ),
+                    _ => (
+                        lhs.eval_with(env, stack, context)?,
+                        rhs.eval_with(env, stack, context)?,
+                    ),
+                };
+                let value = match op {
+                    Binop::LogOr => Value::from_bool(l.is_true() || r.is_true()),
+                    Binop::LogAnd => Value::from_bool(l.is_true() && r.is_true()),
+                    Binop::Add => arith(l, r, u128::wrapping_add),
+                    Binop::Sub => arith(l, r, u128::wrapping_sub),
+                    Binop::Mul => arith(l, r, u128::wrapping_mul),
+                    Binop::Div | Binop::Mod => divide(l, r, *op == Binop::Mod)
+                        .ok_or_else(|| EvalError::Unknown(self.clone()))?,
+                    Binop::Pow => power(l, r).ok_or_else(|| EvalError::Unknown(self.clone()))?,
+                    Binop::BitAnd => arith(l, r, |a, b| a & b),
+                    Binop::BitOr => arith(l, r, |a, b| a | b),
+                    Binop::BitXor => arith(l, r, |a, b| a ^ b),
+                    Binop::BitXnor => arith(l, r, |a, b| !(a ^ b)),
+                    Binop::Gt => compare(l, r, |a, b| a > b),
+                    Binop::Lt => compare(l, r, |a, b| a < b),
+                    Binop::Geq => compare(l, r, |a, b| a >= b),
+                    Binop::Leq => compare(l, r, |a, b| a <= b),
+                    // values are two-state, case equality is plain equality
+                    Binop::Equal | Binop::CaseEqual => compare(l, r, |a, b| a == b),
+                    Binop::NotEqual | Binop::CaseNotEqual => compare(l, r, |a, b| a != b),
+                    Binop::ShiftLeft | Binop::ArithShiftLeft => shift(l, r, true, false),
+                    Binop::ShiftRight => shift(l, r, false, false),
+                    Binop::ArithShiftRight => shift(l, r, false, true),
+                    Binop::IndexBit => unreachable!(),
+                };
+                Ok(value)
+            }
+            Expr::Terop(Terop::Mux, cond, tru, fal) => {
+                let (t, f) = unify(
+                    tru.eval_with(env, stack, context)?,
+                    fal.eval_with(env, stack, context)?,
+                );
+                if cond.eval_with(env, stack, own)?.is_true() {
+                    Ok(t)
+                } else {
+                    Ok(f)
+                }
+            }
+            Expr::Terop(Terop::Slice, e, hi, lo) => {
+                let v = e.eval_with(env, stack, own)?;
+                let hi = hi.eval_with(env, stack, own)?.to_i128();
+                let lo = lo.eval_with(env, stack, own)?.to_i128();
+                if lo < 0 || hi < lo || hi >= v.width as i128 {
+                    Err(EvalError::Unknown(self.clone()))
+                } else {
+                    Ok(Value::new((hi - lo + 1) as u32, v.bits >> lo))
+                }
+            }
+            Expr::Terop(Terop::IndexSlice, e, lo, width) => {
+                let v = e.eval_with(env, stack, own)?;
+                let lo = lo.eval_with(env, stack, own)?.to_i128();
+                let width = width.eval_with(env, stack, own)?.to_i128();
+                if lo < 0 || width < 1 || lo + width > v.width as i128 {
+                    Err(EvalError::Unknown(self.clone()))
+                } else {
+                    Ok(Value::new(width as u32, v.bits >> lo))
+                }
+            }
+            Expr::Concat(c) => {
+                let values = c
+                    .exprs()
+                    .iter()
+                    .map(|e| e.eval_with(env, stack, own))
+                    .collect::<Result<Vec<_>, _>>()?;
+                concat(&values)
+            }
+            Expr::Repeat(times, e) => {
+                let v = e.eval_with(env, stack, own)?;
+                let width = *times * v.width as u64;
+                if *times == 0 || width > MAX_WIDTH as u64 {
+                    return Err(EvalError::TooWide(width));
+                }
+                concat(&vec![v; *times as usize])
+            }
+            Expr::Call(name, args) if name == "$clog2" && args.len() == 1 => {
+                Ok(clog2(args[0].eval_with(env, stack, own)?))
+            }
+            Expr::Str(_) | Expr::IPath(_, _) | Expr::Call(_, _) => Err(unsupported()),
+        }
+    }
+
+    /// Replace every constant subexpression with its value. A subexpression
+    /// is only folded when a wider context can not change its value, e.g.
+    /// `4'hf + 4'h1` is kept since it is 16 when assigned to 8 bits.
+    /// Literals are kept as they are written.
+    pub fn simplify(&self, env: &Env) -> Expr {
+        match self {
+            Expr::X | Expr::Int(_) | Expr::ULit(_, _) | Expr::Str(_) => return self.clone(),
+            Expr::Signed(e) if matches!(**e, Expr::ULit(_, _)) => return self.clone(),
+            _ => (),
+        }
+        if let Ok(value) = self.eval(env) {
+            let widened = self.eval_at(env, MAX_WIDTH);
+            if widened == Ok(value.resize(MAX_WIDTH)) {
+                return Expr::from(value);
+            }
+        }
+        let fold = |e: &Rc<Expr>| Rc::new(e.simplify(env));
+        match self {
+            Expr::Signed(e) => Expr::Signed(fold(e)),
+            Expr::IPath(path, index) => Expr::IPath(path.clone(), index.as_ref().map(fold)),
+            Expr::Unop(op, e) => Expr::Unop(op.clone(), fold(e)),
+            // keep the variable of a selection
+            Expr::Binop(Binop::IndexBit, e, index) => {
+                Expr::Binop(Binop::IndexBit, e.clone(), fold(index))
+            }
+            Expr::Binop(op, lhs, rhs) => Expr::Binop(op.clone(), fold(lhs), fold(rhs)),
+            // pruning a branch must not change the width or sign of the mux
+            Expr::Terop(Terop::Mux, cond, tru, fal) => {
+                match (cond.eval(env), tru.eval(env), fal.eval(env)) {
+                    (Ok(value), Ok(t), Ok(f)) if t.width == f.width && t.signed == f.signed => {
+                        if value.is_true() {
+                            tru.simplify(env)
+                        } else {
+                            fal.simplify(env)
+                        }
+                    }
+                    _ => Expr::Terop(Terop::Mux, fold(cond), fold(tru), fold(fal)),
+                }
+            }
+            Expr::Terop(op, e, a, b) => Expr::Terop(op.clone(), e.clone(), fold(a), fold(b)),
+            Expr::Concat(c) => Expr::Concat(ExprConcat {
+                exprs: c.exprs().iter().map(|e| e.simplify(env)).collect(),
+            }),
+            Expr::Repeat(times, e) => Expr::Repeat(*times, fold(e)),
+            Expr::Call(name, args) => {
+                Expr::Call(name.clone(), args.iter().map(|e| e.simplify(env)).collect())
+            }
+            Expr::Ref(_) => self.clone(),
+            Expr::X | Expr::Int(_) | Expr::ULit(_, _) | Expr::Str(_) => unreachable!(),
+        }
+    }
+}
@@ -1,4 +1,8 @@
 use crate::subset::ast::*;
+use crate::subset::bitvec::BitVec;
+use crate::subset::eval::Value;
+use crate::subset::parser::ParseError;
+use std::str::FromStr;
 
 impl From<ExprConcat> for Expr {
     fn from(concat: ExprConcat) -> Self {
@@ -23,3 +27,26 @@ impl From<i32> for Expr {
         Expr::new_int(i)
     }
 }
+
+/// Signed 32-bit values become integers, everything else a sized decimal.
+impl From<Value> for Expr {
+    fn from(value: Value) -> Self {
+        let bits = BitVec::from_u128(value.width(), value.to_u128()).unwrap();
+        let ulit = Expr::new_ulit(bits, Radix::Dec);
+        if !value.is_signed() {
+            ulit
+        } else if value.width() == 32 {
+            Expr::new_int(value.to_i128() as i32)
+        } else {
+            Expr::new_signed(ulit)
+        }
+    }
+}
+
+impl FromStr for Expr {
+    type Err = ParseError;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        Expr::parse(src)
+    }
+}
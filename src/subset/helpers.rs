@@ -1,6 +1,36 @@
 use crate::subset::ast::*;
+use crate::subset::bitvec::BitVec;
+use crate::subset::parser::{ParseError, Parser};
+use crate::Error;
+use std::iter::FromIterator;
+use std::ops::Index;
 use std::rc::Rc;
 
+impl Binop {
+    /// Binding strength of the operator as given by IEEE 1800-2017 table
+    /// 11-2, higher binds tighter. All binary operators are left
+    /// associative and bit-selects bind like primaries.
+    pub fn precedence(&self) -> u8 {
+        match self {
+            Binop::LogOr => 1,
+            Binop::LogAnd => 2,
+            Binop::BitOr => 3,
+            Binop::BitXor | Binop::BitXnor => 4,
+            Binop::BitAnd => 5,
+            Binop::Equal | Binop::NotEqual | Binop::CaseEqual | Binop::CaseNotEqual => 6,
+            Binop::Lt | Binop::Gt | Binop::Leq | Binop::Geq => 7,
+            Binop::ShiftLeft
+            | Binop::ShiftRight
+            | Binop::ArithShiftLeft
+            | Binop::ArithShiftRight => 8,
+            Binop::Add | Binop::Sub => 9,
+            Binop::Mul | Binop::Div | Binop::Mod => 10,
+            Binop::Pow => 11,
+            Binop::IndexBit => 13,
+        }
+    }
+}
+
 impl InstancePath {
     pub fn new(path: &str) -> InstancePath {
         let p: Vec<String> = path.split('.').map(|x| x.to_string()).collect();
@@ -30,6 +60,17 @@ impl ExprConcat {
 }
 
 impl Expr {
+    /// Parse an expression, the whole input must be a single expression.
+    pub fn parse(src: &str) -> Result<Expr, ParseError> {
+        let mut parser = Parser::new(src)?;
+        let expr = parser.parse_expr()?;
+        if parser.is_eof() {
+            Ok(expr)
+        } else {
+            Err(parser.unexpected("end of expression"))
+        }
+    }
+
     pub fn id(&self) -> String {
         match self {
             Expr::Ref(id) => id.to_string(),
@@ -63,19 +104,45 @@ impl Expr {
         Expr::Str(value.to_string())
     }
 
+    pub fn new_ulit(value: BitVec, radix: Radix) -> Expr {
+        Expr::ULit(value, radix)
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `value` is not a valid `width` bit literal in `radix`, see
+    /// `try_new_ulit_str`.
+    pub fn new_ulit_str(width: u32, value: &str, radix: Radix) -> Expr {
+        match Expr::try_new_ulit_str(width, value, radix) {
+            Ok(expr) => expr,
+            Err(err) => panic!("Error: {}", err),
+        }
+    }
+
+    pub fn try_new_ulit_str(width: u32, value: &str, radix: Radix) -> Result<Expr, Error> {
+        let value = BitVec::from_str_radix(width, value, radix)?;
+        Ok(Expr::ULit(value, radix))
+    }
+
+    /// # Panics
+    ///
+    /// Panics like `new_ulit_str`.
     pub fn new_ulit_dec(width: u32, value: &str) -> Expr {
-        assert!(width > 0, "Error: width must be greater than zero");
-        Expr::ULit(width, Radix::Dec, value.to_string())
+        Expr::new_ulit_str(width, value, Radix::Dec)
     }
 
+    /// # Panics
+    ///
+    /// Panics like `new_ulit_str`.
     pub fn new_ulit_hex(width: u32, value: &str) -> Expr {
-        assert!(width > 0, "Error: width must be greater than zero");
-        Expr::ULit(width, Radix::Hex, value.to_string())
+        Expr::new_ulit_str(width, value, Radix::Hex)
     }
 
+    /// # Panics
+    ///
+    /// Panics like `new_ulit_str`.
     pub fn new_ulit_bin(width: u32, value: &str) -> Expr {
-        assert!(width > 0, "Error: width must be greater than zero");
-        Expr::ULit(width, Radix::Bin, value.to_string())
+        Expr::new_ulit_str(width, value, Radix::Bin)
     }
 
     pub fn new_bit_or<L, R>(lhs: L, rhs: R) -> Expr
@@ -190,6 +257,98 @@ impl Expr {
         Expr::Binop(Binop::Mul, Rc::new(lhs.into()), Rc::new(rhs.into()))
     }
 
+    pub fn new_div<L, R>(lhs: L, rhs: R) -> Expr
+    where
+        L: Into<Expr>,
+        R: Into<Expr>,
+    {
+        Expr::Binop(Binop::Div, Rc::new(lhs.into()), Rc::new(rhs.into()))
+    }
+
+    pub fn new_mod<L, R>(lhs: L, rhs: R) -> Expr
+    where
+        L: Into<Expr>,
+        R: Into<Expr>,
+    {
+        Expr::Binop(Binop::Mod, Rc::new(lhs.into()), Rc::new(rhs.into()))
+    }
+
+    pub fn new_pow<L, R>(lhs: L, rhs: R) -> Expr
+    where
+        L: Into<Expr>,
+        R: Into<Expr>,
+    {
+        Expr::Binop(Binop::Pow, Rc::new(lhs.into()), Rc::new(rhs.into()))
+    }
+
+    pub fn new_case_eq<L, R>(lhs: L, rhs: R) -> Expr
+    where
+        L: Into<Expr>,
+        R: Into<Expr>,
+    {
+        Expr::Binop(Binop::CaseEqual, Rc::new(lhs.into()), Rc::new(rhs.into()))
+    }
+
+    pub fn new_case_neq<L, R>(lhs: L, rhs: R) -> Expr
+    where
+        L: Into<Expr>,
+        R: Into<Expr>,
+    {
+        Expr::Binop(
+            Binop::CaseNotEqual,
+            Rc::new(lhs.into()),
+            Rc::new(rhs.into()),
+        )
+    }
+
+    pub fn new_bit_xor<L, R>(lhs: L, rhs: R) -> Expr
+    where
+        L: Into<Expr>,
+        R: Into<Expr>,
+    {
+        Expr::Binop(Binop::BitXor, Rc::new(lhs.into()), Rc::new(rhs.into()))
+    }
+
+    pub fn new_bit_xnor<L, R>(lhs: L, rhs: R) -> Expr
+    where
+        L: Into<Expr>,
+        R: Into<Expr>,
+    {
+        Expr::Binop(Binop::BitXnor, Rc::new(lhs.into()), Rc::new(rhs.into()))
+    }
+
+    pub fn new_shift_right<L, R>(lhs: L, rhs: R) -> Expr
+    where
+        L: Into<Expr>,
+        R: Into<Expr>,
+    {
+        Expr::Binop(Binop::ShiftRight, Rc::new(lhs.into()), Rc::new(rhs.into()))
+    }
+
+    pub fn new_arith_shift_left<L, R>(lhs: L, rhs: R) -> Expr
+    where
+        L: Into<Expr>,
+        R: Into<Expr>,
+    {
+        Expr::Binop(
+            Binop::ArithShiftLeft,
+            Rc::new(lhs.into()),
+            Rc::new(rhs.into()),
+        )
+    }
+
+    pub fn new_arith_shift_right<L, R>(lhs: L, rhs: R) -> Expr
+    where
+        L: Into<Expr>,
+        R: Into<Expr>,
+    {
+        Expr::Binop(
+            Binop::ArithShiftRight,
+            Rc::new(lhs.into()),
+            Rc::new(rhs.into()),
+        )
+    }
+
     pub fn new_mux<C, T, F>(cond: C, tru: T, fal: F) -> Expr
     where
         C: Into<Expr>,
@@ -211,6 +370,13 @@ impl Expr {
         Expr::Unop(Unop::Not, Rc::new(exp.into()))
     }
 
+    pub fn new_neg<E>(exp: E) -> Expr
+    where
+        E: Into<Expr>,
+    {
+        Expr::Unop(Unop::Neg, Rc::new(exp.into()))
+    }
+
     pub fn new_slice<H, L>(var: &str, hi: H, lo: L) -> Expr
     where
         H: Into<Expr>,
@@ -255,6 +421,15 @@ impl Expr {
         )
     }
 
+    /// Index into `expr`, indexing a select chains as `x[i][j]`.
+    pub fn new_index<E, I>(expr: E, index: I) -> Expr
+    where
+        E: Into<Expr>,
+        I: Into<Expr>,
+    {
+        Expr::Binop(Binop::IndexBit, Rc::new(expr.into()), Rc::new(index.into()))
+    }
+
     pub fn new_int(value: i32) -> Expr {
         Expr::Int(value)
     }
@@ -263,6 +438,12 @@ impl Expr {
         Expr::IPath(InstancePath::new(path), None)
     }
 
+    /// Access `member` of `name`, i.e. the signal `m.valid` of an interface
+    /// port `m`.
+    pub fn new_member(name: &str, member: &str) -> Expr {
+        Expr::new_ipath(&format!("{}.{}", name, member))
+    }
+
     pub fn new_ipath_with_index(path: &str, index: &str) -> Expr {
         Expr::IPath(InstancePath::new(path), Some(Rc::new(Expr::new_ref(index))))
     }
@@ -307,13 +488,237 @@ impl Attribute {
     }
 }
 
+impl Map {
+    pub fn new() -> Map {
+        Map::default()
+    }
+
+    /// The number of names, open ones included.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Expr> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k == id)
+            .and_then(|(_, v)| v.as_ref())
+    }
+
+    pub fn get_mut(&mut self, id: &str) -> Option<&mut Expr> {
+        self.entries
+            .iter_mut()
+            .find(|(k, _)| k == id)
+            .and_then(|(_, v)| v.as_mut())
+    }
+
+    pub fn contains_key(&self, id: &str) -> bool {
+        self.get(id).is_some()
+    }
+
+    /// Set `id` to `value`, a new name goes last and an existing one keeps
+    /// its place. Returns the previous value.
+    fn set(&mut self, id: Id, value: Option<Expr>) -> Option<Expr> {
+        match self.entries.iter_mut().find(|(k, _)| *k == id) {
+            Some((_, old)) => std::mem::replace(old, value),
+            None => {
+                self.entries.push((id, value));
+                None
+            }
+        }
+    }
+
+    /// Set the value of `id`, a new name goes last and an existing one keeps
+    /// its place. Returns the previous value.
+    pub fn insert(&mut self, id: Id, expr: Expr) -> Option<Expr> {
+        self.set(id, Some(expr))
+    }
+
+    /// Leave `id` open, i.e. `.id()`. Returns the previous value.
+    pub fn insert_open(&mut self, id: Id) -> Option<Expr> {
+        self.set(id, None)
+    }
+
+    pub fn is_open(&self, id: &str) -> bool {
+        self.entries.iter().any(|(k, v)| k == id && v.is_none())
+    }
+
+    /// Remove `id`, whether it has a value or is open.
+    pub fn remove(&mut self, id: &str) -> Option<Expr> {
+        let index = self.entries.iter().position(|(k, _)| k == id)?;
+        self.entries.remove(index).1
+    }
+
+    /// The names that have a value with their value, in order.
+    pub fn iter(&self) -> MapIter<'_> {
+        MapIter {
+            entries: self.entries.iter(),
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &Id> {
+        self.iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Expr> {
+        self.entries.iter().filter_map(|(_, v)| v.as_ref())
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut Expr> {
+        self.entries.iter_mut().filter_map(|(_, v)| v.as_mut())
+    }
+
+    /// The names that are left open.
+    pub fn open(&self) -> impl Iterator<Item = &Id> {
+        self.entries
+            .iter()
+            .filter(|(_, v)| v.is_none())
+            .map(|(k, _)| k)
+    }
+
+    /// Every name, with a value or open, in order.
+    pub fn names(&self) -> impl Iterator<Item = &Id> {
+        self.entries.iter().map(|(k, _)| k)
+    }
+
+    /// Order the entries as `names`, entries missing from `names` keep
+    /// their order after the others.
+    pub fn sort_by_names<S: AsRef<str>>(&mut self, names: &[S]) {
+        self.entries.sort_by_key(|(k, _)| {
+            names
+                .iter()
+                .position(|name| name.as_ref() == k)
+                .unwrap_or(names.len())
+        });
+    }
+}
+
+impl<'a> Iterator for MapIter<'a> {
+    type Item = (&'a Id, &'a Expr);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries
+            .by_ref()
+            .find_map(|(k, v)| v.as_ref().map(|v| (k, v)))
+    }
+}
+
+impl<'a> IntoIterator for &'a Map {
+    type Item = (&'a Id, &'a Expr);
+    type IntoIter = MapIter<'a>;
+
+    fn into_iter(self) -> MapIter<'a> {
+        self.iter()
+    }
+}
+
+impl FromIterator<(Id, Expr)> for Map {
+    fn from_iter<I: IntoIterator<Item = (Id, Expr)>>(iter: I) -> Map {
+        let mut map = Map::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl Extend<(Id, Expr)> for Map {
+    fn extend<I: IntoIterator<Item = (Id, Expr)>>(&mut self, iter: I) {
+        for (id, expr) in iter {
+            self.insert(id, expr);
+        }
+    }
+}
+
+impl Index<&str> for Map {
+    type Output = Expr;
+
+    /// # Panics
+    ///
+    /// Panics if `id` has no value in the map.
+    fn index(&self, id: &str) -> &Expr {
+        match self.get(id) {
+            Some(expr) => expr,
+            None => panic!("`{}` has no value", id),
+        }
+    }
+}
+
+impl Default for Connections {
+    fn default() -> Connections {
+        Connections::Named(Map::new())
+    }
+}
+
+impl Connections {
+    /// The number of connections, open ones included.
+    pub fn len(&self) -> usize {
+        match self {
+            Connections::Named(map) => map.len(),
+            Connections::Positional(exprs) => exprs.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The connections by name, `None` if the ports are connected by
+    /// position.
+    pub fn named(&self) -> Option<&Map> {
+        match self {
+            Connections::Named(map) => Some(map),
+            Connections::Positional(_) => None,
+        }
+    }
+
+    /// The connections by position, `None` if the ports are connected by
+    /// name.
+    pub fn positional(&self) -> Option<&[Expr]> {
+        match self {
+            Connections::Named(_) => None,
+            Connections::Positional(exprs) => Some(exprs),
+        }
+    }
+
+    /// The connections by name, connections by position can only be turned
+    /// into named ones while there are none.
+    fn named_mut(&mut self) -> Option<&mut Map> {
+        if let Connections::Positional(exprs) = self {
+            if exprs.is_empty() {
+                *self = Connections::default();
+            }
+        }
+        match self {
+            Connections::Named(map) => Some(map),
+            Connections::Positional(_) => None,
+        }
+    }
+
+    /// The connections by position, connections by name can only be turned
+    /// into positional ones while there are none.
+    fn positional_mut(&mut self) -> Option<&mut Vec<Expr>> {
+        if let Connections::Named(map) = self {
+            if map.is_empty() {
+                *self = Connections::Positional(Vec::new());
+            }
+        }
+        match self {
+            Connections::Named(_) => None,
+            Connections::Positional(exprs) => Some(exprs),
+        }
+    }
+}
+
 impl Instance {
     pub fn new(id: &str, prim: &str) -> Instance {
         Instance {
             id: id.to_string(),
             prim: prim.to_string(),
             params: Map::new(),
-            ports: Map::new(),
+            ports: Connections::default(),
             attr: Attribute::default(),
         }
     }
@@ -334,7 +739,13 @@ impl Instance {
         &self.params
     }
 
-    pub fn port_map(&self) -> &Map {
+    /// The connections by name, `None` if the ports are connected by
+    /// position.
+    pub fn port_map(&self) -> Option<&Map> {
+        self.ports.named()
+    }
+
+    pub fn connections(&self) -> &Connections {
         &self.ports
     }
 
@@ -368,14 +779,78 @@ impl Instance {
         self.params.insert(param.to_string(), Expr::new_str(value));
     }
 
-    pub fn connect<E>(&mut self, port: &str, expr: E)
+    /// Connect `port` by name, fails if the instance already connects ports
+    /// by position.
+    pub fn connect<E>(&mut self, port: &str, expr: E) -> Result<(), Error>
     where
         E: Into<Expr>,
     {
-        self.ports.insert(port.to_string(), expr.into());
+        match self.ports.named_mut() {
+            Some(map) => {
+                map.insert(port.to_string(), expr.into());
+                Ok(())
+            }
+            None => Err(Error::Invalid(format!(
+                "instance `{}` connects ports by position",
+                self.id
+            ))),
+        }
     }
 
-    pub fn connect_ref(&mut self, port: &str, id: &str) {
-        self.ports.insert(port.to_string(), Expr::new_ref(id));
+    /// Connect `port` to `id` by name, fails if the instance already
+    /// connects ports by position.
+    pub fn connect_ref(&mut self, port: &str, id: &str) -> Result<(), Error> {
+        self.connect(port, Expr::new_ref(id))
+    }
+
+    /// Connect the next port of the module by position, fails if the
+    /// instance already connects ports by name.
+    pub fn connect_positional<E>(&mut self, expr: E) -> Result<(), Error>
+    where
+        E: Into<Expr>,
+    {
+        match self.ports.positional_mut() {
+            Some(exprs) => {
+                exprs.push(expr.into());
+                Ok(())
+            }
+            None => Err(Error::Invalid(format!(
+                "instance `{}` connects ports by name",
+                self.id
+            ))),
+        }
+    }
+
+    /// Turn positional connections into named ones, `names` are the ports
+    /// of the module in order. Connections are left by position if there
+    /// are more of them than names.
+    pub fn name_ports<S: AsRef<str>>(&mut self, names: &[S]) {
+        if let Connections::Positional(exprs) = &self.ports {
+            if exprs.len() <= names.len() {
+                let map = names
+                    .iter()
+                    .map(|name| name.as_ref().to_string())
+                    .zip(exprs.iter().cloned())
+                    .collect();
+                self.ports = Connections::Named(map);
+            }
+        }
+    }
+}
+
+impl<T> GenericPort<T> {
+    pub fn decl(&self) -> &T {
+        match self {
+            GenericPort::Input(decl) | GenericPort::Output(decl) | GenericPort::Inout(decl) => decl,
+        }
+    }
+
+    /// Constructor of a port with the same direction as this one.
+    pub fn direction(&self) -> fn(T) -> GenericPort<T> {
+        match self {
+            GenericPort::Input(_) => GenericPort::Input,
+            GenericPort::Output(_) => GenericPort::Output,
+            GenericPort::Inout(_) => GenericPort::Inout,
+        }
     }
 }
@@ -0,0 +1,109 @@
+use crate::subset::ast::*;
+use crate::subset::width::Scope;
+use crate::Error;
+
+/// A module as seen by its instances.
+pub trait Definition: Clone {
+    fn name(&self) -> &Id;
+
+    /// Names of the ports, in the order they are declared.
+    fn port_names(&self) -> Vec<&Id>;
+
+    /// Names of the input ports.
+    fn input_names(&self) -> Vec<&Id>;
+
+    /// Names of the parameters that an instance can set, in the header or
+    /// in the body.
+    fn param_names(&self) -> Vec<&Id>;
+
+    /// Set the parameters named in `values`.
+    fn set_params(&mut self, values: &Map);
+}
+
+/// Port connections of `inst`, a connection by position is named after the
+/// port of `def` at that position, if there is one.
+pub fn connections<'a, D: Definition>(
+    inst: &'a Instance,
+    def: Option<&'a D>,
+) -> Vec<(Option<&'a Id>, &'a Expr)> {
+    match &inst.ports {
+        Connections::Named(map) => map.iter().map(|(port, expr)| (Some(port), expr)).collect(),
+        Connections::Positional(exprs) => {
+            let ports = def.map(|def| def.port_names()).unwrap_or_default();
+            exprs
+                .iter()
+                .enumerate()
+                .map(|(i, expr)| (ports.get(i).copied(), expr))
+                .collect()
+        }
+    }
+}
+
+/// Collect the scope of `def` as instantiated by `inst` with `scope`, the
+/// parameter values of the instance are folded in the scope `outer` of the
+/// module that holds it.
+pub fn instance_scope<D, F>(def: &D, inst: &Instance, outer: &Scope, scope: F) -> Scope
+where
+    D: Definition,
+    F: FnOnce(&D) -> Scope,
+{
+    let values = inst
+        .params
+        .iter()
+        .map(|(param, expr)| (param.clone(), expr.simplify(outer.env())))
+        .collect();
+    let mut def = def.clone();
+    def.set_params(&values);
+    scope(&def)
+}
+
+/// Check the parameters and the connections of `inst` against `def`.
+pub fn check_instance<D: Definition>(inst: &Instance, def: &D) -> Vec<Error> {
+    let mut errors = Vec::new();
+    let names = def.param_names();
+    for param in inst.params.keys().filter(|param| !names.contains(param)) {
+        errors.push(Error::UnknownParam {
+            instance: inst.id.clone(),
+            module: def.name().clone(),
+            param: param.clone(),
+        });
+    }
+    let ports = def.port_names();
+    let open: Vec<&Id> = match &inst.ports {
+        Connections::Named(map) => map.open().collect(),
+        Connections::Positional(exprs) => {
+            if exprs.len() > ports.len() {
+                errors.push(Error::Invalid(format!(
+                    "instance `{}` of `{}` connects {} ports by position, which is more than {}",
+                    inst.id,
+                    def.name(),
+                    exprs.len(),
+                    ports.len()
+                )));
+            }
+            Vec::new()
+        }
+    };
+    let conns = connections(inst, Some(def));
+    let connected = conns.iter().filter_map(|(port, _)| *port);
+    for port in open.iter().copied().chain(connected) {
+        if !ports.contains(&port) {
+            errors.push(Error::UnknownPort {
+                instance: inst.id.clone(),
+                module: def.name().clone(),
+                port: port.clone(),
+            });
+        }
+    }
+    for input in def.input_names() {
+        // an input left open on purpose, i.e. `.a()`, is not reported
+        if !open.contains(&input) && !conns.iter().any(|(port, _)| *port == Some(input)) {
+            errors.push(Error::Unconnected {
+                instance: inst.id.clone(),
+                module: def.name().clone(),
+                port: input.clone(),
+            });
+        }
+    }
+    errors
+}
@@ -0,0 +1,275 @@
+use crate::subset::parser::ParseError;
+
+/// Operators and punctuation, longest first so that the lexer always takes
+/// the longest match.
+const SYMBOLS: &[&str] = &[
+    "<<<", ">>>", "===", "!==", "**", "&&", "||", "==", "!=", "<=", ">=", "<<", ">>", "~&", "~|",
+    "~^", "^~", "+:", "-:", "::", "(", ")", "[", "]", "{", "}", ",", ";", ":", ".", "#", "@", "=",
+    "+", "-", "*", "/", "%", "<", ">", "!", "~", "&", "|", "^", "?",
+];
+
+/// Compiler directives that do not change the meaning of the design and are
+/// skipped by the lexer.
+const DIRECTIVES: &[&str] = &[
+    "timescale",
+    "default_nettype",
+    "resetall",
+    "celldefine",
+    "endcelldefine",
+];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TokenKind {
+    /// Identifiers and keywords.
+    Ident(String),
+    /// System identifiers such as `$display`, stored without the `$`.
+    SysIdent(String),
+    /// Unsized decimal number.
+    Int(String),
+    /// Based number with optional size, signed if the base has an `s`
+    /// prefix, i.e. `8'hff`, `8'sd5` or `'b1`.
+    Based(Option<String>, bool, char, String),
+    /// Unbased unsized literal such as `'x`.
+    Fill(char),
+    /// String literal, stored without the quotes.
+    Str(String),
+    /// Operator or punctuation.
+    Symbol(&'static str),
+    /// End of input.
+    Eof,
+}
+
+#[derive(Clone, Debug)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub line: usize,
+    pub col: usize,
+}
+
+struct Lexer<'a> {
+    src: &'a str,
+    pos: usize,
+    line: usize,
+    col: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(src: &'a str) -> Lexer<'a> {
+        Lexer {
+            src,
+            pos: 0,
+            line: 1,
+            col: 1,
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn peek_nth(&self, n: usize) -> Option<char> {
+        self.rest().chars().nth(n)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.col = 1;
+        } else {
+            self.col += 1;
+        }
+        Some(c)
+    }
+
+    fn take_while<P>(&mut self, pred: P) -> String
+    where
+        P: Fn(char) -> bool,
+    {
+        let mut s = String::new();
+        while let Some(c) = self.peek() {
+            if pred(c) {
+                s.push(c);
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        s
+    }
+
+    fn error(&self, msg: &str) -> ParseError {
+        ParseError::new(self.line, self.col, msg)
+    }
+
+    fn skip_trivia(&mut self) -> Result<(), ParseError> {
+        loop {
+            match (self.peek(), self.peek_nth(1)) {
+                (Some(c), _) if c.is_whitespace() => {
+                    self.bump();
+                }
+                (Some('/'), Some('/')) => {
+                    self.take_while(|c| c != '\n');
+                }
+                (Some('/'), Some('*')) => {
+                    let err = self.error("unterminated block comment");
+                    self.bump();
+                    self.bump();
+                    loop {
+                        match (self.peek(), self.peek_nth(1)) {
+                            (Some('*'), Some('/')) => {
+                                self.bump();
+                                self.bump();
+                                break;
+                            }
+                            (Some(_), _) => {
+                                self.bump();
+                            }
+                            (None, _) => return Err(err),
+                        }
+                    }
+                }
+                (Some('`'), _) => {
+                    let err = self.error("unsupported compiler directive");
+                    self.bump();
+                    let name = self.take_while(is_ident_char);
+                    if DIRECTIVES.contains(&name.as_str()) {
+                        self.take_while(|c| c != '\n');
+                    } else {
+                        return Err(err);
+                    }
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn based(&mut self, size: Option<String>) -> Result<TokenKind, ParseError> {
+        let err = self.error("invalid based literal");
+        // consume the tick
+        self.bump();
+        let signed = matches!(self.peek(), Some('s') | Some('S'));
+        if signed {
+            self.bump();
+        }
+        match self.peek() {
+            Some(c) if "bBdDhHoO".contains(c) => {
+                self.bump();
+                self.take_while(|c| c == ' ' || c == '\t');
+                let digits = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_' || c == '?');
+                if digits.is_empty() {
+                    Err(err)
+                } else {
+                    Ok(TokenKind::Based(
+                        size,
+                        signed,
+                        c.to_ascii_lowercase(),
+                        digits,
+                    ))
+                }
+            }
+            Some(c) if !signed && size.is_none() && "01xXzZ".contains(c) => {
+                self.bump();
+                Ok(TokenKind::Fill(c.to_ascii_lowercase()))
+            }
+            _ => Err(err),
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Token, ParseError> {
+        self.skip_trivia()?;
+        let (line, col) = (self.line, self.col);
+        let c = match self.peek() {
+            Some(c) => c,
+            None => {
+                return Ok(Token {
+                    kind: TokenKind::Eof,
+                    line,
+                    col,
+                })
+            }
+        };
+        let kind = if c.is_ascii_alphabetic() || c == '_' {
+            TokenKind::Ident(self.take_while(is_ident_char))
+        } else if c == '$' {
+            self.bump();
+            let name = self.take_while(is_ident_char);
+            if name.is_empty() {
+                return Err(ParseError::new(line, col, "expected system identifier"));
+            }
+            TokenKind::SysIdent(name)
+        } else if c.is_ascii_digit() {
+            let num = self.take_while(|c| c.is_ascii_digit() || c == '_');
+            let save = (self.pos, self.line, self.col);
+            self.take_while(|c| c == ' ' || c == '\t');
+            if self.peek() == Some('\'') {
+                self.based(Some(num))?
+            } else {
+                self.pos = save.0;
+                self.line = save.1;
+                self.col = save.2;
+                if self.peek() == Some('.') && self.peek_nth(1).is_some_and(|c| c.is_ascii_digit())
+                {
+                    return Err(ParseError::new(line, col, "real numbers are not supported"));
+                }
+                TokenKind::Int(num)
+            }
+        } else if c == '\'' {
+            self.based(None)?
+        } else if c == '"' {
+            self.bump();
+            let mut s = String::new();
+            loop {
+                match self.bump() {
+                    Some('"') => break,
+                    Some('\\') => {
+                        s.push('\\');
+                        if let Some(e) = self.bump() {
+                            s.push(e);
+                        }
+                    }
+                    Some('\n') | None => {
+                        return Err(ParseError::new(line, col, "unterminated string literal"))
+                    }
+                    Some(c) => s.push(c),
+                }
+            }
+            TokenKind::Str(s)
+        } else if let Some(sym) = SYMBOLS.iter().find(|s| self.rest().starts_with(*s)) {
+            for _ in 0..sym.len() {
+                self.bump();
+            }
+            TokenKind::Symbol(sym)
+        } else {
+            return Err(ParseError::new(
+                line,
+                col,
+                &format!("unexpected character `{}`", c),
+            ));
+        };
+        Ok(Token { kind, line, col })
+    }
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '$'
+}
+
+/// Split the source text into tokens, the last token is always `Eof`.
+pub fn tokenize(src: &str) -> Result<Vec<Token>, ParseError> {
+    let mut lexer = Lexer::new(src);
+    let mut tokens = Vec::new();
+    loop {
+        let token = lexer.next_token()?;
+        let eof = token.kind == TokenKind::Eof;
+        tokens.push(token);
+        if eof {
+            return Ok(tokens);
+        }
+    }
+}
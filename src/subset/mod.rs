@@ -1,6 +1,13 @@
 pub mod ast;
-pub mod default;
+pub mod bitvec;
 pub mod display;
+pub mod elaborate;
+pub mod eval;
 pub mod from;
 pub mod helpers;
+pub mod instance;
+pub mod lexer;
+pub mod parser;
 pub mod pretty_print;
+pub mod visit;
+pub mod width;
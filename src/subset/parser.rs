@@ -0,0 +1,857 @@
+use crate::subset::ast::*;
+use crate::subset::bitvec::BitVec;
+use crate::subset::lexer::{tokenize, Token, TokenKind};
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+use std::rc::Rc;
+
+/// Reserved words that can not be used as identifiers.
+const KEYWORDS: &[&str] = &[
+    "always",
+    "always_comb",
+    "always_ff",
+    "assert",
+    "assign",
+    "automatic",
+    "begin",
+    "case",
+    "default",
+    "do",
+    "else",
+    "end",
+    "endcase",
+    "endfunction",
+    "endgenerate",
+    "endinterface",
+    "endmodule",
+    "endpackage",
+    "endtask",
+    "enum",
+    "export",
+    "final",
+    "for",
+    "foreach",
+    "forever",
+    "function",
+    "generate",
+    "genvar",
+    "if",
+    "import",
+    "initial",
+    "inout",
+    "input",
+    "int",
+    "integer",
+    "interface",
+    "localparam",
+    "logic",
+    "modport",
+    "module",
+    "negedge",
+    "or",
+    "output",
+    "package",
+    "packed",
+    "parameter",
+    "posedge",
+    "ref",
+    "reg",
+    "repeat",
+    "return",
+    "signed",
+    "struct",
+    "task",
+    "typedef",
+    "unique",
+    "void",
+    "while",
+    "wire",
+];
+
+/// Error produced when the source text is not valid or uses a construct that
+/// can not be represented by the AST.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// Line of the offending token, starting at one.
+    pub line: usize,
+    /// Column of the offending token, starting at one.
+    pub col: usize,
+    /// Description of the error.
+    pub msg: String,
+}
+
+impl ParseError {
+    pub fn new(line: usize, col: usize, msg: &str) -> ParseError {
+        ParseError {
+            line,
+            col,
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.col, self.msg)
+    }
+}
+
+impl error::Error for ParseError {}
+
+/// Binding strength and operator for binary operators, higher binds tighter.
+/// Operators without a `Binop` counterpart are reported as unsupported.
+fn binop(sym: &str) -> Option<Binop> {
+    let op = match sym {
+        "||" => Binop::LogOr,
+        "&&" => Binop::LogAnd,
+        "|" => Binop::BitOr,
+        "^" => Binop::BitXor,
+        "~^" | "^~" => Binop::BitXnor,
+        "&" => Binop::BitAnd,
+        "==" => Binop::Equal,
+        "!=" => Binop::NotEqual,
+        "===" => Binop::CaseEqual,
+        "!==" => Binop::CaseNotEqual,
+        "<" => Binop::Lt,
+        "<=" => Binop::Leq,
+        ">" => Binop::Gt,
+        ">=" => Binop::Geq,
+        "<<" => Binop::ShiftLeft,
+        ">>" => Binop::ShiftRight,
+        "<<<" => Binop::ArithShiftLeft,
+        ">>>" => Binop::ArithShiftRight,
+        "+" => Binop::Add,
+        "-" => Binop::Sub,
+        "*" => Binop::Mul,
+        "/" => Binop::Div,
+        "%" => Binop::Mod,
+        "**" => Binop::Pow,
+        _ => return None,
+    };
+    Some(op)
+}
+
+fn unop(sym: &str) -> Option<Unop> {
+    match sym {
+        "!" => Some(Unop::LogNot),
+        "~" => Some(Unop::Not),
+        "&" => Some(Unop::And),
+        "~&" => Some(Unop::Nand),
+        "|" => Some(Unop::Or),
+        "~|" => Some(Unop::Nor),
+        "^" => Some(Unop::Xor),
+        "~^" | "^~" => Some(Unop::Xnor),
+        _ => None,
+    }
+}
+
+/// Parser for a single module item, appending the parsed statements to the
+/// body. Generate constructs are parsed with the item parser of the standard.
+pub type ItemParser<T, U> = fn(&mut Parser, &mut Vec<GenericStmt<T, U>>) -> Result<(), ParseError>;
+
+/// Token cursor with the parsing routines shared by both standards.
+pub struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    pub fn new(src: &str) -> Result<Parser, ParseError> {
+        Ok(Parser {
+            tokens: tokenize(src)?,
+            pos: 0,
+        })
+    }
+
+    pub fn peek(&self) -> &Token {
+        self.peek_nth(0)
+    }
+
+    pub fn peek_nth(&self, n: usize) -> &Token {
+        let last = self.tokens.len() - 1;
+        &self.tokens[(self.pos + n).min(last)]
+    }
+
+    pub fn bump(&mut self) -> Token {
+        let token = self.peek().clone();
+        if token.kind != TokenKind::Eof {
+            self.pos += 1;
+        }
+        token
+    }
+
+    pub fn is_eof(&self) -> bool {
+        self.peek().kind == TokenKind::Eof
+    }
+
+    /// Create an error located at the current token.
+    pub fn error(&self, msg: &str) -> ParseError {
+        let token = self.peek();
+        ParseError::new(token.line, token.col, msg)
+    }
+
+    /// Create an error about the current token not being what was expected.
+    pub fn unexpected(&self, expected: &str) -> ParseError {
+        self.error(&format!(
+            "expected {}, found {}",
+            expected,
+            describe(&self.peek().kind)
+        ))
+    }
+
+    pub fn is_symbol_nth(&self, n: usize, sym: &str) -> bool {
+        matches!(self.peek_nth(n).kind, TokenKind::Symbol(s) if s == sym)
+    }
+
+    pub fn is_symbol(&self, sym: &str) -> bool {
+        self.is_symbol_nth(0, sym)
+    }
+
+    pub fn eat_symbol(&mut self, sym: &str) -> bool {
+        if self.is_symbol(sym) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn expect_symbol(&mut self, sym: &str) -> Result<(), ParseError> {
+        if self.eat_symbol(sym) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("`{}`", sym)))
+        }
+    }
+
+    pub fn is_keyword_nth(&self, n: usize, kw: &str) -> bool {
+        matches!(&self.peek_nth(n).kind, TokenKind::Ident(s) if s == kw)
+    }
+
+    pub fn is_keyword(&self, kw: &str) -> bool {
+        self.is_keyword_nth(0, kw)
+    }
+
+    pub fn eat_keyword(&mut self, kw: &str) -> bool {
+        if self.is_keyword(kw) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn expect_keyword(&mut self, kw: &str) -> Result<(), ParseError> {
+        if self.eat_keyword(kw) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("`{}`", kw)))
+        }
+    }
+
+    /// Returns true if the token at offset `n` is an identifier that is not
+    /// a reserved word.
+    pub fn is_ident_nth(&self, n: usize) -> bool {
+        matches!(&self.peek_nth(n).kind, TokenKind::Ident(s) if !KEYWORDS.contains(&s.as_str()))
+    }
+
+    pub fn is_ident(&self) -> bool {
+        self.is_ident_nth(0)
+    }
+
+    pub fn expect_ident(&mut self) -> Result<Id, ParseError> {
+        if self.is_ident() {
+            match self.bump().kind {
+                TokenKind::Ident(name) => Ok(name),
+                _ => unreachable!(),
+            }
+        } else {
+            Err(self.unexpected("identifier"))
+        }
+    }
+
+    pub fn expect_str(&mut self) -> Result<String, ParseError> {
+        if let TokenKind::Str(s) = &self.peek().kind {
+            let s = s.clone();
+            self.bump();
+            Ok(s)
+        } else {
+            Err(self.unexpected("string"))
+        }
+    }
+
+    /// Returns true if the next tokens open an attribute, i.e. `(*` but not
+    /// the `(*)` wildcard.
+    pub fn is_attribute(&self) -> bool {
+        self.is_symbol("(") && self.is_symbol_nth(1, "*") && !self.is_symbol_nth(2, ")")
+    }
+
+    /// Parse any number of consecutive attributes into a single one.
+    pub fn parse_attributes(&mut self) -> Result<Attribute, ParseError> {
+        let mut attrs = Vec::new();
+        while self.is_attribute() {
+            self.bump();
+            self.bump();
+            loop {
+                let id = self.expect_ident()?;
+                if self.eat_symbol("=") {
+                    let value = match self.bump().kind {
+                        TokenKind::Str(s) | TokenKind::Int(s) | TokenKind::Ident(s) => s,
+                        TokenKind::Based(Some(size), signed, base, digits) => {
+                            format!("{}'{}{}{}", size, sign(signed), base, digits)
+                        }
+                        _ => return Err(self.error("expected attribute value")),
+                    };
+                    attrs.push(AttributeTy::Stmt(id, value));
+                } else {
+                    attrs.push(AttributeTy::Val(id));
+                }
+                if !self.eat_symbol(",") {
+                    break;
+                }
+            }
+            self.expect_symbol("*")?;
+            self.expect_symbol(")")?;
+        }
+        // attributes are printed in reverse order
+        attrs.reverse();
+        Ok(Attribute { attrs })
+    }
+
+    /// Parse a range `[msb:lsb]` and return its bounds.
+    pub fn parse_range(&mut self) -> Result<(Expr, Expr), ParseError> {
+        self.expect_symbol("[")?;
+        let msb = self.parse_expr()?;
+        self.expect_symbol(":")?;
+        let lsb = self.parse_expr()?;
+        self.expect_symbol("]")?;
+        Ok((msb, lsb))
+    }
+
+    /// Parse an event expression such as `posedge clock`.
+    pub fn parse_event(&mut self) -> Result<(EventTy, Expr), ParseError> {
+        let ty = if self.eat_keyword("posedge") {
+            EventTy::Posedge
+        } else if self.eat_keyword("negedge") {
+            EventTy::Negedge
+        } else {
+            return Err(self.error("only posedge and negedge events are supported"));
+        };
+        let expr = self.parse_expr()?;
+        if self.is_keyword("or") || self.is_symbol(",") {
+            return Err(self.error("multiple events are not supported"));
+        }
+        Ok((ty, expr))
+    }
+
+    pub fn parse_expr(&mut self) -> Result<Expr, ParseError> {
+        let cond = self.parse_binary(1)?;
+        if self.eat_symbol("?") {
+            let tru = self.parse_expr()?;
+            self.expect_symbol(":")?;
+            let fal = self.parse_expr()?;
+            Ok(Expr::Terop(
+                Terop::Mux,
+                Rc::new(cond),
+                Rc::new(tru),
+                Rc::new(fal),
+            ))
+        } else {
+            Ok(cond)
+        }
+    }
+
+    fn parse_binary(&mut self, min_prec: u8) -> Result<Expr, ParseError> {
+        let mut lhs = self.parse_unary()?;
+        while let TokenKind::Symbol(sym) = self.peek().kind {
+            let op = match binop(sym) {
+                Some(op) if op.precedence() >= min_prec => op,
+                _ => break,
+            };
+            let prec = op.precedence();
+            self.bump();
+            let rhs = self.parse_binary(prec + 1)?;
+            lhs = Expr::Binop(op, Rc::new(lhs), Rc::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, ParseError> {
+        if let TokenKind::Symbol(sym) = self.peek().kind {
+            if let Some(op) = unop(sym) {
+                self.bump();
+                let expr = self.parse_unary()?;
+                return Ok(Expr::Unop(op, Rc::new(expr)));
+            }
+            if sym == "-" {
+                self.bump();
+                // a negative integer literal stays an integer, which can be
+                // one smaller than the largest positive literal, unless it
+                // is selected from
+                if let TokenKind::Int(s) = self.peek().kind.clone() {
+                    let (line, col) = (self.peek().line, self.peek().col);
+                    self.bump();
+                    let n = s.replace('_', "").parse::<i64>().ok();
+                    let selected = self.peek().kind == TokenKind::Symbol("[");
+                    let n = if selected { n } else { n.map(|n| -n) };
+                    let int = n
+                        .and_then(|n| i32::try_from(n).ok())
+                        .map(Expr::Int)
+                        .ok_or_else(|| {
+                            ParseError::new(line, col, "integer literal out of range")
+                        })?;
+                    if selected {
+                        return Ok(Expr::new_neg(self.parse_selects(int)?));
+                    }
+                    return Ok(int);
+                }
+                let expr = self.parse_unary()?;
+                return Ok(Expr::new_neg(expr));
+            }
+        }
+        let primary = self.parse_primary()?;
+        self.parse_selects(primary)
+    }
+
+    /// Parse an expression that can be the target of an assignment.
+    pub fn parse_lvalue(&mut self) -> Result<Expr, ParseError> {
+        if self.is_symbol("{") || self.is_ident() {
+            let primary = self.parse_primary()?;
+            self.parse_selects(primary)
+        } else {
+            Err(self.unexpected("assignment target"))
+        }
+    }
+
+    fn parse_selects(&mut self, mut expr: Expr) -> Result<Expr, ParseError> {
+        while self.eat_symbol("[") {
+            let first = self.parse_expr()?;
+            expr = if self.eat_symbol(":") {
+                let lo = self.parse_expr()?;
+                Expr::Terop(Terop::Slice, Rc::new(expr), Rc::new(first), Rc::new(lo))
+            } else if self.eat_symbol("+:") {
+                let width = self.parse_expr()?;
+                Expr::Terop(
+                    Terop::IndexSlice,
+                    Rc::new(expr),
+                    Rc::new(first),
+                    Rc::new(width),
+                )
+            } else if self.is_symbol("-:") {
+                return Err(self.error("descending index slices are not supported"));
+            } else {
+                match expr {
+                    Expr::IPath(path, None) => Expr::IPath(path, Some(Rc::new(first))),
+                    expr => Expr::Binop(Binop::IndexBit, Rc::new(expr), Rc::new(first)),
+                }
+            };
+            self.expect_symbol("]")?;
+        }
+        Ok(expr)
+    }
+
+    fn parse_args(&mut self) -> Result<Vec<Expr>, ParseError> {
+        let mut args = Vec::new();
+        self.expect_symbol("(")?;
+        if !self.eat_symbol(")") {
+            loop {
+                args.push(self.parse_expr()?);
+                if !self.eat_symbol(",") {
+                    break;
+                }
+            }
+            self.expect_symbol(")")?;
+        }
+        Ok(args)
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, ParseError> {
+        let (line, col) = (self.peek().line, self.peek().col);
+        let err = |msg: &str| ParseError::new(line, col, msg);
+        match self.peek().kind.clone() {
+            TokenKind::Int(s) => {
+                self.bump();
+                s.replace('_', "")
+                    .parse::<i32>()
+                    .map(Expr::Int)
+                    .map_err(|_| err("integer literal out of range"))
+            }
+            TokenKind::Based(Some(size), signed, base, digits) => {
+                self.bump();
+                let width = size
+                    .replace('_', "")
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|w| *w > 0)
+                    .ok_or_else(|| err("invalid literal width"))?;
+                let radix = match base {
+                    'd' => Radix::Dec,
+                    'b' => Radix::Bin,
+                    'h' => Radix::Hex,
+                    _ => return Err(err("octal literals are not supported")),
+                };
+                let value = BitVec::from_str_radix(width, &digits, radix)
+                    .map_err(|e| err(&e.to_string()))?;
+                // a signed literal is the same value as `$signed` of it
+                let ulit = Expr::ULit(value, radix);
+                Ok(if signed {
+                    Expr::Signed(Rc::new(ulit))
+                } else {
+                    ulit
+                })
+            }
+            TokenKind::Based(None, _, _, _) => Err(err("unsized based literals are not supported")),
+            TokenKind::Fill('x') => {
+                self.bump();
+                Ok(Expr::X)
+            }
+            TokenKind::Fill(_) => Err(err("only the 'x literal is supported")),
+            TokenKind::Str(s) => {
+                self.bump();
+                Ok(Expr::Str(s))
+            }
+            TokenKind::SysIdent(name) => {
+                self.bump();
+                let mut args = if self.is_symbol("(") {
+                    self.parse_args()?
+                } else {
+                    Vec::new()
+                };
+                if name == "signed" {
+                    if args.len() != 1 {
+                        return Err(err("$signed expects one argument"));
+                    }
+                    Ok(Expr::Signed(Rc::new(args.remove(0))))
+                } else {
+                    Ok(Expr::Call(format!("${}", name), args))
+                }
+            }
+            TokenKind::Symbol("(") => {
+                self.bump();
+                let expr = self.parse_expr()?;
+                self.expect_symbol(")")?;
+                Ok(expr)
+            }
+            TokenKind::Symbol("{") => {
+                self.bump();
+                let first = self.parse_expr()?;
+                if self.is_symbol("{") {
+                    let times = match first {
+                        Expr::Int(n) if n >= 0 => n as u64,
+                        _ => return Err(err("replication count must be a constant integer")),
+                    };
+                    self.bump();
+                    let mut exprs = vec![self.parse_expr()?];
+                    while self.eat_symbol(",") {
+                        exprs.push(self.parse_expr()?);
+                    }
+                    self.expect_symbol("}")?;
+                    self.expect_symbol("}")?;
+                    let expr = if exprs.len() == 1 {
+                        exprs.remove(0)
+                    } else {
+                        exprs.reverse();
+                        Expr::Concat(ExprConcat { exprs })
+                    };
+                    Ok(Expr::Repeat(times, Rc::new(expr)))
+                } else {
+                    let mut exprs = vec![first];
+                    while self.eat_symbol(",") {
+                        exprs.push(self.parse_expr()?);
+                    }
+                    self.expect_symbol("}")?;
+                    // concatenations are printed in reverse order
+                    exprs.reverse();
+                    Ok(Expr::Concat(ExprConcat { exprs }))
+                }
+            }
+            TokenKind::Ident(_) if self.is_ident() => {
+                let mut name = self.expect_ident()?;
+                // a name scoped by a package, i.e. `pkg::IDLE`
+                if self.eat_symbol("::") {
+                    name = format!("{}::{}", name, self.expect_ident()?);
+                }
+                if self.is_symbol(".") {
+                    let mut path = vec![name];
+                    while self.eat_symbol(".") {
+                        path.push(self.expect_ident()?);
+                    }
+                    if self.is_symbol("(") {
+                        // a task or function of an interface, `m.send(x)`
+                        let args = self.parse_args()?;
+                        Ok(Expr::Call(path.join("."), args))
+                    } else {
+                        Ok(Expr::IPath(InstancePath { path }, None))
+                    }
+                } else if self.is_symbol("(") {
+                    let args = self.parse_args()?;
+                    Ok(Expr::Call(name, args))
+                } else {
+                    Ok(Expr::Ref(name))
+                }
+            }
+            _ => Err(self.unexpected("expression")),
+        }
+    }
+
+    fn parse_connections(&mut self, what: &str) -> Result<Map, ParseError> {
+        let mut map = Map::new();
+        self.expect_symbol("(")?;
+        if self.eat_symbol(")") {
+            return Ok(map);
+        }
+        loop {
+            if !self.is_symbol(".") {
+                return Err(self.error(&format!("only named {} connections are supported", what)));
+            }
+            self.bump();
+            let token = self.peek().clone();
+            let name = self.expect_ident()?;
+            self.expect_symbol("(")?;
+            if map.names().any(|k| *k == name) {
+                return Err(ParseError::new(
+                    token.line,
+                    token.col,
+                    &format!("duplicate {} connection `{}`", what, name),
+                ));
+            }
+            if self.eat_symbol(")") {
+                map.insert_open(name);
+            } else {
+                map.insert(name, self.parse_expr()?);
+                self.expect_symbol(")")?;
+            }
+            if !self.eat_symbol(",") {
+                break;
+            }
+        }
+        self.expect_symbol(")")?;
+        Ok(map)
+    }
+
+    /// Returns true if the next tokens start a function or system task call.
+    pub fn is_call(&self) -> bool {
+        matches!(self.peek().kind, TokenKind::SysIdent(_))
+            || (self.is_ident() && self.is_symbol_nth(1, "("))
+            || (self.is_ident()
+                && self.is_symbol_nth(1, ".")
+                && self.is_ident_nth(2)
+                && self.is_symbol_nth(3, "("))
+    }
+
+    /// Parse the header of a for loop without the parentheses, i.e.
+    /// `i = init; cond; i = step`, returning the variable, the initial
+    /// value, the condition and the next value.
+    pub fn parse_for_header(&mut self) -> Result<(Id, Expr, Expr, Expr), ParseError> {
+        let var = self.expect_ident()?;
+        self.expect_symbol("=")?;
+        let init = self.parse_expr()?;
+        self.expect_symbol(";")?;
+        let cond = self.parse_expr()?;
+        self.expect_symbol(";")?;
+        if self.expect_ident()? != var {
+            return Err(self.error(&format!("loop must update `{}`", var)));
+        }
+        self.expect_symbol("=")?;
+        let step = self.parse_expr()?;
+        Ok((var, init, cond, step))
+    }
+
+    /// Check for the header of a non-ANSI module, a list of port names.
+    pub fn is_port_names(&self) -> bool {
+        // an ANSI port may start with an interface name, `(bus_if m)`
+        self.is_symbol("(")
+            && self.is_ident_nth(1)
+            && (self.is_symbol_nth(2, ",") || self.is_symbol_nth(2, ")"))
+    }
+
+    /// Parse the port names of a non-ANSI module header, `(a, b, c)`.
+    pub fn parse_port_names(&mut self) -> Result<Vec<Id>, ParseError> {
+        let mut names = Vec::new();
+        self.expect_symbol("(")?;
+        loop {
+            names.push(self.expect_ident()?);
+            if !self.eat_symbol(",") {
+                break;
+            }
+        }
+        self.expect_symbol(")")?;
+        Ok(names)
+    }
+
+    /// Order the ports declared in the body of a non-ANSI module as the
+    /// `names` in its header.
+    pub fn order_ports<P>(
+        &self,
+        names: &[Id],
+        mut ports: Vec<P>,
+        name: fn(&P) -> &Id,
+    ) -> Result<Vec<P>, ParseError> {
+        if let Some(port) = ports.iter().find(|port| !names.contains(name(port))) {
+            let msg = format!("`{}` is not in the port list", name(port));
+            return Err(self.error(&msg));
+        }
+        let mut ordered = Vec::new();
+        for id in names {
+            match ports.iter().position(|port| name(port) == id) {
+                Some(i) => ordered.push(ports.remove(i)),
+                None => return Err(self.error(&format!("port `{}` has no direction", id))),
+            }
+        }
+        Ok(ordered)
+    }
+
+    /// Parse a parenthesized expression, i.e. the condition of a loop.
+    pub fn parse_paren_expr(&mut self) -> Result<Expr, ParseError> {
+        self.expect_symbol("(")?;
+        let expr = self.parse_expr()?;
+        self.expect_symbol(")")?;
+        Ok(expr)
+    }
+
+    /// Parse a generate block, either `begin [: label] ... end` or a single
+    /// module item.
+    pub fn parse_generate_block<T, U>(
+        &mut self,
+        item: ItemParser<T, U>,
+    ) -> Result<GenericGenerateBlock<T, U>, ParseError> {
+        let mut block = GenericGenerateBlock {
+            label: None,
+            body: Vec::new(),
+        };
+        if self.eat_keyword("begin") {
+            if self.eat_symbol(":") {
+                block.label = Some(self.expect_ident()?);
+            }
+            while !self.eat_keyword("end") {
+                if self.is_eof() {
+                    return Err(self.unexpected("`end`"));
+                }
+                item(self, &mut block.body)?;
+            }
+        } else {
+            item(self, &mut block.body)?;
+        }
+        Ok(block)
+    }
+
+    /// Parse a generate loop, `for (i = init; cond; i = step) block`.
+    pub fn parse_generate_for<T, U>(
+        &mut self,
+        item: ItemParser<T, U>,
+    ) -> Result<GenericGenerateFor<T, U>, ParseError> {
+        self.expect_keyword("for")?;
+        self.expect_symbol("(")?;
+        if self.is_keyword("genvar") {
+            return Err(self.error("genvar declarations in a loop header are not supported"));
+        }
+        let (var, init, cond, step) = self.parse_for_header()?;
+        self.expect_symbol(")")?;
+        Ok(GenericGenerateFor {
+            var,
+            init,
+            cond,
+            step,
+            block: self.parse_generate_block(item)?,
+        })
+    }
+
+    /// Parse a generate conditional, `else if` yields an else block that
+    /// holds the nested conditional.
+    pub fn parse_generate_if<T, U>(
+        &mut self,
+        item: ItemParser<T, U>,
+    ) -> Result<GenericGenerateIf<T, U>, ParseError> {
+        self.expect_keyword("if")?;
+        self.expect_symbol("(")?;
+        let cond = self.parse_expr()?;
+        self.expect_symbol(")")?;
+        let block = self.parse_generate_block(item)?;
+        let else_branch = if self.eat_keyword("else") {
+            Some(self.parse_generate_block(item)?)
+        } else {
+            None
+        };
+        Ok(GenericGenerateIf {
+            cond,
+            block,
+            else_branch,
+        })
+    }
+
+    /// Returns true if the next tokens start a module instance.
+    pub fn is_instance(&self) -> bool {
+        self.is_ident() && (self.is_ident_nth(1) || self.is_symbol_nth(1, "#"))
+    }
+
+    /// Parse port connections by position, i.e. `(a, b, c)`.
+    fn parse_positional(&mut self) -> Result<Vec<Expr>, ParseError> {
+        let mut exprs = Vec::new();
+        self.expect_symbol("(")?;
+        loop {
+            exprs.push(self.parse_expr()?);
+            if !self.eat_symbol(",") {
+                break;
+            }
+        }
+        self.expect_symbol(")")?;
+        Ok(exprs)
+    }
+
+    /// Parse a module instance with named parameters, and ports connected
+    /// either by name or by position.
+    pub fn parse_instance(&mut self, attr: Attribute) -> Result<Instance, ParseError> {
+        let prim = self.expect_ident()?;
+        let params = if self.eat_symbol("#") {
+            self.parse_connections("parameter")?
+        } else {
+            Map::new()
+        };
+        let id = self.expect_ident()?;
+        if self.is_symbol("[") {
+            return Err(self.error("arrays of instances are not supported"));
+        }
+        let ports =
+            if self.is_symbol("(") && !self.is_symbol_nth(1, ".") && !self.is_symbol_nth(1, ")") {
+                Connections::Positional(self.parse_positional()?)
+            } else {
+                Connections::Named(self.parse_connections("port")?)
+            };
+        self.expect_symbol(";")?;
+        Ok(Instance {
+            id,
+            prim,
+            params,
+            ports,
+            attr,
+        })
+    }
+}
+
+/// The prefix of the base of a signed literal.
+fn sign(signed: bool) -> &'static str {
+    if signed {
+        "s"
+    } else {
+        ""
+    }
+}
+
+fn describe(kind: &TokenKind) -> String {
+    match kind {
+        TokenKind::Ident(s) => format!("`{}`", s),
+        TokenKind::SysIdent(s) => format!("`${}`", s),
+        TokenKind::Int(s) => format!("`{}`", s),
+        TokenKind::Based(Some(size), signed, base, digits) => {
+            format!("`{}'{}{}{}`", size, sign(*signed), base, digits)
+        }
+        TokenKind::Based(None, signed, base, digits) => {
+            format!("`'{}{}{}`", sign(*signed), base, digits)
+        }
+        TokenKind::Fill(c) => format!("`'{}`", c),
+        TokenKind::Str(s) => format!("\"{}\"", s),
+        TokenKind::Symbol(s) => format!("`{}`", s),
+        TokenKind::Eof => "end of input".to_string(),
+    }
+}
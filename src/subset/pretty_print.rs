@@ -1,98 +1,63 @@
 use crate::subset::ast::*;
 use crate::util::pretty_print::{block, intersperse, PrettyHelper, PrettyPrint};
-use core::cmp::Ordering;
-use itertools::Itertools;
 use pretty::RcDoc;
 
-/// Tracks the context in the guards to only generate parens when inside an
-/// operator with stronger binding.
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
-enum ParenCtx {
-    Op,
-    Not,
-    And,
-    Or,
-    BAnd,
-    BOr,
-}
-
-impl From<&Binop> for ParenCtx {
-    fn from(s: &Binop) -> Self {
-        match s {
-            Binop::BitOr => ParenCtx::BOr,
-            Binop::BitAnd => ParenCtx::BAnd,
-            Binop::LogOr => ParenCtx::Or,
-            Binop::LogAnd => ParenCtx::And,
-            Binop::Add
-            | Binop::Sub
-            | Binop::Mul
-            | Binop::Lt
-            | Binop::Gt
-            | Binop::Geq
-            | Binop::Leq
-            | Binop::Equal
-            | Binop::NotEqual
-            | Binop::IndexBit
-            | Binop::ShiftLeft => ParenCtx::Op,
-        }
-    }
-}
-
-impl Ord for ParenCtx {
-    fn cmp(&self, other: &Self) -> Ordering {
-        use ParenCtx as P;
-        if self == other {
-            return Ordering::Equal;
-        }
-        match (self, other) {
-            (P::Not, _) => Ordering::Greater,
+/// Precedence of the unary operators, which bind tighter than any binary
+/// operator.
+const UNARY_PRECEDENCE: u8 = 12;
 
-            (P::Op, P::Not) => Ordering::Less,
-            (P::Op, _) => Ordering::Greater,
+/// Precedence of primaries and selects, which never need parens.
+const PRIMARY_PRECEDENCE: u8 = 13;
 
-            (P::BAnd, P::Not) | (P::BAnd, P::Op) => Ordering::Less,
-            (P::BAnd, _) => Ordering::Greater,
-
-            (P::BOr, P::Not) | (P::BOr, P::Op) | (P::BOr, P::BAnd) => Ordering::Less,
-            (P::BOr, _) => Ordering::Greater,
-
-            (P::And, P::Not) | (P::And, P::Op) | (P::And, P::BAnd) | (P::And, P::BOr) => {
-                Ordering::Less
-            }
-            (P::And, _) => Ordering::Greater,
-
-            (P::Or, _) => Ordering::Less,
-        }
+/// Binding strength of an expression used as an operand, the conditional
+/// operator binds the weakest.
+fn precedence(e: &Expr) -> u8 {
+    match e {
+        Expr::Terop(Terop::Mux, _, _, _) => 0,
+        Expr::Binop(op, _, _) => op.precedence(),
+        Expr::Unop(_, _) => UNARY_PRECEDENCE,
+        Expr::Int(n) if *n < 0 => UNARY_PRECEDENCE,
+        _ => PRIMARY_PRECEDENCE,
     }
 }
 
-impl PartialOrd for ParenCtx {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+/// Print an operand that binds at least as tight as `min`, adding parens
+/// otherwise.
+fn print_expr(e: &Expr, min: u8) -> RcDoc<'_, ()> {
+    if precedence(e) < min {
+        e.to_doc().parens()
+    } else {
+        e.to_doc()
     }
 }
 
-fn print_expr(e: &Expr, cur_ctx: ParenCtx) -> RcDoc<()> {
+/// Print the operand of a unary operator, adding parens when the operators
+/// would lex as a single token such as `~&` or `&&`.
+fn print_unary_operand<'a>(op: &Unop, e: &'a Expr) -> RcDoc<'a, ()> {
     match e {
-        Expr::Binop(op, lhs, rhs) => {
-            let ctx = ParenCtx::from(op);
-            let doc = print_expr(lhs, ctx)
-                .append(RcDoc::space())
-                .append(op.to_doc())
-                .append(RcDoc::space())
-                .append(print_expr(rhs, ctx));
-            if cur_ctx > ctx {
-                doc.parens()
-            } else {
-                doc
+        Expr::Unop(inner, _) => {
+            let pair = (op.to_string(), inner.to_string());
+            let last = pair.0.chars().last();
+            let first = pair.1.chars().next();
+            match (last, first) {
+                (Some('&'), Some('&'))
+                | (Some('|'), Some('|'))
+                | (Some('~'), Some('&'))
+                | (Some('~'), Some('|'))
+                | (Some('~'), Some('^'))
+                | (Some('^'), Some('~'))
+                | (Some('-'), Some('-')) => e.to_doc().parens(),
+                _ => e.to_doc(),
             }
         }
-        e => e.to_doc(),
+        // `-1` would parse as a negative literal and `--1` as a decrement
+        Expr::Int(_) if *op == Unop::Neg => e.to_doc().parens(),
+        _ => print_expr(e, UNARY_PRECEDENCE),
     }
 }
 
 impl PrettyPrint for Unop {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         match self {
             Unop::LogNot => RcDoc::text("!"),
             Unop::Not => RcDoc::text("~"),
@@ -102,34 +67,45 @@ impl PrettyPrint for Unop {
             Unop::Nor => RcDoc::text("~|"),
             Unop::Xor => RcDoc::text("^"),
             Unop::Xnor => RcDoc::text("~^"),
+            Unop::Neg => RcDoc::text("-"),
         }
     }
 }
 
 impl PrettyPrint for Binop {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         match self {
             Binop::BitOr => RcDoc::text("|"),
             Binop::BitAnd => RcDoc::text("&"),
+            Binop::BitXor => RcDoc::text("^"),
+            Binop::BitXnor => RcDoc::text("~^"),
             Binop::LogOr => RcDoc::text("||"),
             Binop::LogAnd => RcDoc::text("&&"),
             Binop::Add => RcDoc::text("+"),
             Binop::Sub => RcDoc::text("-"),
             Binop::Mul => RcDoc::text("*"),
+            Binop::Div => RcDoc::text("/"),
+            Binop::Mod => RcDoc::text("%"),
+            Binop::Pow => RcDoc::text("**"),
             Binop::Lt => RcDoc::text("<"),
             Binop::Gt => RcDoc::text(">"),
             Binop::Geq => RcDoc::text(">="),
             Binop::Leq => RcDoc::text("<="),
             Binop::Equal => RcDoc::text("=="),
             Binop::NotEqual => RcDoc::text("!="),
+            Binop::CaseEqual => RcDoc::text("==="),
+            Binop::CaseNotEqual => RcDoc::text("!=="),
             Binop::ShiftLeft => RcDoc::text("<<"),
+            Binop::ShiftRight => RcDoc::text(">>"),
+            Binop::ArithShiftLeft => RcDoc::text("<<<"),
+            Binop::ArithShiftRight => RcDoc::text(">>>"),
             Binop::IndexBit => RcDoc::nil(),
         }
     }
 }
 
 impl PrettyPrint for Radix {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         match self {
             Radix::Dec => RcDoc::text("d"),
             Radix::Bin => RcDoc::text("b"),
@@ -139,13 +115,13 @@ impl PrettyPrint for Radix {
 }
 
 impl PrettyPrint for InstancePath {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         intersperse(self.path().iter().map(RcDoc::as_string), RcDoc::text("."))
     }
 }
 
 impl PrettyPrint for ExprConcat {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         intersperse(
             self.exprs().iter().rev().map(|x| x.to_doc()),
             RcDoc::text(",").append(RcDoc::space()),
@@ -155,15 +131,12 @@ impl PrettyPrint for ExprConcat {
 }
 
 impl PrettyPrint for Expr {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         match self {
             Expr::X => RcDoc::text("'x"),
             Expr::Ref(name) => RcDoc::as_string(name),
             Expr::Int(num) => RcDoc::as_string(num),
-            Expr::ULit(width, radix, value) => RcDoc::as_string(width)
-                .append(RcDoc::text("'"))
-                .append(radix.to_doc())
-                .append(RcDoc::as_string(value)),
+            Expr::ULit(value, radix) => RcDoc::as_string(value.to_string_radix(*radix)),
             Expr::Str(value) => RcDoc::as_string(value).quotes(),
             Expr::Signed(expr) => RcDoc::text("$")
                 .append(RcDoc::text("signed"))
@@ -175,11 +148,20 @@ impl PrettyPrint for Expr {
                     path.to_doc()
                 }
             }
-            Expr::Unop(op, input) => op.to_doc().append(print_expr(input, ParenCtx::Not)),
+            Expr::Unop(op, input) => op.to_doc().append(print_unary_operand(op, input)),
             Expr::Binop(Binop::IndexBit, lhs, rhs) => {
-                print_expr(lhs, ParenCtx::Not).append(rhs.to_doc().brackets())
+                print_expr(lhs, PRIMARY_PRECEDENCE).append(rhs.to_doc().brackets())
+            }
+            Expr::Binop(op, lhs, rhs) => {
+                // operators are left associative, so a right operand at the
+                // same level needs parens
+                let prec = op.precedence();
+                print_expr(lhs, prec)
+                    .append(RcDoc::space())
+                    .append(op.to_doc())
+                    .append(RcDoc::space())
+                    .append(print_expr(rhs, prec + 1))
             }
-            Expr::Binop(_, _, _) => print_expr(self, ParenCtx::Or),
             Expr::Call(name, params) => RcDoc::as_string(name).append(
                 intersperse(
                     params.iter().map(RcDoc::as_string),
@@ -187,8 +169,8 @@ impl PrettyPrint for Expr {
                 )
                 .parens(),
             ),
-            Expr::Terop(Terop::Mux, cond, tru, fal) => cond
-                .to_doc()
+            // the conditional operator is right associative
+            Expr::Terop(Terop::Mux, cond, tru, fal) => print_expr(cond, 1)
                 .append(RcDoc::space())
                 .append(RcDoc::text("?"))
                 .append(RcDoc::space())
@@ -202,21 +184,22 @@ impl PrettyPrint for Expr {
                 })
                 .append(RcDoc::space())
                 .append(fal.to_doc()),
-            Expr::Terop(Terop::Slice, var, hi, lo) => var.to_doc().append(
+            Expr::Terop(Terop::Slice, var, hi, lo) => print_expr(var, PRIMARY_PRECEDENCE).append(
                 hi.to_doc()
                     .append(RcDoc::text(":"))
                     .append(lo.to_doc())
                     .brackets(),
             ),
-            Expr::Terop(Terop::IndexSlice, var, lo, width) => var.to_doc().append(
-                lo.to_doc()
-                    .append(RcDoc::space())
-                    .append(RcDoc::text("+"))
-                    .append(RcDoc::text(":"))
-                    .append(RcDoc::space())
-                    .append(width.to_doc())
-                    .brackets(),
-            ),
+            Expr::Terop(Terop::IndexSlice, var, lo, width) => print_expr(var, PRIMARY_PRECEDENCE)
+                .append(
+                    lo.to_doc()
+                        .append(RcDoc::space())
+                        .append(RcDoc::text("+"))
+                        .append(RcDoc::text(":"))
+                        .append(RcDoc::space())
+                        .append(width.to_doc())
+                        .brackets(),
+                ),
             Expr::Concat(concat) => concat.to_doc(),
             Expr::Repeat(times, expr) => RcDoc::text(times.to_string())
                 .append(expr.to_doc().braces())
@@ -226,7 +209,7 @@ impl PrettyPrint for Expr {
 }
 
 impl PrettyPrint for AttributeTy {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         match self {
             AttributeTy::Val(name) => RcDoc::as_string(name),
             AttributeTy::Stmt(id, value) => RcDoc::as_string(id)
@@ -239,7 +222,7 @@ impl PrettyPrint for AttributeTy {
 }
 
 impl PrettyPrint for Attribute {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         intersperse(
             self.attrs().iter().rev().map(|x| x.to_doc()),
             RcDoc::text(",").append(RcDoc::space()),
@@ -250,7 +233,7 @@ impl PrettyPrint for Attribute {
 }
 
 impl PrettyPrint for EventTy {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         match self {
             EventTy::Posedge => RcDoc::text("posedge"),
             EventTy::Negedge => RcDoc::text("negedge"),
@@ -259,7 +242,7 @@ impl PrettyPrint for EventTy {
 }
 
 impl PrettyPrint for AssignTy {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         match self {
             AssignTy::Blocking => RcDoc::text("="),
             AssignTy::NonBlocking => RcDoc::text("<="),
@@ -268,22 +251,24 @@ impl PrettyPrint for AssignTy {
 }
 
 impl PrettyPrint for Map {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         intersperse(
-            self.iter()
-                .sorted_by_key(|(id, _)| (*id).clone())
-                .map(|(id, expr)| {
-                    RcDoc::text(".")
-                        .append(RcDoc::as_string(id))
-                        .append(expr.to_doc().parens())
-                }),
+            self.entries.iter().map(|(id, expr)| {
+                let expr = match expr {
+                    Some(expr) => expr.to_doc(),
+                    None => RcDoc::nil(),
+                };
+                RcDoc::text(".")
+                    .append(RcDoc::as_string(id))
+                    .append(expr.parens())
+            }),
             RcDoc::text(",").append(RcDoc::hardline()),
         )
     }
 }
 
 impl PrettyPrint for Instance {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         let params = if self.param_map().is_empty() {
             RcDoc::space()
         } else {
@@ -293,10 +278,17 @@ impl PrettyPrint for Instance {
                 .append(block(self.param_map().to_doc()).parens())
                 .append(RcDoc::space())
         };
-        let ports = if self.port_map().is_empty() {
+        let ports = if self.connections().is_empty() {
             RcDoc::space().parens()
         } else {
-            RcDoc::space().append(block(self.port_map().to_doc()).parens())
+            match self.connections() {
+                Connections::Named(map) => RcDoc::space().append(block(map.to_doc()).parens()),
+                Connections::Positional(exprs) => {
+                    let exprs = exprs.iter().map(|expr| expr.to_doc());
+                    let exprs = intersperse(exprs, RcDoc::text(",").append(RcDoc::hardline()));
+                    RcDoc::space().append(block(exprs).parens())
+                }
+            }
         };
         let attr = if self.attr().attrs().is_empty() {
             RcDoc::nil()
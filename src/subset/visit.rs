@@ -0,0 +1,82 @@
+use crate::subset::ast::*;
+use std::rc::Rc;
+
+/// Call `f` on every direct subexpression of `expr`.
+pub fn walk_expr<F>(expr: &Expr, mut f: F)
+where
+    F: FnMut(&Expr),
+{
+    match expr {
+        Expr::X | Expr::Ref(_) | Expr::Int(_) | Expr::ULit(_, _) | Expr::Str(_) => (),
+        Expr::Signed(e) | Expr::Unop(_, e) | Expr::Repeat(_, e) => f(e),
+        Expr::IPath(_, index) => {
+            if let Some(e) = index {
+                f(e)
+            }
+        }
+        Expr::Binop(_, lhs, rhs) => {
+            f(lhs);
+            f(rhs);
+        }
+        Expr::Terop(_, a, b, c) => {
+            f(a);
+            f(b);
+            f(c);
+        }
+        Expr::Concat(concat) => concat.exprs().iter().for_each(f),
+        Expr::Call(_, args) => args.iter().for_each(f),
+    }
+}
+
+/// Call `f` on every parameter value and port connection of `inst`, in the
+/// order they are printed.
+pub fn walk_instance<F>(inst: &Instance, mut f: F)
+where
+    F: FnMut(&Expr),
+{
+    inst.params.values().for_each(&mut f);
+    match &inst.ports {
+        Connections::Named(map) => map.values().for_each(f),
+        Connections::Positional(exprs) => exprs.iter().for_each(f),
+    }
+}
+
+/// Call `f` on every direct subexpression of `expr`, shared subexpressions
+/// are cloned before they are handed out.
+pub fn walk_expr_mut<F>(expr: &mut Expr, mut f: F)
+where
+    F: FnMut(&mut Expr),
+{
+    match expr {
+        Expr::X | Expr::Ref(_) | Expr::Int(_) | Expr::ULit(_, _) | Expr::Str(_) => (),
+        Expr::Signed(e) | Expr::Unop(_, e) | Expr::Repeat(_, e) => f(Rc::make_mut(e)),
+        Expr::IPath(_, index) => {
+            if let Some(e) = index {
+                f(Rc::make_mut(e))
+            }
+        }
+        Expr::Binop(_, lhs, rhs) => {
+            f(Rc::make_mut(lhs));
+            f(Rc::make_mut(rhs));
+        }
+        Expr::Terop(_, a, b, c) => {
+            f(Rc::make_mut(a));
+            f(Rc::make_mut(b));
+            f(Rc::make_mut(c));
+        }
+        Expr::Concat(concat) => concat.exprs.iter_mut().for_each(f),
+        Expr::Call(_, args) => args.iter_mut().for_each(f),
+    }
+}
+
+/// Call `f` on every parameter value and port connection of `inst`.
+pub fn walk_instance_mut<F>(inst: &mut Instance, mut f: F)
+where
+    F: FnMut(&mut Expr),
+{
+    inst.params.values_mut().for_each(&mut f);
+    match &mut inst.ports {
+        Connections::Named(map) => map.values_mut().for_each(f),
+        Connections::Positional(exprs) => exprs.iter_mut().for_each(f),
+    }
+}
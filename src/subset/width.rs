@@ -0,0 +1,347 @@
+use crate::subset::ast::*;
+use crate::subset::eval::{Env, Value, MAX_WIDTH};
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WidthError {
+    /// A reference to a name that is not declared.
+    Undeclared(Id),
+    /// An assignment that truncates its right hand side.
+    Assign {
+        lexpr: Expr,
+        rexpr: Expr,
+        expected: u64,
+        found: u64,
+    },
+    /// An instance port connected to an expression of a different width.
+    Port {
+        instance: Id,
+        port: Id,
+        expected: u64,
+        found: u64,
+    },
+}
+
+impl fmt::Display for WidthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WidthError::Undeclared(id) => write!(f, "`{}` is not declared", id),
+            WidthError::Assign {
+                lexpr,
+                rexpr,
+                expected,
+                found,
+            } => write!(
+                f,
+                "assigning `{}` to `{}`: expected {} bits, found {}",
+                rexpr, lexpr, expected, found
+            ),
+            WidthError::Port {
+                instance,
+                port,
+                expected,
+                found,
+            } => write!(
+                f,
+                "port `{}` of instance `{}`: expected {} bits, found {}",
+                port, instance, expected, found
+            ),
+        }
+    }
+}
+
+impl error::Error for WidthError {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Signal {
+    width: Option<u64>,
+    /// Width after each successive index, further indices select a bit.
+    selects: Vec<Option<u64>>,
+}
+
+/// Declarations visible to width inference.
+#[derive(Clone, Debug, Default)]
+pub struct Scope {
+    signals: HashMap<Id, Signal>,
+    functions: HashMap<Id, u64>,
+    // width of user-defined types, `None` when it is not constant
+    types: HashMap<Id, Option<u64>>,
+    env: Env,
+}
+
+/// The wider of two widths, an unknown width takes the other one.
+fn max(lhs: Option<u64>, rhs: Option<u64>) -> Option<u64> {
+    match (lhs, rhs) {
+        (Some(l), Some(r)) => Some(l.max(r)),
+        (l, r) => l.or(r),
+    }
+}
+
+/// Number of bits needed to hold a non-negative integer.
+fn min_width(n: i32) -> u64 {
+    (32 - n.leading_zeros()).max(1) as u64
+}
+
+impl Scope {
+    pub fn new() -> Scope {
+        Scope::default()
+    }
+
+    pub fn env(&self) -> &Env {
+        &self.env
+    }
+
+    pub fn add_signal(&mut self, id: &str, width: u64) {
+        let signal = Signal {
+            width: Some(width),
+            selects: Vec::new(),
+        };
+        self.signals.insert(id.to_string(), signal);
+    }
+
+    /// Add a signal whose width is not known, i.e. a range that depends on
+    /// something other than parameters.
+    pub fn add_unsized(&mut self, id: &str) {
+        let signal = Signal {
+            width: None,
+            selects: Vec::new(),
+        };
+        self.signals.insert(id.to_string(), signal);
+    }
+
+    /// Add an array, indexing it yields an element of `width` bits.
+    pub fn add_array(&mut self, id: &str, width: u64) {
+        let signal = Signal {
+            width: Some(width),
+            selects: vec![Some(width)],
+        };
+        self.signals.insert(id.to_string(), signal);
+    }
+
+    /// Add an array whose elements have an unknown width.
+    pub fn add_unsized_array(&mut self, id: &str) {
+        let signal = Signal {
+            width: None,
+            selects: vec![None],
+        };
+        self.signals.insert(id.to_string(), signal);
+    }
+
+    /// Add a signal with several dimensions, `selects` has the width after
+    /// each index, i.e. `[Some(8)]` for `logic [3:0][7:0] x`.
+    pub fn add_dims(&mut self, id: &str, width: Option<u64>, selects: Vec<Option<u64>>) {
+        let signal = Signal { width, selects };
+        self.signals.insert(id.to_string(), signal);
+    }
+
+    /// Width of the range `[msb:lsb]`, if both bounds are constant in this
+    /// scope.
+    pub fn range_width(&self, msb: &Expr, lsb: &Expr) -> Option<u64> {
+        let msb = msb.eval(&self.env).ok()?.to_i128();
+        let lsb = lsb.eval(&self.env).ok()?.to_i128();
+        Some((msb - lsb).unsigned_abs() as u64 + 1)
+    }
+
+    pub fn add_param(&mut self, id: &str, value: &Expr) {
+        self.env.insert(id.to_string(), value.clone());
+    }
+
+    /// Add a parameter of a vector type, a constant value is converted to
+    /// `width` bits with the signedness of the type.
+    pub fn add_typed_param(&mut self, id: &str, value: &Expr, width: u64, signed: bool) {
+        let value = match value.eval(&self.env) {
+            Ok(v) if width <= MAX_WIDTH as u64 => {
+                let v = v.resize(width as u32);
+                if signed {
+                    Expr::from(Value::new_signed(v.width(), v.to_u128()))
+                } else {
+                    Expr::from(Value::new(v.width(), v.to_u128()))
+                }
+            }
+            _ => value.clone(),
+        };
+        self.env.insert(id.to_string(), value);
+    }
+
+    /// Add a function returning `width` bits.
+    pub fn add_function(&mut self, id: &str, width: u64) {
+        self.functions.insert(id.to_string(), width);
+    }
+
+    /// Add a user-defined type of `width` bits.
+    pub fn add_type(&mut self, id: &str, width: Option<u64>) {
+        self.types.insert(id.to_string(), width);
+    }
+
+    /// Width of a user-defined type, if it is known.
+    pub fn type_width(&self, id: &str) -> Option<u64> {
+        self.types.get(id).copied().flatten()
+    }
+
+    /// Add every name of `other` with `prefix` in front of it, i.e. `pkg::`
+    /// for the names of a package. Parameter values are folded in `other`
+    /// so that they do not refer to names missing from this scope.
+    pub fn import(&mut self, other: &Scope, prefix: &str) {
+        let name = |id: &Id| format!("{}{}", prefix, id);
+        for (id, signal) in &other.signals {
+            self.signals.insert(name(id), signal.clone());
+        }
+        for (id, width) in &other.functions {
+            self.functions.insert(name(id), *width);
+        }
+        for (id, width) in &other.types {
+            self.types.insert(name(id), *width);
+        }
+        for (id, value) in &other.env {
+            self.env.insert(name(id), value.simplify(&other.env));
+        }
+    }
+
+    /// Check that `rexpr` fits in `lexpr`, a narrower `rexpr` is extended
+    /// and only a wider one is reported since it is truncated.
+    pub fn check_assign(&self, lexpr: &Expr, rexpr: &Expr) -> Result<(), WidthError> {
+        let expected = lexpr.width(self)?;
+        let found = match rexpr {
+            Expr::Int(n) if *n >= 0 => expected
+                .filter(|w| min_width(*n) > *w)
+                .map(|_| min_width(*n)),
+            _ => rexpr.width(self)?,
+        };
+        match (expected, found) {
+            (Some(expected), Some(found)) if found > expected => Err(WidthError::Assign {
+                lexpr: lexpr.clone(),
+                rexpr: rexpr.clone(),
+                expected,
+                found,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Expr {
+    /// Infer the self-determined width of the expression. The width is
+    /// `None` for unsized integers and for expressions whose width is not
+    /// known, i.e. system calls and non-constant slices.
+    pub fn width(&self, scope: &Scope) -> Result<Option<u64>, WidthError> {
+        let eval = |e: &Expr| e.eval(&scope.env).ok().map(|v| v.to_i128());
+        match self {
+            Expr::X | Expr::Int(_) | Expr::Str(_) => Ok(None),
+            // a member of an interface is added as `inst.name`, other paths
+            // point into instances whose widths are not known
+            Expr::IPath(path, index) => {
+                let signal = scope.signals.get(&path.path().join("."));
+                match (signal, index) {
+                    (Some(signal), None) => Ok(signal.width),
+                    (Some(signal), Some(index)) => {
+                        index.width(scope)?;
+                        Ok(signal.selects.first().copied().unwrap_or(Some(1)))
+                    }
+                    (None, _) => Ok(None),
+                }
+            }
+            Expr::ULit(value, _) => Ok(Some(value.width() as u64)),
+            Expr::Ref(id) => {
+                if let Some(signal) = scope.signals.get(id) {
+                    Ok(signal.width)
+                } else if scope.env.contains_key(id) {
+                    // parameters without a sized value behave as integers
+                    Ok(self
+                        .eval(&scope.env)
+                        .ok()
+                        .filter(|v| !(v.is_signed() && v.width() == 32))
+                        .map(|v| v.width() as u64))
+                } else {
+                    Err(WidthError::Undeclared(id.clone()))
+                }
+            }
+            Expr::Signed(e) => e.width(scope),
+            Expr::Unop(Unop::Not, e) | Expr::Unop(Unop::Neg, e) => e.width(scope),
+            Expr::Unop(_, e) => e.width(scope).map(|_| Some(1)),
+            Expr::Binop(op, lhs, rhs) => {
+                let l = lhs.width(scope)?;
+                let r = rhs.width(scope)?;
+                match op {
+                    Binop::Add
+                    | Binop::Sub
+                    | Binop::Mul
+                    | Binop::Div
+                    | Binop::Mod
+                    | Binop::BitAnd
+                    | Binop::BitOr
+                    | Binop::BitXor
+                    | Binop::BitXnor => Ok(max(l, r)),
+                    Binop::Pow
+                    | Binop::ShiftLeft
+                    | Binop::ShiftRight
+                    | Binop::ArithShiftLeft
+                    | Binop::ArithShiftRight => Ok(l),
+                    Binop::IndexBit => {
+                        // count the indices of a chained select `x[i][j]`
+                        let mut base = lhs.as_ref();
+                        let mut depth = 0;
+                        while let Expr::Binop(Binop::IndexBit, e, _) = base {
+                            base = e.as_ref();
+                            depth += 1;
+                        }
+                        match base {
+                            Expr::Ref(id) => match scope.signals.get(id) {
+                                Some(signal) => {
+                                    Ok(signal.selects.get(depth).copied().unwrap_or(Some(1)))
+                                }
+                                None => Ok(Some(1)),
+                            },
+                            _ => Ok(Some(1)),
+                        }
+                    }
+                    Binop::LogOr
+                    | Binop::LogAnd
+                    | Binop::Gt
+                    | Binop::Lt
+                    | Binop::Geq
+                    | Binop::Leq
+                    | Binop::Equal
+                    | Binop::NotEqual
+                    | Binop::CaseEqual
+                    | Binop::CaseNotEqual => Ok(Some(1)),
+                }
+            }
+            Expr::Terop(Terop::Mux, cond, tru, fal) => {
+                cond.width(scope)?;
+                Ok(max(tru.width(scope)?, fal.width(scope)?))
+            }
+            Expr::Terop(Terop::Slice, e, hi, lo) => {
+                e.width(scope)?;
+                hi.width(scope)?;
+                lo.width(scope)?;
+                match (eval(hi), eval(lo)) {
+                    (Some(hi), Some(lo)) if hi >= lo => Ok(Some((hi - lo + 1) as u64)),
+                    _ => Ok(None),
+                }
+            }
+            Expr::Terop(Terop::IndexSlice, e, lo, width) => {
+                e.width(scope)?;
+                lo.width(scope)?;
+                width.width(scope)?;
+                Ok(eval(width).filter(|w| *w > 0).map(|w| w as u64))
+            }
+            Expr::Concat(c) => {
+                let mut sum = Some(0);
+                for expr in c.exprs() {
+                    let width = expr.width(scope)?;
+                    sum = sum.and_then(|s| width.map(|w| s + w));
+                }
+                Ok(sum)
+            }
+            Expr::Repeat(times, e) => Ok(e.width(scope)?.map(|w| w * *times)),
+            Expr::Call(name, args) => {
+                for arg in args {
+                    arg.width(scope)?;
+                }
+                Ok(scope.functions.get(name).copied())
+            }
+        }
+    }
+}
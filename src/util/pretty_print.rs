@@ -1,21 +1,62 @@
+use crate::Error;
 use pretty::RcDoc;
+use std::io;
 
 pub const PRETTY_WIDTH: usize = 100;
 pub const PRETTY_INDENT: isize = 4;
 
-pub trait PrettyPrint {
-    fn to_doc(&self) -> RcDoc<()>;
+/// How a module declares its ports.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PortStyle {
+    /// Directions and types in the header, `module m (input wire a);`.
+    #[default]
+    Ansi,
+    /// Only the names in the header, `module m (a);`, the directions and
+    /// types are declared in the body.
+    NonAnsi,
+}
 
+pub trait PrettyPrint {
+    fn to_doc(&self) -> RcDoc<'_, ()>;
+
+    /// Print the node, see `try_to_pretty_with_width` for a version that
+    /// returns an error instead.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node fails `validate`.
     fn to_pretty_with_width(&self, width: usize) -> String {
-        let mut w = Vec::new();
-        self.to_doc().render(width, &mut w).unwrap();
-        String::from_utf8(w).unwrap()
+        match self.try_to_pretty_with_width(width) {
+            Ok(pretty) => pretty,
+            Err(err) => panic!("Error: {}", err),
+        }
     }
 
+    /// # Panics
+    ///
+    /// Panics if the node fails `validate`.
     fn to_pretty(&self) -> String {
         self.to_pretty_with_width(PRETTY_WIDTH)
     }
 
+    /// Check that the node can be printed as valid Verilog, the `to_doc`
+    /// method may panic on nodes that fail this check.
+    fn validate(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn try_to_pretty_with_width(&self, width: usize) -> Result<String, Error> {
+        self.validate()?;
+        let mut w = Vec::new();
+        self.to_doc().render(width, &mut w)?;
+        String::from_utf8(w)
+            .map_err(|err| Error::Io(io::Error::new(io::ErrorKind::InvalidData, err)))
+    }
+
+    fn try_to_pretty(&self) -> Result<String, Error> {
+        self.try_to_pretty_with_width(PRETTY_WIDTH)
+    }
+
     fn render_fmt(&self, f: &mut impl std::fmt::Write) -> std::fmt::Result {
         let doc = self.to_doc();
         doc.render_fmt(PRETTY_WIDTH, f)
@@ -57,9 +98,21 @@ pub trait PrettyHelper<'a>: Sized {
         self.surround("function", "endfunction")
     }
 
+    fn task_endtask(self) -> Self {
+        self.surround("task", "endtask")
+    }
+
     fn module_endmodule(self) -> Self {
         self.surround("module", "endmodule")
     }
+
+    fn package_endpackage(self) -> Self {
+        self.surround("package", "endpackage")
+    }
+
+    fn interface_endinterface(self) -> Self {
+        self.surround("interface", "endinterface")
+    }
 }
 
 impl<'a, A> PrettyHelper<'a> for RcDoc<'a, A> {
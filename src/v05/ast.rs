@@ -1,69 +1,132 @@
 use crate::subset;
+use std::collections::BTreeMap;
 use std::rc::Rc;
 
 pub type Id = subset::ast::Id;
 pub type Expr = subset::ast::Expr;
+pub type Unop = subset::ast::Unop;
+pub type Binop = subset::ast::Binop;
+pub type Terop = subset::ast::Terop;
+pub type Bit = subset::bitvec::Bit;
+pub type BitVec = subset::bitvec::BitVec;
+pub type BitVecError = subset::bitvec::BitVecError;
+pub type Radix = subset::ast::Radix;
+pub type Env = subset::eval::Env;
+pub type EvalError = subset::eval::EvalError;
+pub type Value = subset::eval::Value;
 pub type ExprConcat = subset::ast::ExprConcat;
 pub type EventTy = subset::ast::EventTy;
 pub type Attribute = subset::ast::Attribute;
 pub type AttributeTy = subset::ast::AttributeTy;
 pub type AssignTy = subset::ast::AssignTy;
 pub type Instance = subset::ast::Instance;
+pub type Map = subset::ast::Map;
+pub type MapIter<'a> = subset::ast::MapIter<'a>;
+pub type Connections = subset::ast::Connections;
 pub type Stmt = subset::ast::GenericStmt<Decl, Parallel>;
+pub type GenerateBlock = subset::ast::GenericGenerateBlock<Decl, Parallel>;
+pub type GenerateFor = subset::ast::GenericGenerateFor<Decl, Parallel>;
+pub type GenerateIf = subset::ast::GenericGenerateIf<Decl, Parallel>;
 pub type Port = subset::ast::GenericPort<Decl>;
 pub type Module = subset::ast::GenericModule<Decl, Parallel>;
 pub type CaseBranch = subset::ast::GenericCaseBranch<Sequential>;
 pub type CaseDefault = subset::ast::GenericCaseDefault<Sequential>;
 pub type Case = subset::ast::GenericCase<Sequential>;
+pub type Function = subset::ast::GenericFunction<FunctionTy, Decl, Sequential, Ty>;
+// tasks do not return a value
+pub type Task = subset::ast::GenericFunction<FunctionTy, Decl, Sequential, ()>;
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Ty {
     Int,
     // maybe use nonzero64?
     Width(u64),
+    /// A range `[msb:lsb]` whose bounds may refer to parameters.
+    Range(Expr, Expr),
+    /// A signed vector, i.e. `signed [7:0]`.
+    Signed(Rc<Ty>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum FunctionTy {
+    Default,
+    Automatic,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Decl {
     Int(Id, Ty),
     Wire(Id, Ty),
+    /// A wire with a net declaration assignment, i.e. `wire [7:0] a = b`.
+    WireAssign(Id, Ty, Expr),
     Reg(Id, Ty),
     Array(Id, Ty, Ty),
-    Param(Id, Expr),
+    /// A parameter with an optional type, i.e. `parameter [7:0] W = 8`, a
+    /// parameter without a type takes the width of its value.
+    Param(Id, Option<Ty>, Expr),
+    /// A parameter that can not be set by an instance, `localparam`.
+    LocalParam(Id, Option<Ty>, Expr),
+    Genvar(Id),
+    Func(Function),
+    Task(Task),
     AttributeDecl(Attribute, Rc<Decl>),
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub struct SequentialIfElse {
     pub cond: Option<Expr>,
     pub body: Vec<Sequential>,
     pub elsebr: Option<Rc<Sequential>>,
 }
 
-#[derive(Clone, Debug)]
+/// A loop `for (var = init; cond; var = step)`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SequentialFor {
+    pub var: Id,
+    pub init: Expr,
+    pub cond: Expr,
+    pub step: Expr,
+    pub body: Vec<Sequential>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Sequential {
     Wildcard,
     Event(EventTy, Expr),
     Assign(Expr, Expr, AssignTy),
     IfElse(SequentialIfElse),
     SeqCase(Case),
+    Call(Expr),
+    Delay(Expr),
+    EventControl(EventTy, Expr),
+    For(SequentialFor),
+    While(Expr, Vec<Sequential>),
+    Repeat(Expr, Vec<Sequential>),
+    Forever(Vec<Sequential>),
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ProcessTy {
     Always,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct ParallelProcess {
     pub ty: ProcessTy,
     pub event: Option<Sequential>,
     pub body: Vec<Sequential>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Parallel {
     Inst(Instance),
     Assign(Expr, Expr),
     Process(ParallelProcess),
 }
+
+/// A whole design, its modules keyed by name.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Design {
+    /// Modules of the design.
+    pub modules: BTreeMap<Id, Module>,
+}
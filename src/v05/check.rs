@@ -0,0 +1,330 @@
+use crate::subset;
+use crate::subset::instance::{check_instance, connections, instance_scope, Definition};
+use crate::v05::ast::*;
+use crate::v05::visit::{
+    walk_decl, walk_function, walk_module, walk_parallel, walk_sequential, walk_task, Visitor,
+};
+use crate::Error;
+
+pub type Scope = subset::width::Scope;
+pub type WidthError = subset::width::WidthError;
+
+fn ty_width(scope: &Scope, ty: &Ty) -> Option<u64> {
+    match ty {
+        Ty::Int => Some(32),
+        Ty::Width(w) => Some(*w),
+        Ty::Range(msb, lsb) => scope.range_width(msb, lsb),
+        Ty::Signed(ty) => ty_width(scope, ty),
+    }
+}
+
+/// Add a signal of type `ty`, a range that is not constant gives a signal
+/// of unknown width.
+fn add_typed(scope: &mut Scope, id: &str, ty: &Ty) {
+    match ty_width(scope, ty) {
+        Some(width) => scope.add_signal(id, width),
+        None => scope.add_unsized(id),
+    }
+}
+
+/// Add a parameter, a typed parameter is converted to its type.
+fn add_param(scope: &mut Scope, id: &str, ty: Option<&Ty>, expr: &Expr) {
+    match ty.and_then(|ty| Some((ty, ty_width(scope, ty)?))) {
+        Some((ty, width)) => scope.add_typed_param(id, expr, width, ty.is_signed()),
+        None => scope.add_param(id, expr),
+    }
+}
+
+pub(crate) fn decl_id(decl: &Decl) -> &Id {
+    match decl {
+        Decl::Int(id, _)
+        | Decl::Wire(id, _)
+        | Decl::WireAssign(id, _, _)
+        | Decl::Reg(id, _)
+        | Decl::Array(id, _, _)
+        | Decl::Param(id, _, _)
+        | Decl::LocalParam(id, _, _)
+        | Decl::Genvar(id) => id,
+        Decl::Func(func) => &func.name,
+        Decl::Task(task) => &task.name,
+        Decl::AttributeDecl(_, decl) => decl_id(decl),
+    }
+}
+
+fn add_decl(scope: &mut Scope, decl: &Decl) {
+    match decl {
+        Decl::Int(id, ty)
+        | Decl::Wire(id, ty)
+        | Decl::WireAssign(id, ty, _)
+        | Decl::Reg(id, ty) => add_typed(scope, id, ty),
+        Decl::Array(id, ty, _) => match ty_width(scope, ty) {
+            Some(width) => scope.add_array(id, width),
+            None => scope.add_unsized_array(id),
+        },
+        Decl::Param(id, ty, expr) | Decl::LocalParam(id, ty, expr) => {
+            add_param(scope, id, ty.as_ref(), expr)
+        }
+        Decl::Genvar(id) => scope.add_signal(id, 32),
+        Decl::Func(func) => {
+            if let Some(width) = ty_width(scope, &func.ret) {
+                scope.add_function(&func.name, width);
+            }
+        }
+        Decl::Task(_) => (),
+        Decl::AttributeDecl(_, decl) => add_decl(scope, decl),
+    }
+}
+
+fn add_port(scope: &mut Scope, port: &Port) {
+    match port {
+        Port::Input(decl) | Port::Output(decl) | Port::Inout(decl) => add_decl(scope, decl),
+    }
+}
+
+/// Add the declarations of a module body, declarations in generate blocks
+/// share the scope of the module.
+fn add_stmts(scope: &mut Scope, body: &[Stmt]) {
+    for stmt in body {
+        match stmt {
+            Stmt::Decl(decl) => add_decl(scope, decl),
+            Stmt::GenFor(gen) => add_stmts(scope, gen.block().body()),
+            Stmt::GenIf(gen) => {
+                add_stmts(scope, gen.block().body());
+                if let Some(block) = gen.else_branch() {
+                    add_stmts(scope, block.body());
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Collect the parameters, ports and declarations of a module.
+pub fn module_scope(module: &Module) -> Scope {
+    let mut scope = Scope::new();
+    for param in module.params() {
+        add_decl(&mut scope, param);
+    }
+    for port in module.ports() {
+        add_port(&mut scope, port);
+    }
+    add_stmts(&mut scope, module.body());
+    scope
+}
+
+struct Checker<'a> {
+    scope: Scope,
+    defs: &'a [Module],
+    errors: Vec<WidthError>,
+}
+
+impl<'a> Checker<'a> {
+    fn check(&mut self, res: Result<(), WidthError>) {
+        if let Err(err) = res {
+            self.errors.push(err);
+        }
+    }
+}
+
+impl<'a> Visitor for Checker<'a> {
+    fn visit_function(&mut self, func: &Function) {
+        let outer = self.scope.clone();
+        for port in func.ports() {
+            add_port(&mut self.scope, port);
+        }
+        for decl in func.decls() {
+            add_decl(&mut self.scope, decl);
+        }
+        // the result is assigned to the name of the function
+        add_typed(&mut self.scope, &func.name, &func.ret);
+        walk_function(self, func);
+        self.scope = outer;
+    }
+
+    fn visit_task(&mut self, task: &Task) {
+        let outer = self.scope.clone();
+        for port in task.ports() {
+            add_port(&mut self.scope, port);
+        }
+        for decl in task.decls() {
+            add_decl(&mut self.scope, decl);
+        }
+        walk_task(self, task);
+        self.scope = outer;
+    }
+
+    fn visit_decl(&mut self, decl: &Decl) {
+        match decl {
+            Decl::WireAssign(id, _, expr) => {
+                let res = self.scope.check_assign(&Expr::new_ref(id), expr);
+                self.check(res);
+            }
+            _ => walk_decl(self, decl),
+        }
+    }
+
+    fn visit_parallel(&mut self, par: &Parallel) {
+        match par {
+            Parallel::Assign(lexpr, rexpr) => {
+                let res = self.scope.check_assign(lexpr, rexpr);
+                self.check(res);
+            }
+            _ => walk_parallel(self, par),
+        }
+    }
+
+    fn visit_instance(&mut self, inst: &Instance) {
+        for expr in inst.params.values() {
+            self.visit_expr(expr);
+        }
+        let def = self.defs.iter().find(|def| def.name == inst.prim);
+        // the widths of the ports follow the parameter values of the instance
+        let scope = def.map(|def| instance_scope(def, inst, &self.scope, module_scope));
+        for (port, expr) in connections(inst, def) {
+            let found = match expr.width(&self.scope) {
+                Ok(found) => found,
+                Err(err) => {
+                    self.errors.push(err);
+                    continue;
+                }
+            };
+            // ports that are not declared are reported by `check_instances`
+            let expected = match (port, &scope) {
+                (Some(port), Some(scope)) => Expr::new_ref(port).width(scope).ok().flatten(),
+                _ => None,
+            };
+            if let (Some(port), Some(expected), Some(found)) = (port, expected, found) {
+                if expected != found {
+                    self.errors.push(WidthError::Port {
+                        instance: inst.id.clone(),
+                        port: port.clone(),
+                        expected,
+                        found,
+                    });
+                }
+            }
+        }
+    }
+
+    fn visit_sequential(&mut self, seq: &Sequential) {
+        match seq {
+            Sequential::Assign(lexpr, rexpr, _) => {
+                let res = self.scope.check_assign(lexpr, rexpr);
+                self.check(res);
+            }
+            _ => walk_sequential(self, seq),
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        let res = expr.width(&self.scope).map(|_| ());
+        self.check(res);
+    }
+}
+
+/// Check the widths of assignments and instance connections in a module,
+/// instance ports are checked against the modules in `defs` with the
+/// parameter values of the instance.
+pub fn check_widths(module: &Module, defs: &[Module]) -> Result<(), Vec<WidthError>> {
+    let mut checker = Checker {
+        scope: module_scope(module),
+        defs,
+        errors: Vec::new(),
+    };
+    walk_module(&mut checker, module);
+    if checker.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(checker.errors)
+    }
+}
+
+impl Definition for Module {
+    fn name(&self) -> &Id {
+        &self.name
+    }
+
+    fn port_names(&self) -> Vec<&Id> {
+        self.ports()
+            .iter()
+            .map(|port| decl_id(port.decl()))
+            .collect()
+    }
+
+    fn input_names(&self) -> Vec<&Id> {
+        self.ports()
+            .iter()
+            .filter_map(|port| match port {
+                Port::Input(decl) => Some(decl_id(decl)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn param_names(&self) -> Vec<&Id> {
+        let body = self.body().iter().filter_map(|stmt| match stmt {
+            Stmt::Decl(decl) => Some(decl),
+            _ => None,
+        });
+        self.params()
+            .iter()
+            .chain(body)
+            .filter_map(|decl| match decl {
+                Decl::Param(id, _, _) => Some(id),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn set_params(&mut self, values: &Map) {
+        let body = self.body.iter_mut().filter_map(|stmt| match stmt {
+            Stmt::Decl(decl) => Some(decl),
+            _ => None,
+        });
+        for decl in self.params.iter_mut().chain(body) {
+            if let Decl::Param(id, _, expr) = decl {
+                if let Some(value) = values.get(id) {
+                    *expr = value.clone();
+                }
+            }
+        }
+    }
+}
+
+struct InstanceChecker<'a> {
+    defs: &'a [&'a Module],
+    errors: Vec<Error>,
+}
+
+impl<'a> Visitor for InstanceChecker<'a> {
+    fn visit_instance(&mut self, inst: &Instance) {
+        if let Some(def) = self.defs.iter().find(|def| def.name == inst.prim) {
+            self.errors.extend(check_instance(inst, *def));
+        }
+    }
+}
+
+/// Errors of the instances in `module`, instances of modules missing from
+/// `defs` are not checked.
+pub(crate) fn instance_errors(module: &Module, defs: &[&Module]) -> Vec<Error> {
+    let mut checker = InstanceChecker {
+        defs,
+        errors: Vec::new(),
+    };
+    walk_module(&mut checker, module);
+    checker.errors
+}
+
+/// Check the instances of a module against the modules in `defs`: every
+/// connection is a port of the module, every input is connected and every
+/// parameter set is declared. The widths of the connections are checked by
+/// [`check_widths`].
+pub fn check_instances(module: &Module, defs: &[Module]) -> Result<(), Vec<Error>> {
+    let defs: Vec<&Module> = defs.iter().collect();
+    let errors = instance_errors(module, &defs);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
@@ -0,0 +1,186 @@
+use crate::subset::instance::Definition;
+use crate::util::pretty_print::PrettyPrint;
+use crate::v05::ast::*;
+use crate::v05::check::{decl_id, instance_errors};
+use crate::v05::visit::{walk_instance, Visitor};
+use crate::v05::visit_mut::VisitorMut;
+use crate::Error;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Collect the names of the modules instantiated by a module.
+#[derive(Default)]
+struct Deps {
+    defs: BTreeSet<Id>,
+}
+
+impl Visitor for Deps {
+    fn visit_instance(&mut self, inst: &Instance) {
+        self.defs.insert(inst.prim.clone());
+        walk_instance(self, inst)
+    }
+}
+
+fn deps(module: &Module) -> BTreeSet<Id> {
+    let mut deps = Deps::default();
+    deps.visit_module(module);
+    deps.defs
+}
+
+/// Parameter and port names of each module, in order.
+type Orders = BTreeMap<Id, (Vec<Id>, Vec<Id>)>;
+
+/// Reorder the connections of instances as the parameters and ports of
+/// their modules.
+struct Order<'a> {
+    orders: &'a Orders,
+}
+
+impl<'a> VisitorMut for Order<'a> {
+    fn visit_instance_mut(&mut self, inst: &mut Instance) {
+        if let Some((params, ports)) = self.orders.get(&inst.prim) {
+            inst.params.sort_by_names(params);
+            if let Connections::Named(map) = &mut inst.ports {
+                map.sort_by_names(ports);
+            }
+        }
+    }
+}
+
+impl Design {
+    pub fn new() -> Design {
+        Design::default()
+    }
+
+    pub fn modules(&self) -> &BTreeMap<Id, Module> {
+        &self.modules
+    }
+
+    pub fn module(&self, name: &str) -> Option<&Module> {
+        self.modules.get(name)
+    }
+
+    pub fn add_module(&mut self, module: Module) -> Result<&mut Self, Error> {
+        if self.modules.contains_key(&module.name) {
+            return Err(Error::Duplicate(module.name));
+        }
+        self.modules.insert(module.name.clone(), module);
+        Ok(self)
+    }
+
+    /// The module an instance refers to, `None` for an unknown module.
+    pub fn resolve(&self, inst: &Instance) -> Option<&Module> {
+        self.modules.get(&inst.prim)
+    }
+
+    /// Check that every module instantiated is defined in this design.
+    pub fn check_definitions(&self) -> Result<(), Vec<Error>> {
+        let mut errors = Vec::new();
+        for module in self.modules.values() {
+            for name in deps(module) {
+                if !self.modules.contains_key(&name) {
+                    errors.push(Error::Undefined {
+                        unit: module.name.clone(),
+                        name,
+                    });
+                }
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Check every instance of a module of this design against that module,
+    /// see `check::check_instances`.
+    pub fn check_instances(&self) -> Result<(), Vec<Error>> {
+        let defs: Vec<&Module> = self.modules.values().collect();
+        let errors: Vec<Error> = self
+            .modules
+            .values()
+            .flat_map(|module| instance_errors(module, &defs))
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Reorder the named parameters and ports of every instance of a module
+    /// of this design as they are declared by that module.
+    pub fn order_connections(&mut self) {
+        let orders: Orders = self
+            .modules
+            .values()
+            .map(|module| {
+                let params = module.param_names().into_iter().cloned().collect();
+                let ports = module
+                    .ports()
+                    .iter()
+                    .map(|port| decl_id(port.decl()).clone())
+                    .collect();
+                (module.name.clone(), (params, ports))
+            })
+            .collect();
+        let mut order = Order { orders: &orders };
+        for module in self.modules.values_mut() {
+            order.visit_module_mut(module);
+        }
+    }
+
+    /// Modules that are not instantiated by any other module, ordered by
+    /// name.
+    pub fn top_modules(&self) -> Vec<&Module> {
+        let used: BTreeSet<Id> = self.modules.values().flat_map(deps).collect();
+        self.modules
+            .values()
+            .filter(|module| !used.contains(&module.name))
+            .collect()
+    }
+
+    /// Every module ordered so that a module comes after the modules it
+    /// instantiates, ties are broken by name.
+    pub fn units(&self) -> Vec<&Module> {
+        fn visit<'a>(
+            design: &'a Design,
+            module: &'a Module,
+            done: &mut BTreeSet<&'a Id>,
+            order: &mut Vec<&'a Module>,
+        ) {
+            // modules are marked before their instances, which breaks
+            // cycles of recursive instances
+            if !done.insert(&module.name) {
+                return;
+            }
+            for name in deps(module) {
+                if let Some(def) = design.modules.get(&name) {
+                    visit(design, def, done, order);
+                }
+            }
+            order.push(module);
+        }
+        let mut done = BTreeSet::new();
+        let mut order = Vec::new();
+        for module in self.modules.values() {
+            visit(self, module, &mut done, &mut order);
+        }
+        order
+    }
+
+    /// Write every module to its own file `<name>.v` in `dir`, returns the
+    /// paths in dependency order.
+    pub fn write_files<P: AsRef<Path>>(&self, dir: P) -> Result<Vec<PathBuf>, Error> {
+        self.validate()?;
+        let mut paths = Vec::new();
+        for module in self.units() {
+            let path = dir.as_ref().join(format!("{}.v", module.name));
+            fs::write(&path, module.try_to_pretty()?)?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+}
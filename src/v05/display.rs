@@ -55,3 +55,9 @@ impl fmt::Display for Module {
         self.render_fmt(f)
     }
 }
+
+impl fmt::Display for Design {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.render_fmt(f)
+    }
+}
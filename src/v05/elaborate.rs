@@ -0,0 +1,256 @@
+use crate::subset::elaborate::{
+    self, inlined_params, port_connections, prefixed, AsInstance, Hierarchy,
+};
+use crate::subset::instance::Definition;
+use crate::v05::ast::*;
+use crate::v05::check::{decl_id, module_scope, Scope};
+use crate::v05::visit::{walk_decl, walk_generate_block, walk_instance, walk_stmt, Visitor};
+use crate::v05::visit_mut::{
+    walk_decl_mut, walk_expr_mut, walk_generate_block_mut, walk_instance_mut, walk_sequential_mut,
+    walk_stmt_mut, VisitorMut,
+};
+use crate::Error;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Collect the names declared in a module.
+#[derive(Default)]
+struct Names {
+    names: BTreeSet<Id>,
+}
+
+impl Visitor for Names {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::GenFor(gen) = stmt {
+            self.names.insert(gen.var.clone());
+        }
+        walk_stmt(self, stmt)
+    }
+
+    fn visit_decl(&mut self, decl: &Decl) {
+        self.names.insert(decl_id(decl).clone());
+        walk_decl(self, decl)
+    }
+
+    fn visit_generate_block(&mut self, block: &GenerateBlock) {
+        if let Some(label) = &block.label {
+            self.names.insert(label.clone());
+        }
+        walk_generate_block(self, block)
+    }
+
+    fn visit_instance(&mut self, inst: &Instance) {
+        self.names.insert(inst.id.clone());
+        walk_instance(self, inst)
+    }
+}
+
+/// Rename the names declared in a module and their uses.
+struct Rename<'a> {
+    names: &'a HashMap<Id, Id>,
+}
+
+impl<'a> Rename<'a> {
+    fn id(&self, id: &mut Id) {
+        if let Some(name) = self.names.get(id) {
+            *id = name.clone();
+        }
+    }
+
+    /// Rename the first part of a dotted name, i.e. `m` in `m.send`.
+    fn path(&self, name: &mut Id) {
+        let (head, tail) = match name.split_once('.') {
+            Some((head, tail)) => (head, format!(".{}", tail)),
+            None => (name.as_str(), String::new()),
+        };
+        if let Some(head) = self.names.get(head) {
+            *name = format!("{}{}", head, tail);
+        }
+    }
+}
+
+impl<'a> VisitorMut for Rename<'a> {
+    fn visit_stmt_mut(&mut self, stmt: &mut Stmt) {
+        if let Stmt::GenFor(gen) = stmt {
+            self.id(&mut gen.var);
+        }
+        walk_stmt_mut(self, stmt)
+    }
+
+    fn visit_decl_mut(&mut self, decl: &mut Decl) {
+        match decl {
+            Decl::Int(id, _)
+            | Decl::Wire(id, _)
+            | Decl::WireAssign(id, _, _)
+            | Decl::Reg(id, _)
+            | Decl::Array(id, _, _)
+            | Decl::Param(id, _, _)
+            | Decl::LocalParam(id, _, _)
+            | Decl::Genvar(id) => self.id(id),
+            Decl::Func(func) => self.id(&mut func.name),
+            Decl::Task(task) => self.id(&mut task.name),
+            // the declaration is renamed when it is walked
+            Decl::AttributeDecl(_, _) => (),
+        }
+        walk_decl_mut(self, decl)
+    }
+
+    fn visit_generate_block_mut(&mut self, block: &mut GenerateBlock) {
+        if let Some(label) = &mut block.label {
+            self.id(label);
+        }
+        walk_generate_block_mut(self, block)
+    }
+
+    fn visit_instance_mut(&mut self, inst: &mut Instance) {
+        self.id(&mut inst.id);
+        walk_instance_mut(self, inst)
+    }
+
+    fn visit_sequential_mut(&mut self, seq: &mut Sequential) {
+        if let Sequential::For(seq_for) = seq {
+            self.id(&mut seq_for.var);
+        }
+        walk_sequential_mut(self, seq)
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        match expr {
+            Expr::Ref(id) => self.id(id),
+            Expr::Call(name, _) => self.path(name),
+            Expr::IPath(path, _) => {
+                if let Some(head) = path.path.first_mut() {
+                    self.id(head);
+                }
+            }
+            _ => (),
+        }
+        walk_expr_mut(self, expr)
+    }
+}
+
+/// A parameter of an inlined module, it can not be set anymore.
+fn local(decl: Decl) -> Decl {
+    match decl {
+        Decl::Param(id, ty, expr) => Decl::LocalParam(id, ty, expr),
+        decl => decl,
+    }
+}
+
+/// Statements that replace `inst` of `def`, the names declared by `def` are
+/// prefixed by the name of the instance. Ports that are not inputs or
+/// outputs are replaced by the name they are connected to.
+fn inline(inst: &Instance, def: &Module) -> Result<Vec<Stmt>, Error> {
+    let conns = port_connections(inst, def)?;
+    let mut collect = Names::default();
+    collect.visit_module(def);
+    let mut renames = prefixed(inst, collect.names);
+    for port in def.ports() {
+        let id = decl_id(port.decl());
+        match (port, conns.get(id)) {
+            (Port::Input(_), _) | (Port::Output(_), _) => (),
+            (_, Some(Expr::Ref(name))) => {
+                renames.insert(id.clone(), name.clone());
+            }
+            (_, None) => (),
+            (_, Some(expr)) => {
+                return Err(Error::Invalid(format!(
+                    "instance `{}` connects port `{}` to `{}`, which is not a name",
+                    inst.id, id, expr
+                )))
+            }
+        }
+    }
+    let mut module = def.clone();
+    Rename { names: &renames }.visit_module_mut(&mut module);
+    // the values of the instance refer to names outside of the module
+    module.set_params(&inlined_params(inst, &renames));
+    let mut stmts: Vec<Stmt> = module
+        .params
+        .into_iter()
+        .map(|decl| Stmt::Decl(local(decl)))
+        .collect();
+    for (port, renamed) in def.ports().iter().zip(module.ports) {
+        let conn = conns.get(decl_id(port.decl()));
+        let local = Expr::new_ref(decl_id(renamed.decl()));
+        match renamed {
+            Port::Input(decl) => {
+                // an input is driven by an assignment once it is inlined
+                let decl = match decl {
+                    Decl::Reg(id, ty) => Decl::Wire(id, ty),
+                    decl => decl,
+                };
+                stmts.push(Stmt::Decl(decl));
+                if let Some(expr) = conn {
+                    stmts.push(Stmt::Parallel(Parallel::Assign(local, expr.clone())));
+                }
+            }
+            Port::Output(decl) => {
+                stmts.push(Stmt::Decl(decl));
+                if let Some(expr) = conn {
+                    stmts.push(Stmt::Parallel(Parallel::Assign(expr.clone(), local)));
+                }
+            }
+            Port::Inout(decl) if conn.is_none() => stmts.push(Stmt::Decl(decl)),
+            _ => (),
+        }
+    }
+    stmts.extend(module.body.into_iter().map(|stmt| match stmt {
+        Stmt::Decl(decl) => Stmt::Decl(local(decl)),
+        stmt => stmt,
+    }));
+    Ok(stmts)
+}
+
+impl AsInstance for Parallel {
+    fn as_instance_mut(&mut self) -> Option<&mut Instance> {
+        match self {
+            Parallel::Inst(inst) => Some(inst),
+            _ => None,
+        }
+    }
+}
+
+impl Hierarchy<Decl, Parallel, Port> for Design {
+    fn modules(&self) -> &BTreeMap<Id, Module> {
+        &self.modules
+    }
+
+    fn scope(&self, module: &Module) -> Scope {
+        module_scope(module)
+    }
+
+    fn inline(&self, inst: &Instance, def: &Module) -> Result<Vec<Stmt>, Error> {
+        inline(inst, def)
+    }
+}
+
+impl Design {
+    /// Elaborate the hierarchy below `top` with its parameters set to
+    /// `params`. Every module instantiated is specialized for the values
+    /// of its parameters, a module with parameters is renamed after them,
+    /// i.e. `fifo__WIDTH_16__DEPTH_4`. The result holds `top` and the
+    /// specialized modules.
+    pub fn elaborate(&self, top: &str, params: &Map) -> Result<Design, Error> {
+        Ok(Design {
+            modules: elaborate::elaborate(self, top, params)?,
+        })
+    }
+
+    /// Inline the instance `inst` of `module`, together with the hierarchy
+    /// below it. The names of an inlined module are prefixed by the names of
+    /// the instances above them, i.e. `u0__u1__x`, and its parameters are
+    /// turned into local parameters.
+    pub fn flatten(&mut self, module: &str, inst: &str) -> Result<&mut Self, Error> {
+        let flat = elaborate::flatten(self, module, inst)?;
+        self.modules.insert(flat.name.clone(), flat);
+        Ok(self)
+    }
+
+    /// Inline every instance of a module of this design in `module`, see
+    /// `flatten`. The modules that are inlined stay in the design.
+    pub fn flatten_all(&mut self, module: &str) -> Result<&mut Self, Error> {
+        let flat = elaborate::flatten_all(self, module)?;
+        self.modules.insert(flat.name.clone(), flat);
+        Ok(self)
+    }
+}
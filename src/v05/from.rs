@@ -1,4 +1,7 @@
 use crate::v05::ast::*;
+use crate::v05::parser::{parse_design, parse_module, ParseError};
+use crate::Error;
+use std::str::FromStr;
 
 impl From<Instance> for Parallel {
     fn from(inst: Instance) -> Self {
@@ -12,6 +15,24 @@ impl From<Decl> for Stmt {
     }
 }
 
+impl From<Function> for Decl {
+    fn from(func: Function) -> Self {
+        Decl::Func(func)
+    }
+}
+
+impl From<Task> for Decl {
+    fn from(task: Task) -> Self {
+        Decl::Task(task)
+    }
+}
+
+impl From<SequentialFor> for Sequential {
+    fn from(seq: SequentialFor) -> Self {
+        Sequential::For(seq)
+    }
+}
+
 impl From<SequentialIfElse> for Sequential {
     fn from(seq: SequentialIfElse) -> Self {
         Sequential::IfElse(seq)
@@ -41,3 +62,37 @@ impl From<Instance> for Stmt {
         Stmt::from(Parallel::from(inst))
     }
 }
+
+impl From<GenerateFor> for Stmt {
+    fn from(gen: GenerateFor) -> Self {
+        Stmt::GenFor(gen)
+    }
+}
+
+impl From<GenerateIf> for Stmt {
+    fn from(gen: GenerateIf) -> Self {
+        Stmt::GenIf(gen)
+    }
+}
+
+impl From<u64> for Ty {
+    fn from(width: u64) -> Self {
+        Ty::Width(width)
+    }
+}
+
+impl FromStr for Module {
+    type Err = ParseError;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        parse_module(src)
+    }
+}
+
+impl FromStr for Design {
+    type Err = Error;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        parse_design(src)
+    }
+}
@@ -1,4 +1,5 @@
 use crate::v05::ast::*;
+use crate::Error;
 use std::rc::Rc;
 
 impl Ty {
@@ -6,14 +7,57 @@ impl Ty {
         Ty::Int
     }
 
+    /// # Panics
+    ///
+    /// Panics if `width` is zero, see `try_new_width`.
     pub fn new_width(width: u64) -> Ty {
-        assert!(width > 0, "Error: width must be greater than zero");
-        Ty::Width(width)
+        match Ty::try_new_width(width) {
+            Ok(ty) => ty,
+            Err(err) => panic!("Error: {}", err),
+        }
+    }
+
+    pub fn try_new_width(width: u64) -> Result<Ty, Error> {
+        if width > 0 {
+            Ok(Ty::Width(width))
+        } else {
+            Err(Error::invalid("width must be greater than zero"))
+        }
+    }
+
+    /// Create the range `[msb:lsb]`, a constant range `[n:0]` is the same as
+    /// `Ty::Width(n + 1)`.
+    pub fn new_range<M, L>(msb: M, lsb: L) -> Ty
+    where
+        M: Into<Expr>,
+        L: Into<Expr>,
+    {
+        match (msb.into(), lsb.into()) {
+            (Expr::Int(msb), Expr::Int(0)) if msb >= 0 => Ty::Width(msb as u64 + 1),
+            (msb, lsb) => Ty::Range(msb, lsb),
+        }
+    }
+
+    /// Make `ty` signed, integers are already signed and stay the same.
+    pub fn new_signed<T>(ty: T) -> Ty
+    where
+        T: Into<Ty>,
+    {
+        match ty.into() {
+            ty @ Ty::Int | ty @ Ty::Signed(_) => ty,
+            ty => Ty::Signed(Rc::new(ty)),
+        }
+    }
+
+    pub fn is_signed(&self) -> bool {
+        matches!(self, Ty::Int | Ty::Signed(_))
     }
 
     pub fn width(&self) -> u64 {
         match self {
             Ty::Width(w) => *w,
+            Ty::Range(Expr::Int(msb), Expr::Int(lsb)) => (msb - lsb).unsigned_abs() as u64 + 1,
+            Ty::Signed(ty) => ty.width(),
             _ => panic!("Error: type does not support width"),
         }
     }
@@ -24,24 +68,63 @@ impl Decl {
         Decl::Int(name.to_string(), Ty::new_int())
     }
 
-    pub fn new_wire(name: &str, width: u64) -> Decl {
-        Decl::Wire(name.to_string(), Ty::new_width(width))
+    pub fn new_wire<W>(name: &str, width: W) -> Decl
+    where
+        W: Into<Ty>,
+    {
+        Decl::Wire(name.to_string(), width.into())
+    }
+
+    pub fn new_wire_assign<W>(name: &str, width: W, expr: Expr) -> Decl
+    where
+        W: Into<Ty>,
+    {
+        Decl::WireAssign(name.to_string(), width.into(), expr)
     }
 
-    pub fn new_reg(name: &str, width: u64) -> Decl {
-        Decl::Reg(name.to_string(), Ty::new_width(width))
+    pub fn new_reg<W>(name: &str, width: W) -> Decl
+    where
+        W: Into<Ty>,
+    {
+        Decl::Reg(name.to_string(), width.into())
     }
 
-    pub fn new_array(name: &str, width: u64, depth: u64) -> Decl {
-        Decl::Array(name.to_string(), Ty::new_width(width), Ty::new_width(depth))
+    pub fn new_array<W>(name: &str, width: W, depth: u64) -> Decl
+    where
+        W: Into<Ty>,
+    {
+        Decl::Array(name.to_string(), width.into(), Ty::new_width(depth))
     }
 
     pub fn new_param_uint(name: &str, value: u32) -> Decl {
-        Decl::Param(name.to_string(), Expr::new_ulit_dec(32, &value.to_string()))
+        Decl::Param(
+            name.to_string(),
+            None,
+            Expr::new_ulit_dec(32, &value.to_string()),
+        )
     }
 
     pub fn new_param_str(name: &str, value: &str) -> Decl {
-        Decl::Param(name.to_string(), Expr::new_str(value))
+        Decl::Param(name.to_string(), None, Expr::new_str(value))
+    }
+
+    pub fn new_localparam<E>(name: &str, value: E) -> Decl
+    where
+        E: Into<Expr>,
+    {
+        Decl::LocalParam(name.to_string(), None, value.into())
+    }
+
+    pub fn new_genvar(name: &str) -> Decl {
+        Decl::Genvar(name.to_string())
+    }
+
+    pub fn new_func(func: Function) -> Decl {
+        Decl::Func(func)
+    }
+
+    pub fn new_task(task: Task) -> Decl {
+        Decl::Task(task)
     }
 
     pub fn new_attribute_decl(attr: Attribute, decl: Decl) -> Decl {
@@ -50,23 +133,204 @@ impl Decl {
 }
 
 impl Port {
-    pub fn new_input(name: &str, width: u64) -> Port {
-        let ty = Ty::Width(width);
+    pub fn new_input<W>(name: &str, width: W) -> Port
+    where
+        W: Into<Ty>,
+    {
+        let ty = width.into();
         let wire = Decl::Wire(name.to_string(), ty);
         Port::Input(wire)
     }
 
-    pub fn new_output(name: &str, width: u64) -> Port {
-        let ty = Ty::Width(width);
+    pub fn new_output<W>(name: &str, width: W) -> Port
+    where
+        W: Into<Ty>,
+    {
+        let ty = width.into();
         let wire = Decl::Wire(name.to_string(), ty);
         Port::Output(wire)
     }
 
-    pub fn new_output_reg(name: &str, width: u64) -> Port {
-        let ty = Ty::Width(width);
+    pub fn new_signed_input<W>(name: &str, width: W) -> Port
+    where
+        W: Into<Ty>,
+    {
+        Port::new_input(name, Ty::new_signed(width))
+    }
+
+    pub fn new_signed_output<W>(name: &str, width: W) -> Port
+    where
+        W: Into<Ty>,
+    {
+        Port::new_output(name, Ty::new_signed(width))
+    }
+
+    pub fn new_output_reg<W>(name: &str, width: W) -> Port
+    where
+        W: Into<Ty>,
+    {
+        let ty = width.into();
         let reg = Decl::Reg(name.to_string(), ty);
         Port::Output(reg)
     }
+
+    pub fn new_inout<W>(name: &str, width: W) -> Port
+    where
+        W: Into<Ty>,
+    {
+        let ty = width.into();
+        let wire = Decl::Wire(name.to_string(), ty);
+        Port::Inout(wire)
+    }
+}
+
+impl Function {
+    pub fn new(name: &str, ret: Ty) -> Function {
+        Function {
+            ty: FunctionTy::Default,
+            name: name.to_string(),
+            ports: Vec::new(),
+            decls: Vec::new(),
+            body: Vec::new(),
+            ret,
+        }
+    }
+
+    pub fn ports(&self) -> &Vec<Port> {
+        &self.ports
+    }
+
+    pub fn decls(&self) -> &Vec<Decl> {
+        &self.decls
+    }
+
+    pub fn body(&self) -> &Vec<Sequential> {
+        &self.body
+    }
+
+    pub fn ty(&self) -> &FunctionTy {
+        &self.ty
+    }
+
+    pub fn add_input<W>(&mut self, name: &str, width: W) -> &mut Self
+    where
+        W: Into<Ty>,
+    {
+        self.ports.push(Port::Input(Decl::new_reg(name, width)));
+        self
+    }
+
+    pub fn add_input_int(&mut self, name: &str) -> &mut Self {
+        self.ports.push(Port::Input(Decl::new_int(name)));
+        self
+    }
+
+    pub fn add_reg<W>(&mut self, name: &str, width: W) -> &mut Self
+    where
+        W: Into<Ty>,
+    {
+        self.decls.push(Decl::new_reg(name, width));
+        self
+    }
+
+    pub fn add_int(&mut self, name: &str) -> &mut Self {
+        self.decls.push(Decl::new_int(name));
+        self
+    }
+
+    pub fn add_seq<S>(&mut self, seq: S) -> &mut Self
+    where
+        S: Into<Sequential>,
+    {
+        self.body.push(seq.into());
+        self
+    }
+
+    pub fn set_return_type(&mut self, ret: Ty) {
+        self.ret = ret;
+    }
+
+    pub fn set_automatic(&mut self) {
+        self.ty = FunctionTy::Automatic;
+    }
+}
+
+impl Task {
+    pub fn new(name: &str) -> Task {
+        Task {
+            ty: FunctionTy::Default,
+            name: name.to_string(),
+            ports: Vec::new(),
+            decls: Vec::new(),
+            body: Vec::new(),
+            ret: (),
+        }
+    }
+
+    pub fn ports(&self) -> &Vec<Port> {
+        &self.ports
+    }
+
+    pub fn decls(&self) -> &Vec<Decl> {
+        &self.decls
+    }
+
+    pub fn body(&self) -> &Vec<Sequential> {
+        &self.body
+    }
+
+    pub fn ty(&self) -> &FunctionTy {
+        &self.ty
+    }
+
+    pub fn add_input<W>(&mut self, name: &str, width: W) -> &mut Self
+    where
+        W: Into<Ty>,
+    {
+        self.ports.push(Port::Input(Decl::new_reg(name, width)));
+        self
+    }
+
+    pub fn add_output<W>(&mut self, name: &str, width: W) -> &mut Self
+    where
+        W: Into<Ty>,
+    {
+        self.ports.push(Port::Output(Decl::new_reg(name, width)));
+        self
+    }
+
+    pub fn add_inout<W>(&mut self, name: &str, width: W) -> &mut Self
+    where
+        W: Into<Ty>,
+    {
+        self.ports.push(Port::Inout(Decl::new_reg(name, width)));
+        self
+    }
+
+    pub fn add_reg<W>(&mut self, name: &str, width: W) -> &mut Self
+    where
+        W: Into<Ty>,
+    {
+        self.decls.push(Decl::new_reg(name, width));
+        self
+    }
+
+    pub fn add_int(&mut self, name: &str) -> &mut Self {
+        self.decls.push(Decl::new_int(name));
+        self
+    }
+
+    pub fn add_seq<S>(&mut self, seq: S) -> &mut Self
+    where
+        S: Into<Sequential>,
+    {
+        self.body.push(seq.into());
+        self
+    }
+
+    pub fn set_automatic(&mut self) {
+        self.ty = FunctionTy::Automatic;
+    }
 }
 
 impl SequentialIfElse {
@@ -132,6 +396,89 @@ impl Sequential {
     pub fn new_case(case: Case) -> Sequential {
         Sequential::SeqCase(case)
     }
+
+    pub fn new_while<E>(cond: E, body: Vec<Sequential>) -> Sequential
+    where
+        E: Into<Expr>,
+    {
+        Sequential::While(cond.into(), body)
+    }
+
+    pub fn new_repeat<E>(times: E, body: Vec<Sequential>) -> Sequential
+    where
+        E: Into<Expr>,
+    {
+        Sequential::Repeat(times.into(), body)
+    }
+
+    pub fn new_forever(body: Vec<Sequential>) -> Sequential {
+        Sequential::Forever(body)
+    }
+
+    /// Call a task, `Expr::new_call(name, vec![])` calls a task without
+    /// arguments.
+    pub fn new_call<E>(call: E) -> Sequential
+    where
+        E: Into<Expr>,
+    {
+        Sequential::Call(call.into())
+    }
+
+    pub fn new_delay<E>(delay: E) -> Sequential
+    where
+        E: Into<Expr>,
+    {
+        Sequential::Delay(delay.into())
+    }
+
+    /// Wait for the rising edge of `name`, `@(posedge name);`.
+    pub fn new_wait_posedge(name: &str) -> Sequential {
+        Sequential::EventControl(EventTy::Posedge, name.into())
+    }
+
+    /// Wait for the falling edge of `name`, `@(negedge name);`.
+    pub fn new_wait_negedge(name: &str) -> Sequential {
+        Sequential::EventControl(EventTy::Negedge, name.into())
+    }
+}
+
+impl SequentialFor {
+    /// Create a loop over `var` from `init` while `cond` holds, `step` is
+    /// the next value of `var`.
+    pub fn new<I, C, S>(var: &str, init: I, cond: C, step: S) -> SequentialFor
+    where
+        I: Into<Expr>,
+        C: Into<Expr>,
+        S: Into<Expr>,
+    {
+        SequentialFor {
+            var: var.to_string(),
+            init: init.into(),
+            cond: cond.into(),
+            step: step.into(),
+            body: Vec::new(),
+        }
+    }
+
+    /// Create the usual `for (var = 0; var < bound; var = var + 1)` loop.
+    pub fn new_range<E>(var: &str, bound: E) -> SequentialFor
+    where
+        E: Into<Expr>,
+    {
+        SequentialFor::new(var, 0, Expr::new_lt(var, bound), Expr::new_add(var, 1))
+    }
+
+    pub fn body(&self) -> &Vec<Sequential> {
+        &self.body
+    }
+
+    pub fn add_seq<S>(&mut self, seq: S) -> &mut Self
+    where
+        S: Into<Sequential>,
+    {
+        self.body.push(seq.into());
+        self
+    }
 }
 
 impl ParallelProcess {
@@ -185,6 +532,156 @@ impl Parallel {
     }
 }
 
+impl GenerateBlock {
+    pub fn new() -> GenerateBlock {
+        GenerateBlock {
+            label: None,
+            body: Vec::new(),
+        }
+    }
+
+    pub fn new_labeled(label: &str) -> GenerateBlock {
+        GenerateBlock {
+            label: Some(label.to_string()),
+            body: Vec::new(),
+        }
+    }
+
+    pub fn label(&self) -> Option<&Id> {
+        self.label.as_ref()
+    }
+
+    pub fn body(&self) -> &Vec<Stmt> {
+        &self.body
+    }
+
+    pub fn set_label(&mut self, label: &str) {
+        self.label = Some(label.to_string());
+    }
+
+    pub fn add_decl(&mut self, decl: Decl) {
+        self.body.push(Stmt::from(decl));
+    }
+
+    pub fn add_instance(&mut self, inst: Instance) {
+        self.body.push(Stmt::from(inst));
+    }
+
+    pub fn add_stmt<S>(&mut self, stmt: S)
+    where
+        S: Into<Stmt>,
+    {
+        self.body.push(stmt.into());
+    }
+}
+
+impl Default for GenerateBlock {
+    fn default() -> GenerateBlock {
+        GenerateBlock::new()
+    }
+}
+
+impl GenerateFor {
+    /// Create a loop over `var` from `init` while `cond` holds, `step` is
+    /// the next value of `var`.
+    pub fn new<I, C, S>(var: &str, init: I, cond: C, step: S) -> GenerateFor
+    where
+        I: Into<Expr>,
+        C: Into<Expr>,
+        S: Into<Expr>,
+    {
+        GenerateFor {
+            var: var.to_string(),
+            init: init.into(),
+            cond: cond.into(),
+            step: step.into(),
+            block: GenerateBlock::new(),
+        }
+    }
+
+    /// Create the usual `for (var = 0; var < bound; var = var + 1)` loop.
+    pub fn new_range<E>(var: &str, bound: E) -> GenerateFor
+    where
+        E: Into<Expr>,
+    {
+        GenerateFor::new(var, 0, Expr::new_lt(var, bound), Expr::new_add(var, 1))
+    }
+
+    pub fn block(&self) -> &GenerateBlock {
+        &self.block
+    }
+
+    pub fn set_label(&mut self, label: &str) {
+        self.block.set_label(label);
+    }
+
+    pub fn add_decl(&mut self, decl: Decl) {
+        self.block.add_decl(decl);
+    }
+
+    pub fn add_instance(&mut self, inst: Instance) {
+        self.block.add_instance(inst);
+    }
+
+    pub fn add_stmt<S>(&mut self, stmt: S)
+    where
+        S: Into<Stmt>,
+    {
+        self.block.add_stmt(stmt);
+    }
+}
+
+impl GenerateIf {
+    pub fn new<E>(cond: E) -> GenerateIf
+    where
+        E: Into<Expr>,
+    {
+        GenerateIf {
+            cond: cond.into(),
+            block: GenerateBlock::new(),
+            else_branch: None,
+        }
+    }
+
+    pub fn block(&self) -> &GenerateBlock {
+        &self.block
+    }
+
+    pub fn else_branch(&self) -> Option<&GenerateBlock> {
+        self.else_branch.as_ref()
+    }
+
+    pub fn set_label(&mut self, label: &str) {
+        self.block.set_label(label);
+    }
+
+    pub fn add_decl(&mut self, decl: Decl) {
+        self.block.add_decl(decl);
+    }
+
+    pub fn add_instance(&mut self, inst: Instance) {
+        self.block.add_instance(inst);
+    }
+
+    pub fn add_stmt<S>(&mut self, stmt: S)
+    where
+        S: Into<Stmt>,
+    {
+        self.block.add_stmt(stmt);
+    }
+
+    pub fn set_else(&mut self, block: GenerateBlock) {
+        self.else_branch = Some(block);
+    }
+
+    /// Chain another conditional, printed as `else if`.
+    pub fn set_else_if(&mut self, gen: GenerateIf) {
+        let mut block = GenerateBlock::new();
+        block.add_stmt(gen);
+        self.else_branch = Some(block);
+    }
+}
+
 impl Stmt {
     pub fn new_parallel<P>(par: P) -> Stmt
     where
@@ -229,6 +726,25 @@ impl Module {
         &self.attr
     }
 
+    /// Collect the values of the parameters declared in the module header
+    /// and body, used to evaluate constant expressions.
+    pub fn param_env(&self) -> Env {
+        fn param(decl: &Decl) -> Option<(Id, Expr)> {
+            match decl {
+                Decl::Param(id, _, expr) | Decl::LocalParam(id, _, expr) => {
+                    Some((id.clone(), expr.clone()))
+                }
+                Decl::AttributeDecl(_, decl) => param(decl),
+                _ => None,
+            }
+        }
+        let body = self.body.iter().filter_map(|stmt| match stmt {
+            Stmt::Decl(decl) => Some(decl),
+            _ => None,
+        });
+        self.params.iter().chain(body).filter_map(param).collect()
+    }
+
     pub fn add_param_uint(&mut self, name: &str, value: u32) {
         self.params.push(Decl::new_param_uint(name, value));
     }
@@ -241,15 +757,45 @@ impl Module {
         self.ports.push(port);
     }
 
-    pub fn add_input(&mut self, name: &str, width: u64) {
+    pub fn add_input<W>(&mut self, name: &str, width: W)
+    where
+        W: Into<Ty>,
+    {
         self.ports.push(Port::new_input(name, width));
     }
 
-    pub fn add_output(&mut self, name: &str, width: u64) {
+    pub fn add_output<W>(&mut self, name: &str, width: W)
+    where
+        W: Into<Ty>,
+    {
         self.ports.push(Port::new_output(name, width));
     }
 
-    pub fn add_output_reg(&mut self, name: &str, width: u64) {
+    pub fn add_signed_input<W>(&mut self, name: &str, width: W)
+    where
+        W: Into<Ty>,
+    {
+        self.ports.push(Port::new_signed_input(name, width));
+    }
+
+    pub fn add_signed_output<W>(&mut self, name: &str, width: W)
+    where
+        W: Into<Ty>,
+    {
+        self.ports.push(Port::new_signed_output(name, width));
+    }
+
+    pub fn add_inout<W>(&mut self, name: &str, width: W)
+    where
+        W: Into<Ty>,
+    {
+        self.ports.push(Port::new_inout(name, width));
+    }
+
+    pub fn add_output_reg<W>(&mut self, name: &str, width: W)
+    where
+        W: Into<Ty>,
+    {
         self.ports.push(Port::new_output_reg(name, width));
     }
 
@@ -261,6 +807,26 @@ impl Module {
         self.body.push(Stmt::from(decl));
     }
 
+    pub fn add_genvar(&mut self, name: &str) {
+        self.body.push(Stmt::from(Decl::new_genvar(name)));
+    }
+
+    pub fn add_function(&mut self, func: Function) {
+        self.body.push(Stmt::from(Decl::new_func(func)));
+    }
+
+    pub fn add_task(&mut self, task: Task) {
+        self.body.push(Stmt::from(Decl::new_task(task)));
+    }
+
+    pub fn add_generate_for(&mut self, gen: GenerateFor) {
+        self.body.push(Stmt::from(gen));
+    }
+
+    pub fn add_generate_if(&mut self, gen: GenerateIf) {
+        self.body.push(Stmt::from(gen));
+    }
+
     pub fn add_stmt<S>(&mut self, stmt: S)
     where
         S: Into<Stmt>,
@@ -0,0 +1,598 @@
+use crate::subset;
+use crate::subset::parser::Parser;
+use crate::v05::ast::*;
+use crate::Error;
+use std::rc::Rc;
+
+pub type ParseError = subset::parser::ParseError;
+
+/// Parse every module in a Verilog-2005 source text.
+pub fn parse_modules(src: &str) -> Result<Vec<Module>, ParseError> {
+    let mut p = Parser::new(src)?;
+    let mut modules = Vec::new();
+    while !p.is_eof() {
+        let attr = p.parse_attributes()?;
+        modules.push(parse_module_with_attr(&mut p, attr)?);
+    }
+    Ok(modules)
+}
+
+/// Parse every module in a Verilog-2005 source text into a design, a
+/// module defined twice is an `Error::Duplicate`.
+pub fn parse_design(src: &str) -> Result<Design, Error> {
+    let mut p = Parser::new(src)?;
+    let mut design = Design::new();
+    while !p.is_eof() {
+        let attr = p.parse_attributes()?;
+        design.add_module(parse_module_with_attr(&mut p, attr)?)?;
+    }
+    Ok(design)
+}
+
+/// Parse a Verilog-2005 source text that contains exactly one module.
+pub fn parse_module(src: &str) -> Result<Module, ParseError> {
+    let mut p = Parser::new(src)?;
+    let attr = p.parse_attributes()?;
+    let module = parse_module_with_attr(&mut p, attr)?;
+    if p.is_eof() {
+        Ok(module)
+    } else {
+        Err(p.unexpected("end of input"))
+    }
+}
+
+fn parse_module_with_attr(p: &mut Parser, attr: Attribute) -> Result<Module, ParseError> {
+    p.expect_keyword("module")?;
+    let mut module = Module::new(&p.expect_ident()?);
+    module.set_attr(attr);
+    if p.eat_symbol("#") {
+        p.expect_symbol("(")?;
+        if !p.is_symbol(")") {
+            // the `parameter` keyword and its type carry over to the next
+            // parameter of the list
+            let mut ty = ParamTy::default();
+            loop {
+                if p.eat_keyword("parameter") {
+                    ty = parse_param_ty(p)?;
+                }
+                module.params.push(parse_param(p, &ty)?);
+                if !p.eat_symbol(",") {
+                    break;
+                }
+            }
+        }
+        p.expect_symbol(")")?;
+    }
+    if p.is_port_names() {
+        let names = p.parse_port_names()?;
+        p.expect_symbol(";")?;
+        let mut ports = Vec::new();
+        while !p.eat_keyword("endmodule") {
+            if p.is_eof() {
+                return Err(p.unexpected("`endmodule`"));
+            } else if p.is_keyword("input") || p.is_keyword("output") || p.is_keyword("inout") {
+                parse_port_decls(p, &mut ports)?;
+            } else {
+                parse_item(p, &mut module.body)?;
+            }
+        }
+        module.ports = p.order_ports(&names, ports, port_name)?;
+        return Ok(module);
+    }
+    // a module without ports can leave out the port list, `module foo;`
+    if p.eat_symbol("(") {
+        if !p.is_symbol(")") {
+            let mut dir = None;
+            loop {
+                let port = parse_port(p, dir.as_ref())?;
+                dir = Some(port.clone());
+                module.add_port(port);
+                if !p.eat_symbol(",") {
+                    break;
+                }
+            }
+        }
+        p.expect_symbol(")")?;
+    }
+    p.expect_symbol(";")?;
+    while !p.eat_keyword("endmodule") {
+        if p.is_eof() {
+            return Err(p.unexpected("`endmodule`"));
+        }
+        parse_item(p, &mut module.body)?;
+    }
+    Ok(module)
+}
+
+/// Parse an optional range, a missing range means a width of one.
+fn parse_opt_range(p: &mut Parser) -> Result<Ty, ParseError> {
+    if p.is_symbol("[") {
+        let (msb, lsb) = p.parse_range()?;
+        Ok(Ty::new_range(msb, lsb))
+    } else {
+        Ok(Ty::Width(1))
+    }
+}
+
+/// Parse an optional `signed` keyword followed by an optional range.
+fn parse_packed(p: &mut Parser) -> Result<Ty, ParseError> {
+    if p.eat_keyword("signed") {
+        Ok(Ty::new_signed(parse_opt_range(p)?))
+    } else {
+        parse_opt_range(p)
+    }
+}
+
+fn port_name(port: &Port) -> &Id {
+    match port.decl() {
+        Decl::Wire(name, _) | Decl::Reg(name, _) | Decl::Int(name, _) => name,
+        _ => unreachable!(),
+    }
+}
+
+/// Parse a port declaration in the body of a non-ANSI module, i.e.
+/// `input wire [7:0] a, b;`.
+fn parse_port_decls(p: &mut Parser, ports: &mut Vec<Port>) -> Result<(), ParseError> {
+    let mut prev = parse_port(p, None)?;
+    while p.eat_symbol(",") {
+        ports.push(prev.clone());
+        prev = parse_port(p, Some(&prev))?;
+    }
+    ports.push(prev);
+    p.expect_symbol(";")
+}
+
+/// The type of the parameters of one declaration, `signed` without a range
+/// keeps the width of the value.
+#[derive(Default)]
+struct ParamTy {
+    signed: bool,
+    ty: Option<Ty>,
+}
+
+/// Parse the type following `parameter` or `localparam`, which applies to
+/// every parameter of the declaration.
+fn parse_param_ty(p: &mut Parser) -> Result<ParamTy, ParseError> {
+    let signed = p.eat_keyword("signed");
+    let ty = if !signed && p.eat_keyword("integer") {
+        Some(Ty::Int)
+    } else if p.is_symbol("[") {
+        let ty = parse_opt_range(p)?;
+        Some(if signed { Ty::new_signed(ty) } else { ty })
+    } else {
+        None
+    };
+    Ok(ParamTy { signed, ty })
+}
+
+/// Parse the name and value of a parameter of type `ty`.
+fn parse_param(p: &mut Parser, ty: &ParamTy) -> Result<Decl, ParseError> {
+    let name = p.expect_ident()?;
+    p.expect_symbol("=")?;
+    let expr = p.parse_expr()?;
+    // `parameter signed W = v` is `parameter W = $signed(v)`
+    if ty.signed && ty.ty.is_none() {
+        Ok(Decl::Param(name, None, Expr::Signed(Rc::new(expr))))
+    } else {
+        Ok(Decl::Param(name, ty.ty.clone(), expr))
+    }
+}
+
+/// Parse an ANSI port declaration, a port without direction inherits the
+/// direction and type of the previous one.
+fn parse_port(p: &mut Parser, prev: Option<&Port>) -> Result<Port, ParseError> {
+    let dir: Option<fn(Decl) -> Port> = if p.eat_keyword("input") {
+        Some(Port::Input)
+    } else if p.eat_keyword("output") {
+        Some(Port::Output)
+    } else if p.eat_keyword("inout") {
+        Some(Port::Inout)
+    } else {
+        None
+    };
+    let (dir, decl) = match (dir, prev) {
+        (None, Some(prev)) => {
+            let name = p.expect_ident()?;
+            let decl = match prev.decl() {
+                Decl::Reg(_, ty) => Decl::Reg(name, ty.clone()),
+                Decl::Int(_, ty) => Decl::Int(name, ty.clone()),
+                Decl::Wire(_, ty) => Decl::Wire(name, ty.clone()),
+                _ => unreachable!(),
+            };
+            (prev.direction(), decl)
+        }
+        (None, None) => return Err(p.unexpected("port direction")),
+        (Some(dir), _) => {
+            let decl = if p.eat_keyword("integer") {
+                Decl::Int(p.expect_ident()?, Ty::Int)
+            } else {
+                let reg = p.eat_keyword("reg");
+                if !reg {
+                    p.eat_keyword("wire");
+                }
+                let ty = parse_packed(p)?;
+                let name = p.expect_ident()?;
+                if reg {
+                    Decl::Reg(name, ty)
+                } else {
+                    Decl::Wire(name, ty)
+                }
+            };
+            (dir, decl)
+        }
+    };
+    Ok(dir(decl))
+}
+
+/// Parse the ANSI port list of a task or function, the ports are variables.
+fn parse_tf_ports(p: &mut Parser) -> Result<Vec<Port>, ParseError> {
+    let mut ports: Vec<Port> = Vec::new();
+    p.expect_symbol("(")?;
+    if p.eat_symbol(")") {
+        return Ok(ports);
+    }
+    loop {
+        if p.is_keyword_nth(1, "wire") {
+            return Err(p.error("task and function ports can not be wires"));
+        }
+        let port = match parse_port(p, ports.last())? {
+            Port::Input(Decl::Wire(name, ty)) => Port::Input(Decl::Reg(name, ty)),
+            Port::Output(Decl::Wire(name, ty)) => Port::Output(Decl::Reg(name, ty)),
+            Port::Inout(Decl::Wire(name, ty)) => Port::Inout(Decl::Reg(name, ty)),
+            port => port,
+        };
+        ports.push(port);
+        if !p.eat_symbol(",") {
+            break;
+        }
+    }
+    p.expect_symbol(")")?;
+    Ok(ports)
+}
+
+/// Parse the `reg` and `integer` declarations of a task or function.
+fn parse_tf_decls(p: &mut Parser) -> Result<Vec<Decl>, ParseError> {
+    let mut decls = Vec::new();
+    loop {
+        if p.eat_keyword("integer") {
+            loop {
+                decls.push(Decl::new_int(&p.expect_ident()?));
+                if !p.eat_symbol(",") {
+                    break;
+                }
+            }
+        } else if p.eat_keyword("reg") {
+            let ty = parse_packed(p)?;
+            loop {
+                decls.push(Decl::Reg(p.expect_ident()?, ty.clone()));
+                if !p.eat_symbol(",") {
+                    break;
+                }
+            }
+        } else if p.is_keyword("input") || p.is_keyword("output") || p.is_keyword("inout") {
+            return Err(p.error("non-ANSI task and function ports are not supported"));
+        } else {
+            return Ok(decls);
+        }
+        p.expect_symbol(";")?;
+    }
+}
+
+/// Parse the body of a task or function up to the `end` keyword.
+fn parse_tf_body(p: &mut Parser, end: &str) -> Result<Vec<Sequential>, ParseError> {
+    let mut body = Vec::new();
+    while !p.eat_keyword(end) {
+        if p.is_eof() {
+            return Err(p.unexpected(&format!("`{}`", end)));
+        }
+        body.extend(parse_block(p)?);
+    }
+    Ok(body)
+}
+
+fn parse_function(p: &mut Parser) -> Result<Function, ParseError> {
+    p.expect_keyword("function")?;
+    let automatic = p.eat_keyword("automatic");
+    let ret = if p.eat_keyword("integer") {
+        Ty::Int
+    } else {
+        parse_packed(p)?
+    };
+    let mut func = Function::new(&p.expect_ident()?, ret);
+    if automatic {
+        func.set_automatic();
+    }
+    if !p.is_symbol("(") {
+        return Err(p.error("non-ANSI task and function ports are not supported"));
+    }
+    func.ports = parse_tf_ports(p)?;
+    p.expect_symbol(";")?;
+    func.decls = parse_tf_decls(p)?;
+    func.body = parse_tf_body(p, "endfunction")?;
+    Ok(func)
+}
+
+fn parse_task(p: &mut Parser) -> Result<Task, ParseError> {
+    p.expect_keyword("task")?;
+    let automatic = p.eat_keyword("automatic");
+    let mut task = Task::new(&p.expect_ident()?);
+    if automatic {
+        task.set_automatic();
+    }
+    if p.is_symbol("(") {
+        task.ports = parse_tf_ports(p)?;
+    }
+    p.expect_symbol(";")?;
+    task.decls = parse_tf_decls(p)?;
+    task.body = parse_tf_body(p, "endtask")?;
+    Ok(task)
+}
+
+fn parse_item(p: &mut Parser, body: &mut Vec<Stmt>) -> Result<(), ParseError> {
+    let attr = p.parse_attributes()?;
+    let with_attr = |decl: Decl| {
+        if attr.attrs().is_empty() {
+            decl
+        } else {
+            Decl::new_attribute_decl(attr.clone(), decl)
+        }
+    };
+    if p.is_keyword("wire") || p.is_keyword("reg") {
+        let reg = p.is_keyword("reg");
+        p.bump();
+        let ty = parse_packed(p)?;
+        loop {
+            let name = p.expect_ident()?;
+            let decl = if p.is_symbol("[") {
+                if !reg {
+                    return Err(p.error("arrays of wires are not supported"));
+                }
+                Decl::Array(name, ty.clone(), parse_opt_range(p)?)
+            } else if reg {
+                Decl::Reg(name, ty.clone())
+            } else if p.eat_symbol("=") {
+                Decl::WireAssign(name, ty.clone(), p.parse_expr()?)
+            } else {
+                Decl::Wire(name, ty.clone())
+            };
+            if p.is_symbol("=") {
+                return Err(p.error("register initializers are not supported"));
+            }
+            body.push(Stmt::from(with_attr(decl)));
+            if !p.eat_symbol(",") {
+                break;
+            }
+        }
+        p.expect_symbol(";")
+    } else if p.eat_keyword("integer") {
+        loop {
+            let name = p.expect_ident()?;
+            body.push(Stmt::from(with_attr(Decl::new_int(&name))));
+            if !p.eat_symbol(",") {
+                break;
+            }
+        }
+        p.expect_symbol(";")
+    } else if p.eat_keyword("parameter") {
+        let ty = parse_param_ty(p)?;
+        loop {
+            let param = parse_param(p, &ty)?;
+            body.push(Stmt::from(with_attr(param)));
+            if !p.eat_symbol(",") {
+                break;
+            }
+        }
+        p.expect_symbol(";")
+    } else if p.eat_keyword("localparam") {
+        let ty = parse_param_ty(p)?;
+        loop {
+            if let Decl::Param(name, ty, expr) = parse_param(p, &ty)? {
+                body.push(Stmt::from(with_attr(Decl::LocalParam(name, ty, expr))));
+            }
+            if !p.eat_symbol(",") {
+                break;
+            }
+        }
+        p.expect_symbol(";")
+    } else if !attr.attrs().is_empty() && !p.is_instance() {
+        Err(p.unexpected("declaration or instance after attribute"))
+    } else if p.eat_keyword("assign") {
+        loop {
+            let lexpr = p.parse_lvalue()?;
+            p.expect_symbol("=")?;
+            let rexpr = p.parse_expr()?;
+            body.push(Stmt::from(Parallel::Assign(lexpr, rexpr)));
+            if !p.eat_symbol(",") {
+                break;
+            }
+        }
+        p.expect_symbol(";")
+    } else if p.eat_keyword("always") {
+        let mut process = ParallelProcess::new_always();
+        p.expect_symbol("@")?;
+        if !p.eat_symbol("*") {
+            p.expect_symbol("(")?;
+            if p.eat_symbol("*") {
+                process.set_event(Sequential::Wildcard);
+            } else {
+                let (ty, expr) = p.parse_event()?;
+                process.set_event(Sequential::Event(ty, expr));
+            }
+            p.expect_symbol(")")?;
+        } else {
+            process.set_event(Sequential::Wildcard);
+        }
+        process.body = parse_block(p)?;
+        body.push(Stmt::from(process));
+        Ok(())
+    } else if p.is_keyword("function") {
+        body.push(Stmt::from(Decl::new_func(parse_function(p)?)));
+        Ok(())
+    } else if p.is_keyword("task") {
+        body.push(Stmt::from(Decl::new_task(parse_task(p)?)));
+        Ok(())
+    } else if p.eat_keyword("genvar") {
+        loop {
+            body.push(Stmt::from(Decl::new_genvar(&p.expect_ident()?)));
+            if !p.eat_symbol(",") {
+                break;
+            }
+        }
+        p.expect_symbol(";")
+    } else if p.eat_keyword("generate") {
+        while !p.eat_keyword("endgenerate") {
+            if p.is_eof() {
+                return Err(p.unexpected("`endgenerate`"));
+            }
+            parse_item(p, body)?;
+        }
+        Ok(())
+    } else if p.is_keyword("for") {
+        body.push(Stmt::from(p.parse_generate_for(parse_item)?));
+        Ok(())
+    } else if p.is_keyword("if") {
+        body.push(Stmt::from(p.parse_generate_if(parse_item)?));
+        Ok(())
+    } else if p.is_instance() {
+        body.push(Stmt::from(p.parse_instance(attr)?));
+        Ok(())
+    } else {
+        Err(p.unexpected("module item"))
+    }
+}
+
+/// Parse a `begin ... end` block or a single statement.
+fn parse_block(p: &mut Parser) -> Result<Vec<Sequential>, ParseError> {
+    let mut body = Vec::new();
+    if p.eat_keyword("begin") {
+        if p.is_symbol(":") {
+            return Err(p.error("named blocks are not supported"));
+        }
+        while !p.eat_keyword("end") {
+            if p.is_eof() {
+                return Err(p.unexpected("`end`"));
+            } else if p.is_keyword("begin") {
+                body.extend(parse_block(p)?);
+            } else {
+                parse_stmt(p, &mut body)?;
+            }
+        }
+    } else {
+        parse_stmt(p, &mut body)?;
+    }
+    Ok(body)
+}
+
+/// Parse a single statement into `body`, a statement guarded by a timing
+/// control, i.e. `@(posedge clock) a = b;`, becomes the control followed by
+/// the statement.
+fn parse_stmt(p: &mut Parser, body: &mut Vec<Sequential>) -> Result<(), ParseError> {
+    let seq = parse_seq(p)?;
+    let guard = matches!(
+        seq,
+        Some(Sequential::Delay(_)) | Some(Sequential::EventControl(_, _))
+    );
+    body.extend(seq);
+    if guard && !p.eat_symbol(";") {
+        if p.is_keyword("begin") {
+            body.extend(parse_block(p)?);
+        } else {
+            parse_stmt(p, body)?;
+        }
+    }
+    Ok(())
+}
+
+/// Parse a single statement, the null statement `;` yields nothing and a
+/// timing control is parsed without the statement it guards.
+fn parse_seq(p: &mut Parser) -> Result<Option<Sequential>, ParseError> {
+    if p.eat_symbol(";") {
+        Ok(None)
+    } else if p.is_keyword("if") {
+        Ok(Some(Sequential::IfElse(parse_if(p)?)))
+    } else if p.eat_keyword("case") {
+        p.expect_symbol("(")?;
+        let mut case = Case::new(p.parse_expr()?);
+        p.expect_symbol(")")?;
+        while !p.eat_keyword("endcase") {
+            if p.eat_keyword("default") {
+                p.eat_symbol(":");
+                case.set_default(CaseDefault {
+                    body: parse_block(p)?,
+                });
+            } else {
+                let mut branch = CaseBranch::new(p.parse_expr()?);
+                if p.is_symbol(",") {
+                    return Err(p.error("case items with multiple expressions are not supported"));
+                }
+                p.expect_symbol(":")?;
+                branch.body = parse_block(p)?;
+                case.add_branch(branch);
+            }
+        }
+        Ok(Some(Sequential::new_case(case)))
+    } else if p.eat_keyword("for") {
+        p.expect_symbol("(")?;
+        let (var, init, cond, step) = p.parse_for_header()?;
+        p.expect_symbol(")")?;
+        let mut seq_for = SequentialFor::new(&var, init, cond, step);
+        seq_for.body = parse_block(p)?;
+        Ok(Some(Sequential::For(seq_for)))
+    } else if p.eat_keyword("while") {
+        let cond = p.parse_paren_expr()?;
+        Ok(Some(Sequential::While(cond, parse_block(p)?)))
+    } else if p.eat_keyword("repeat") {
+        let times = p.parse_paren_expr()?;
+        Ok(Some(Sequential::Repeat(times, parse_block(p)?)))
+    } else if p.eat_keyword("forever") {
+        Ok(Some(Sequential::Forever(parse_block(p)?)))
+    } else if p.eat_symbol("@") {
+        p.expect_symbol("(")?;
+        let (ty, expr) = p.parse_event()?;
+        p.expect_symbol(")")?;
+        Ok(Some(Sequential::EventControl(ty, expr)))
+    } else if p.eat_symbol("#") {
+        Ok(Some(Sequential::new_delay(p.parse_expr()?)))
+    } else if p.is_call() {
+        let call = p.parse_expr()?;
+        if !matches!(call, Expr::Call(_, _)) {
+            return Err(p.unexpected("`;`"));
+        }
+        p.expect_symbol(";")?;
+        Ok(Some(Sequential::new_call(call)))
+    } else if p.is_ident() && p.is_symbol_nth(1, ";") {
+        let name = p.expect_ident()?;
+        p.bump();
+        Ok(Some(Sequential::new_call(Expr::new_call(&name, vec![]))))
+    } else {
+        let lexpr = p.parse_lvalue()?;
+        let seq = if p.eat_symbol("=") {
+            Sequential::new_blk_assign(lexpr, p.parse_expr()?)
+        } else if p.eat_symbol("<=") {
+            Sequential::new_nonblk_assign(lexpr, p.parse_expr()?)
+        } else {
+            return Err(p.unexpected("`=` or `<=`"));
+        };
+        p.expect_symbol(";")?;
+        Ok(Some(seq))
+    }
+}
+
+fn parse_if(p: &mut Parser) -> Result<SequentialIfElse, ParseError> {
+    p.expect_keyword("if")?;
+    p.expect_symbol("(")?;
+    let mut ifelse = SequentialIfElse::new(p.parse_expr()?);
+    p.expect_symbol(")")?;
+    ifelse.body = parse_block(p)?;
+    if p.eat_keyword("else") {
+        if p.is_keyword("if") {
+            ifelse.set_else(parse_if(p)?);
+        } else {
+            ifelse.set_else(SequentialIfElse {
+                body: parse_block(p)?,
+                ..Default::default()
+            });
+        }
+    }
+    Ok(ifelse)
+}
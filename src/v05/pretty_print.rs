@@ -1,10 +1,152 @@
-// use crate::util::pretty_print::{PrettyHelper, PrettyPrint, PRETTY_INDENT};
-use crate::util::pretty_print::{block, block_with_parens, intersperse, PrettyHelper, PrettyPrint};
+use crate::subset;
+use crate::util::pretty_print::{
+    block, block_with_parens, intersperse, PortStyle, PrettyHelper, PrettyPrint, PRETTY_WIDTH,
+};
 use crate::v05::ast::*;
+use crate::v05::check::decl_id;
+use crate::Error;
 use pretty::RcDoc;
+use std::collections::HashSet;
+use std::io;
+
+fn validate_width(name: &str, ty: &Ty) -> Result<(), Error> {
+    match ty {
+        Ty::Width(0) => Err(Error::Invalid(format!("`{}` has a zero width", name))),
+        Ty::Width(_) | Ty::Range(_, _) => Ok(()),
+        Ty::Signed(ty) => validate_width(name, ty),
+        Ty::Int => Err(Error::Invalid(format!("`{}` must have a width", name))),
+    }
+}
+
+fn wire<'a>(name: &'a Id, ty: &'a Ty) -> RcDoc<'a, ()> {
+    let extra_space = match ty {
+        Ty::Width(1) => RcDoc::nil(),
+        _ => RcDoc::space(),
+    };
+    RcDoc::text("wire")
+        .append(RcDoc::space())
+        .append(ty.to_doc())
+        .append(extra_space)
+        .append(RcDoc::as_string(name))
+}
+
+/// A `parameter` or `localparam` declaration.
+fn param<'a>(keyword: &'a str, name: &'a Id, ty: &'a Option<Ty>, expr: &'a Expr) -> RcDoc<'a, ()> {
+    // a parameter without a range takes the width of its value
+    let ty = match ty {
+        None => RcDoc::nil(),
+        Some(Ty::Width(1)) => RcDoc::text("[0:0]").append(RcDoc::space()),
+        Some(Ty::Signed(ty)) if **ty == Ty::Width(1) => {
+            RcDoc::text("signed [0:0]").append(RcDoc::space())
+        }
+        Some(ty) => ty.to_doc().append(RcDoc::space()),
+    };
+    RcDoc::text(keyword)
+        .append(RcDoc::space())
+        .append(ty)
+        .append(RcDoc::as_string(name))
+        .append(RcDoc::space())
+        .append(RcDoc::text("="))
+        .append(RcDoc::space())
+        .append(expr.to_doc())
+}
+
+/// Body of a loop, task or function, an empty body prints an empty
+/// `begin ... end` block.
+fn seq_block(body: &[Sequential]) -> RcDoc<'_, ()> {
+    if body.is_empty() {
+        RcDoc::text("begin")
+            .append(RcDoc::hardline())
+            .append(RcDoc::text("end"))
+    } else {
+        block(intersperse(
+            body.iter().map(|x| x.to_doc()),
+            RcDoc::hardline(),
+        ))
+        .begin_end()
+    }
+}
+
+/// Header and items of a task or function, `ret` is the return type of a
+/// function and nil for a task.
+fn subroutine<'a, R>(
+    sub: &'a subset::ast::GenericFunction<FunctionTy, Decl, Sequential, R>,
+    ret: RcDoc<'a, ()>,
+) -> RcDoc<'a, ()> {
+    let ports = if sub.ports.is_empty() {
+        RcDoc::nil()
+    } else {
+        intersperse(
+            sub.ports.iter().map(|x| x.to_doc()),
+            RcDoc::text(",").append(RcDoc::space()),
+        )
+        .parens()
+    };
+    let automatic = match &sub.ty {
+        FunctionTy::Automatic => RcDoc::space().append(RcDoc::text("automatic")),
+        FunctionTy::Default => RcDoc::nil(),
+    };
+    let decls = sub
+        .decls
+        .iter()
+        .map(|x| x.to_doc().append(RcDoc::text(";")));
+    let items = intersperse(
+        decls.chain(std::iter::once(seq_block(&sub.body))),
+        RcDoc::hardline(),
+    );
+    automatic
+        .append(RcDoc::space())
+        .append(ret)
+        .append(RcDoc::as_string(&sub.name))
+        .append(ports)
+        .append(RcDoc::text(";"))
+        .append(block(items))
+}
+
+impl PrettyPrint for Function {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
+        let ret = match &self.ret {
+            Ty::Width(1) => RcDoc::nil(),
+            ret => ret.to_doc().append(RcDoc::space()),
+        };
+        subroutine(self, ret).func_endfunc()
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        if self.ret.validate().is_err() {
+            return Err(Error::Invalid(format!(
+                "return type of `{}` has a zero width",
+                self.name
+            )));
+        }
+        for port in self.ports() {
+            port.validate()?;
+        }
+        for decl in self.decls() {
+            decl.validate()?;
+        }
+        Ok(())
+    }
+}
+
+impl PrettyPrint for Task {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
+        subroutine(self, RcDoc::nil()).task_endtask()
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        for port in self.ports() {
+            port.validate()?;
+        }
+        for decl in self.decls() {
+            decl.validate()?;
+        }
+        Ok(())
+    }
+}
 
 impl PrettyPrint for Ty {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         match self {
             Ty::Int => RcDoc::text("integer"),
             Ty::Width(w) => match w {
@@ -15,31 +157,45 @@ impl PrettyPrint for Ty {
                     .append(RcDoc::text("0"))
                     .brackets(),
             },
+            Ty::Range(msb, lsb) => msb
+                .to_doc()
+                .append(RcDoc::text(":"))
+                .append(lsb.to_doc())
+                .brackets(),
+            Ty::Signed(ty) => match ty.as_ref() {
+                Ty::Width(1) => RcDoc::text("signed"),
+                ty => RcDoc::text("signed")
+                    .append(RcDoc::space())
+                    .append(ty.to_doc()),
+            },
+        }
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        match self {
+            Ty::Width(0) => Err(Error::invalid("width must be greater than zero")),
+            Ty::Signed(ty) => ty.validate(),
+            _ => Ok(()),
         }
     }
 }
 
 impl PrettyPrint for Decl {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         match self {
             Decl::Int(name, ty) => ty
                 .to_doc()
                 .append(RcDoc::space())
                 .append(RcDoc::as_string(name)),
-            Decl::Wire(name, ty) => {
-                let extra_space = match ty.width() {
-                    1 => RcDoc::nil(),
-                    _ => RcDoc::space(),
-                };
-                RcDoc::text("wire")
-                    .append(RcDoc::space())
-                    .append(ty.to_doc())
-                    .append(extra_space)
-                    .append(RcDoc::as_string(name))
-            }
+            Decl::Wire(name, ty) => wire(name, ty),
+            Decl::WireAssign(name, ty, expr) => wire(name, ty)
+                .append(RcDoc::space())
+                .append(RcDoc::text("="))
+                .append(RcDoc::space())
+                .append(expr.to_doc()),
             Decl::Reg(name, ty) => {
-                let extra_space = match ty.width() {
-                    1 => RcDoc::nil(),
+                let extra_space = match ty {
+                    Ty::Width(1) => RcDoc::nil(),
                     _ => RcDoc::space(),
                 };
                 RcDoc::text("reg")
@@ -49,12 +205,12 @@ impl PrettyPrint for Decl {
                     .append(RcDoc::as_string(name))
             }
             Decl::Array(name, width, depth) => {
-                let width_space = match width.width() {
-                    1 => RcDoc::nil(),
+                let width_space = match width {
+                    Ty::Width(1) => RcDoc::nil(),
                     _ => RcDoc::space(),
                 };
-                let depth_space = match depth.width() {
-                    1 => RcDoc::nil(),
+                let depth_space = match depth {
+                    Ty::Width(1) => RcDoc::nil(),
                     _ => RcDoc::space(),
                 };
                 RcDoc::text("reg")
@@ -65,22 +221,43 @@ impl PrettyPrint for Decl {
                     .append(depth_space)
                     .append(depth.to_doc())
             }
-            Decl::Param(name, expr) => RcDoc::text("parameter")
+            Decl::Param(name, ty, expr) => param("parameter", name, ty, expr),
+            Decl::LocalParam(name, ty, expr) => param("localparam", name, ty, expr),
+            Decl::Genvar(name) => RcDoc::text("genvar")
                 .append(RcDoc::space())
-                .append(RcDoc::as_string(name))
-                .append(RcDoc::space())
-                .append(RcDoc::text("="))
-                .append(RcDoc::space())
-                .append(expr.to_doc()),
+                .append(RcDoc::as_string(name)),
+            Decl::Func(func) => func.to_doc(),
+            Decl::Task(task) => task.to_doc(),
             Decl::AttributeDecl(attr, decl) => {
                 attr.to_doc().append(RcDoc::space()).append(decl.to_doc())
             }
         }
     }
+
+    fn validate(&self) -> Result<(), Error> {
+        match self {
+            Decl::Int(name, ty) => match ty {
+                Ty::Width(0) => validate_width(name, ty),
+                _ => Ok(()),
+            },
+            Decl::Wire(name, ty) | Decl::WireAssign(name, ty, _) | Decl::Reg(name, ty) => {
+                validate_width(name, ty)
+            }
+            Decl::Array(name, width, depth) => {
+                validate_width(name, width)?;
+                validate_width(name, depth)
+            }
+            Decl::Param(_, Some(ty), _) | Decl::LocalParam(_, Some(ty), _) => ty.validate(),
+            Decl::Param(_, None, _) | Decl::LocalParam(_, None, _) | Decl::Genvar(_) => Ok(()),
+            Decl::Func(func) => func.validate(),
+            Decl::Task(task) => task.validate(),
+            Decl::AttributeDecl(_, decl) => decl.validate(),
+        }
+    }
 }
 
 impl PrettyPrint for SequentialIfElse {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         let cond = if let Some(c) = self.cond() {
             RcDoc::text("if")
                 .append(c.to_doc().parens())
@@ -110,7 +287,7 @@ impl PrettyPrint for SequentialIfElse {
 }
 
 impl PrettyPrint for Sequential {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         match self {
             // wildcard for sensitivity list
             Sequential::Wildcard => RcDoc::text("*"),
@@ -124,12 +301,69 @@ impl PrettyPrint for Sequential {
                 .append(rexpr.to_doc())
                 .append(RcDoc::text(";")),
             Sequential::SeqCase(case) => case.to_doc(),
+            // Verilog-2005 does not allow empty parens in a task call
+            Sequential::Call(Expr::Call(name, args)) if args.is_empty() => {
+                RcDoc::as_string(name).append(RcDoc::text(";"))
+            }
+            Sequential::Call(call) => call.to_doc().append(RcDoc::text(";")),
+            Sequential::Delay(expr) => {
+                let delay = match expr {
+                    Expr::Int(_) | Expr::Ref(_) | Expr::ULit(_, _) => expr.to_doc(),
+                    _ => expr.to_doc().parens(),
+                };
+                RcDoc::text("#").append(delay).append(RcDoc::text(";"))
+            }
+            Sequential::EventControl(ty, expr) => RcDoc::text("@")
+                .append(
+                    ty.to_doc()
+                        .append(RcDoc::space())
+                        .append(expr.to_doc())
+                        .parens(),
+                )
+                .append(RcDoc::text(";")),
+            Sequential::For(seq_for) => seq_for.to_doc(),
+            Sequential::While(cond, body) => RcDoc::text("while")
+                .append(cond.to_doc().parens())
+                .append(RcDoc::space())
+                .append(seq_block(body)),
+            Sequential::Repeat(times, body) => RcDoc::text("repeat")
+                .append(times.to_doc().parens())
+                .append(RcDoc::space())
+                .append(seq_block(body)),
+            Sequential::Forever(body) => RcDoc::text("forever")
+                .append(RcDoc::space())
+                .append(seq_block(body)),
         }
     }
 }
 
+impl PrettyPrint for SequentialFor {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
+        let var = || RcDoc::as_string(&self.var);
+        let header = var()
+            .append(RcDoc::space())
+            .append(RcDoc::text("="))
+            .append(RcDoc::space())
+            .append(self.init.to_doc())
+            .append(RcDoc::text(";"))
+            .append(RcDoc::space())
+            .append(self.cond.to_doc())
+            .append(RcDoc::text(";"))
+            .append(RcDoc::space())
+            .append(var())
+            .append(RcDoc::space())
+            .append(RcDoc::text("="))
+            .append(RcDoc::space())
+            .append(self.step.to_doc());
+        RcDoc::text("for")
+            .append(header.parens())
+            .append(RcDoc::space())
+            .append(seq_block(&self.body))
+    }
+}
+
 impl PrettyPrint for ProcessTy {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         match self {
             ProcessTy::Always => RcDoc::text("always"),
         }
@@ -137,7 +371,7 @@ impl PrettyPrint for ProcessTy {
 }
 
 impl PrettyPrint for ParallelProcess {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         let body = if self.body().is_empty() {
             RcDoc::nil()
         } else {
@@ -163,7 +397,7 @@ impl PrettyPrint for ParallelProcess {
 }
 
 impl PrettyPrint for Parallel {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         match self {
             Parallel::Inst(ty) => ty.to_doc(),
             Parallel::Assign(lexpr, rexpr) => RcDoc::text("assign")
@@ -180,17 +414,120 @@ impl PrettyPrint for Parallel {
 }
 
 impl PrettyPrint for Stmt {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         match self {
-            Stmt::Decl(decl) => decl.to_doc().append(RcDoc::text(";")),
+            Stmt::Decl(decl) => match decl {
+                Decl::Func(_) | Decl::Task(_) => decl.to_doc(),
+                _ => decl.to_doc().append(RcDoc::text(";")),
+            },
             Stmt::Parallel(par) => par.to_doc(),
             Stmt::RawStr(s) => RcDoc::text(s),
+            Stmt::GenFor(gen) => gen.to_doc(),
+            Stmt::GenIf(gen) => gen.to_doc(),
+        }
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        match self {
+            Stmt::Decl(decl) => decl.validate(),
+            Stmt::GenFor(gen) => gen.validate(),
+            Stmt::GenIf(gen) => gen.validate(),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl PrettyPrint for GenerateBlock {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
+        let begin = if let Some(label) = self.label() {
+            RcDoc::text("begin")
+                .append(RcDoc::space())
+                .append(RcDoc::text(":"))
+                .append(RcDoc::space())
+                .append(RcDoc::as_string(label))
+        } else {
+            RcDoc::text("begin")
+        };
+        let body = if self.body().is_empty() {
+            RcDoc::hardline()
+        } else {
+            block(intersperse(
+                self.body().iter().map(|x| x.to_doc()),
+                RcDoc::hardline(),
+            ))
+        };
+        begin.append(body).append(RcDoc::text("end"))
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        self.body().iter().try_for_each(|stmt| stmt.validate())
+    }
+}
+
+impl PrettyPrint for GenerateFor {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
+        let var = || RcDoc::as_string(&self.var);
+        let header = var()
+            .append(RcDoc::space())
+            .append(RcDoc::text("="))
+            .append(RcDoc::space())
+            .append(self.init.to_doc())
+            .append(RcDoc::text(";"))
+            .append(RcDoc::space())
+            .append(self.cond.to_doc())
+            .append(RcDoc::text(";"))
+            .append(RcDoc::space())
+            .append(var())
+            .append(RcDoc::space())
+            .append(RcDoc::text("="))
+            .append(RcDoc::space())
+            .append(self.step.to_doc());
+        RcDoc::text("for")
+            .append(header.parens())
+            .append(RcDoc::space())
+            .append(self.block.to_doc())
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        self.block.validate()
+    }
+}
+
+impl PrettyPrint for GenerateIf {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
+        let else_branch = match self.else_branch() {
+            Some(GenerateBlock { label: None, body })
+                if matches!(body.as_slice(), [Stmt::GenIf(_)]) =>
+            {
+                RcDoc::space()
+                    .append(RcDoc::text("else"))
+                    .append(RcDoc::space())
+                    .append(body[0].to_doc())
+            }
+            Some(branch) => RcDoc::space()
+                .append(RcDoc::text("else"))
+                .append(RcDoc::space())
+                .append(branch.to_doc()),
+            None => RcDoc::nil(),
+        };
+        RcDoc::text("if")
+            .append(self.cond.to_doc().parens())
+            .append(RcDoc::space())
+            .append(self.block.to_doc())
+            .append(else_branch)
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        self.block.validate()?;
+        match self.else_branch() {
+            Some(branch) => branch.validate(),
+            None => Ok(()),
         }
     }
 }
 
 impl PrettyPrint for Port {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         match self {
             Port::Input(decl) => RcDoc::text("input")
                 .append(RcDoc::space())
@@ -198,12 +535,22 @@ impl PrettyPrint for Port {
             Port::Output(decl) => RcDoc::text("output")
                 .append(RcDoc::space())
                 .append(decl.to_doc()),
+            Port::Inout(decl) => RcDoc::text("inout")
+                .append(RcDoc::space())
+                .append(decl.to_doc()),
+        }
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        match self {
+            Port::Input(decl) | Port::Output(decl) | Port::Inout(decl) => decl.validate(),
         }
     }
 }
 
-impl PrettyPrint for Module {
-    fn to_doc(&self) -> RcDoc<()> {
+impl Module {
+    /// Print the module with its ports declared in the given style.
+    pub fn to_doc_with_style(&self, style: PortStyle) -> RcDoc<'_, ()> {
         let params = if self.params().is_empty() {
             RcDoc::nil()
         } else {
@@ -215,10 +562,11 @@ impl PrettyPrint for Module {
         let ports = if self.ports().is_empty() {
             RcDoc::nil()
         } else {
-            intersperse(
-                self.ports().iter().map(|x| x.to_doc()),
-                RcDoc::text(",").append(RcDoc::hardline()),
-            )
+            let ports = self.ports().iter().map(|x| match style {
+                PortStyle::Ansi => x.to_doc(),
+                PortStyle::NonAnsi => RcDoc::as_string(decl_id(x.decl())),
+            });
+            intersperse(ports, RcDoc::text(",").append(RcDoc::hardline()))
         };
         let name = if self.params.is_empty() && self.ports.is_empty() {
             RcDoc::as_string(&self.name)
@@ -242,11 +590,16 @@ impl PrettyPrint for Module {
             )
             .append(block_with_parens(RcDoc::nil(), ports))
         };
-        let body = if self.body().is_empty() {
+        let decls = match style {
+            PortStyle::Ansi => &[],
+            PortStyle::NonAnsi => self.ports().as_slice(),
+        };
+        let body = if self.body().is_empty() && decls.is_empty() {
             RcDoc::hardline()
         } else {
+            let decls = decls.iter().map(|x| x.to_doc().append(RcDoc::text(";")));
             block(intersperse(
-                self.body().iter().map(|x| x.to_doc()),
+                decls.chain(self.body().iter().map(|x| x.to_doc())),
                 RcDoc::hardline(),
             ))
         };
@@ -263,10 +616,58 @@ impl PrettyPrint for Module {
             .append(RcDoc::hardline());
         attr.append(module)
     }
+
+    /// # Panics
+    ///
+    /// Panics if the module fails `validate`.
+    pub fn to_pretty_with_style(&self, style: PortStyle) -> String {
+        match self.try_to_pretty_with_style(style) {
+            Ok(pretty) => pretty,
+            Err(err) => panic!("Error: {}", err),
+        }
+    }
+
+    pub fn try_to_pretty_with_style(&self, style: PortStyle) -> Result<String, Error> {
+        self.validate()?;
+        let mut w = Vec::new();
+        self.to_doc_with_style(style).render(PRETTY_WIDTH, &mut w)?;
+        String::from_utf8(w)
+            .map_err(|err| Error::Io(io::Error::new(io::ErrorKind::InvalidData, err)))
+    }
+}
+
+impl PrettyPrint for Module {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
+        self.to_doc_with_style(PortStyle::Ansi)
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        for port in self.ports() {
+            port.validate()?;
+        }
+        for stmt in self.body() {
+            stmt.validate()?;
+        }
+        // a generate block has its own scope, only the module scope is
+        // checked for duplicates
+        let ports = self.ports().iter().map(|port| port.decl());
+        let body = self.body().iter().filter_map(|stmt| match stmt {
+            Stmt::Decl(decl) => Some(decl),
+            _ => None,
+        });
+        let mut names = HashSet::new();
+        for decl in self.params().iter().chain(ports).chain(body) {
+            decl.validate()?;
+            if !names.insert(decl_id(decl)) {
+                return Err(Error::Duplicate(decl_id(decl).clone()));
+            }
+        }
+        Ok(())
+    }
 }
 
 impl PrettyPrint for CaseBranch {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         let cond = self
             .cond
             .to_doc()
@@ -287,7 +688,7 @@ impl PrettyPrint for CaseBranch {
 }
 
 impl PrettyPrint for CaseDefault {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         let default = RcDoc::text("default")
             .append(RcDoc::space())
             .append(RcDoc::text(":"))
@@ -306,7 +707,7 @@ impl PrettyPrint for CaseDefault {
 }
 
 impl PrettyPrint for Case {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         let branches = if self.branches().is_empty() {
             RcDoc::nil()
         } else {
@@ -326,3 +727,28 @@ impl PrettyPrint for Case {
             .case_endcase()
     }
 }
+
+impl PrettyPrint for Design {
+    /// Every module in dependency order, separated by an empty line.
+    fn to_doc(&self) -> RcDoc<'_, ()> {
+        intersperse(
+            self.units().into_iter().map(|module| module.to_doc()),
+            RcDoc::hardline(),
+        )
+    }
+
+    /// Check every module, that every module instantiated is defined and
+    /// that instances match their modules, the first error is returned.
+    fn validate(&self) -> Result<(), Error> {
+        for module in self.modules().values() {
+            module.validate()?;
+        }
+        match self
+            .check_definitions()
+            .and_then(|_| self.check_instances())
+        {
+            Ok(()) => Ok(()),
+            Err(mut errors) => Err(errors.remove(0)),
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use crate::subset;
+use crate::v05::ast::*;
+use std::rc::Rc;
+
+/// Mutable traversal over a Verilog-2005 AST.
+///
+/// Every method defaults to the matching `walk_*_mut` function, which visits
+/// the children of the node. Nodes can be modified in place or replaced by
+/// assigning a new value to them. Expressions and statements behind an `Rc`
+/// are cloned when they are shared.
+pub trait VisitorMut {
+    fn visit_module_mut(&mut self, module: &mut Module) {
+        walk_module_mut(self, module)
+    }
+
+    fn visit_port_mut(&mut self, port: &mut Port) {
+        walk_port_mut(self, port)
+    }
+
+    fn visit_stmt_mut(&mut self, stmt: &mut Stmt) {
+        walk_stmt_mut(self, stmt)
+    }
+
+    fn visit_decl_mut(&mut self, decl: &mut Decl) {
+        walk_decl_mut(self, decl)
+    }
+
+    fn visit_ty_mut(&mut self, ty: &mut Ty) {
+        walk_ty_mut(self, ty)
+    }
+
+    fn visit_generate_block_mut(&mut self, block: &mut GenerateBlock) {
+        walk_generate_block_mut(self, block)
+    }
+
+    fn visit_function_mut(&mut self, func: &mut Function) {
+        walk_function_mut(self, func)
+    }
+
+    fn visit_task_mut(&mut self, task: &mut Task) {
+        walk_task_mut(self, task)
+    }
+
+    fn visit_parallel_mut(&mut self, par: &mut Parallel) {
+        walk_parallel_mut(self, par)
+    }
+
+    fn visit_instance_mut(&mut self, inst: &mut Instance) {
+        walk_instance_mut(self, inst)
+    }
+
+    fn visit_process_mut(&mut self, proc: &mut ParallelProcess) {
+        walk_process_mut(self, proc)
+    }
+
+    fn visit_sequential_mut(&mut self, seq: &mut Sequential) {
+        walk_sequential_mut(self, seq)
+    }
+
+    fn visit_case_mut(&mut self, case: &mut Case) {
+        walk_case_mut(self, case)
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        walk_expr_mut(self, expr)
+    }
+}
+
+pub fn walk_module_mut<V: VisitorMut + ?Sized>(v: &mut V, module: &mut Module) {
+    for param in module.params.iter_mut() {
+        v.visit_decl_mut(param);
+    }
+    for port in module.ports.iter_mut() {
+        v.visit_port_mut(port);
+    }
+    for stmt in module.body.iter_mut() {
+        v.visit_stmt_mut(stmt);
+    }
+}
+
+pub fn walk_port_mut<V: VisitorMut + ?Sized>(v: &mut V, port: &mut Port) {
+    match port {
+        Port::Input(decl) | Port::Output(decl) | Port::Inout(decl) => v.visit_decl_mut(decl),
+    }
+}
+
+pub fn walk_stmt_mut<V: VisitorMut + ?Sized>(v: &mut V, stmt: &mut Stmt) {
+    match stmt {
+        Stmt::Decl(decl) => v.visit_decl_mut(decl),
+        Stmt::Parallel(par) => v.visit_parallel_mut(par),
+        Stmt::RawStr(_) => (),
+        Stmt::GenFor(gen) => {
+            v.visit_expr_mut(&mut gen.init);
+            v.visit_expr_mut(&mut gen.cond);
+            v.visit_expr_mut(&mut gen.step);
+            v.visit_generate_block_mut(&mut gen.block);
+        }
+        Stmt::GenIf(gen) => {
+            v.visit_expr_mut(&mut gen.cond);
+            v.visit_generate_block_mut(&mut gen.block);
+            if let Some(block) = &mut gen.else_branch {
+                v.visit_generate_block_mut(block);
+            }
+        }
+    }
+}
+
+pub fn walk_generate_block_mut<V: VisitorMut + ?Sized>(v: &mut V, block: &mut GenerateBlock) {
+    for stmt in block.body.iter_mut() {
+        v.visit_stmt_mut(stmt);
+    }
+}
+
+pub fn walk_decl_mut<V: VisitorMut + ?Sized>(v: &mut V, decl: &mut Decl) {
+    match decl {
+        Decl::Int(_, ty) | Decl::Wire(_, ty) | Decl::Reg(_, ty) => v.visit_ty_mut(ty),
+        Decl::WireAssign(_, ty, expr) => {
+            v.visit_ty_mut(ty);
+            v.visit_expr_mut(expr);
+        }
+        Decl::Array(_, ty, dim) => {
+            v.visit_ty_mut(ty);
+            v.visit_ty_mut(dim);
+        }
+        Decl::Genvar(_) => (),
+        Decl::Param(_, ty, expr) | Decl::LocalParam(_, ty, expr) => {
+            if let Some(ty) = ty {
+                v.visit_ty_mut(ty);
+            }
+            v.visit_expr_mut(expr);
+        }
+        Decl::Func(func) => v.visit_function_mut(func),
+        Decl::Task(task) => v.visit_task_mut(task),
+        Decl::AttributeDecl(_, decl) => v.visit_decl_mut(Rc::make_mut(decl)),
+    }
+}
+
+pub fn walk_ty_mut<V: VisitorMut + ?Sized>(v: &mut V, ty: &mut Ty) {
+    match ty {
+        Ty::Int | Ty::Width(_) => (),
+        Ty::Range(msb, lsb) => {
+            v.visit_expr_mut(msb);
+            v.visit_expr_mut(lsb);
+        }
+        Ty::Signed(ty) => v.visit_ty_mut(Rc::make_mut(ty)),
+    }
+}
+
+pub fn walk_function_mut<V: VisitorMut + ?Sized>(v: &mut V, func: &mut Function) {
+    v.visit_ty_mut(&mut func.ret);
+    for port in func.ports.iter_mut() {
+        v.visit_port_mut(port);
+    }
+    for decl in func.decls.iter_mut() {
+        v.visit_decl_mut(decl);
+    }
+    for seq in func.body.iter_mut() {
+        v.visit_sequential_mut(seq);
+    }
+}
+
+pub fn walk_task_mut<V: VisitorMut + ?Sized>(v: &mut V, task: &mut Task) {
+    for port in task.ports.iter_mut() {
+        v.visit_port_mut(port);
+    }
+    for decl in task.decls.iter_mut() {
+        v.visit_decl_mut(decl);
+    }
+    for seq in task.body.iter_mut() {
+        v.visit_sequential_mut(seq);
+    }
+}
+
+pub fn walk_parallel_mut<V: VisitorMut + ?Sized>(v: &mut V, par: &mut Parallel) {
+    match par {
+        Parallel::Inst(inst) => v.visit_instance_mut(inst),
+        Parallel::Assign(lexpr, rexpr) => {
+            v.visit_expr_mut(lexpr);
+            v.visit_expr_mut(rexpr);
+        }
+        Parallel::Process(proc) => v.visit_process_mut(proc),
+    }
+}
+
+pub fn walk_instance_mut<V: VisitorMut + ?Sized>(v: &mut V, inst: &mut Instance) {
+    subset::visit::walk_instance_mut(inst, |e| v.visit_expr_mut(e))
+}
+
+pub fn walk_process_mut<V: VisitorMut + ?Sized>(v: &mut V, proc: &mut ParallelProcess) {
+    if let Some(event) = &mut proc.event {
+        v.visit_sequential_mut(event);
+    }
+    for seq in proc.body.iter_mut() {
+        v.visit_sequential_mut(seq);
+    }
+}
+
+pub fn walk_sequential_mut<V: VisitorMut + ?Sized>(v: &mut V, seq: &mut Sequential) {
+    match seq {
+        Sequential::Wildcard => (),
+        Sequential::Event(_, expr) => v.visit_expr_mut(expr),
+        Sequential::Assign(lexpr, rexpr, _) => {
+            v.visit_expr_mut(lexpr);
+            v.visit_expr_mut(rexpr);
+        }
+        Sequential::IfElse(ifelse) => {
+            if let Some(cond) = &mut ifelse.cond {
+                v.visit_expr_mut(cond);
+            }
+            for seq in ifelse.body.iter_mut() {
+                v.visit_sequential_mut(seq);
+            }
+            if let Some(elsebr) = &mut ifelse.elsebr {
+                v.visit_sequential_mut(Rc::make_mut(elsebr));
+            }
+        }
+        Sequential::SeqCase(case) => v.visit_case_mut(case),
+        Sequential::Call(expr) | Sequential::Delay(expr) | Sequential::EventControl(_, expr) => {
+            v.visit_expr_mut(expr)
+        }
+        Sequential::For(seq_for) => {
+            v.visit_expr_mut(&mut seq_for.init);
+            v.visit_expr_mut(&mut seq_for.cond);
+            v.visit_expr_mut(&mut seq_for.step);
+            for seq in seq_for.body.iter_mut() {
+                v.visit_sequential_mut(seq);
+            }
+        }
+        Sequential::While(cond, body) | Sequential::Repeat(cond, body) => {
+            v.visit_expr_mut(cond);
+            for seq in body.iter_mut() {
+                v.visit_sequential_mut(seq);
+            }
+        }
+        Sequential::Forever(body) => {
+            for seq in body.iter_mut() {
+                v.visit_sequential_mut(seq);
+            }
+        }
+    }
+}
+
+pub fn walk_case_mut<V: VisitorMut + ?Sized>(v: &mut V, case: &mut Case) {
+    v.visit_expr_mut(&mut case.cond);
+    for branch in case.branches.iter_mut() {
+        v.visit_expr_mut(&mut branch.cond);
+        for seq in branch.body.iter_mut() {
+            v.visit_sequential_mut(seq);
+        }
+    }
+    if let Some(default) = &mut case.default {
+        for seq in default.body.iter_mut() {
+            v.visit_sequential_mut(seq);
+        }
+    }
+}
+
+pub fn walk_expr_mut<V: VisitorMut + ?Sized>(v: &mut V, expr: &mut Expr) {
+    subset::visit::walk_expr_mut(expr, |e| v.visit_expr_mut(e))
+}
@@ -1,46 +1,163 @@
 use crate::subset;
+use std::collections::BTreeMap;
 use std::rc::Rc;
 
 pub type Id = subset::ast::Id;
 pub type InstancePath = subset::ast::InstancePath;
 pub type Expr = subset::ast::Expr;
+pub type Unop = subset::ast::Unop;
+pub type Binop = subset::ast::Binop;
+pub type Terop = subset::ast::Terop;
+pub type Bit = subset::bitvec::Bit;
+pub type BitVec = subset::bitvec::BitVec;
+pub type BitVecError = subset::bitvec::BitVecError;
+pub type Radix = subset::ast::Radix;
+pub type Env = subset::eval::Env;
+pub type EvalError = subset::eval::EvalError;
+pub type Value = subset::eval::Value;
 pub type EventTy = subset::ast::EventTy;
 pub type Attribute = subset::ast::Attribute;
 pub type AssignTy = subset::ast::AssignTy;
 pub type Instance = subset::ast::Instance;
+pub type Map = subset::ast::Map;
+pub type MapIter<'a> = subset::ast::MapIter<'a>;
+pub type Connections = subset::ast::Connections;
 pub type CaseBranch = subset::ast::GenericCaseBranch<Sequential>;
 pub type CaseDefault = subset::ast::GenericCaseDefault<Sequential>;
 pub type Case = subset::ast::GenericCase<Sequential>;
-pub type Function = subset::ast::GenericFunction<FunctionTy, Decl, Sequential, Ty>;
+pub type Function = subset::ast::GenericFunction<FunctionTy, Decl, Sequential, Ty, Port>;
+// tasks do not return a value
+pub type Task = subset::ast::GenericFunction<FunctionTy, Decl, Sequential, (), Port>;
 pub type Stmt = subset::ast::GenericStmt<Decl, Parallel>;
-pub type Port = subset::ast::GenericPort<Decl>;
-pub type Module = subset::ast::GenericModule<Decl, Parallel>;
+pub type GenerateBlock = subset::ast::GenericGenerateBlock<Decl, Parallel>;
+pub type GenerateFor = subset::ast::GenericGenerateFor<Decl, Parallel>;
+pub type GenerateIf = subset::ast::GenericGenerateIf<Decl, Parallel>;
+pub type Port = GenericPort<Decl>;
+// modports only name the signals of an interface
+pub type ModportPort = GenericPort<Id>;
+pub type Module = subset::ast::GenericModule<Decl, Parallel, Port>;
 pub type ExprConcat = subset::ast::ExprConcat;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Ty {
     Void,
     Int,
     // maybe use nonzero64?
     Width(u64),
+    /// A range `[msb:lsb]` whose bounds may refer to parameters.
+    Range(Expr, Expr),
+    /// A signed vector, i.e. `signed [7:0]`.
+    Signed(Rc<Ty>),
+    /// Packed dimensions, i.e. `[3:0][7:0]`, the first one is the outermost.
+    Packed(Vec<Ty>),
+    /// A reference to a type defined by a `typedef`, possibly scoped by a
+    /// package as in `pkg::state_t`.
+    Named(Id),
 }
 
-#[derive(Clone, Debug)]
+/// A port of a module, a function or a modport.
+// T ~> Declaration type
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum GenericPort<T> {
+    /// An input port.
+    Input(T),
+    /// An output port.
+    Output(T),
+    /// A bidirectional port.
+    Inout(T),
+    /// A port passed by reference.
+    Ref(T),
+    /// A port of an interface type.
+    Interface(T),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum FunctionTy {
     Default,
     Export,
     Import,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Decl {
     Int(Id, Ty),
     Logic(Id, Ty),
+    /// An unpacked array of `ty` elements, i.e. `logic [7:0] mem [16]`, a
+    /// width `n` is printed as the size `[n]`.
+    Array(Id, Ty, Vec<Ty>),
     Func(Function),
+    Task(Task),
     Param(Id, Ty, Expr),
+    LocalParam(Id, Ty, Expr),
+    Genvar(Id),
+    Typedef(Id, Typedef),
+    /// A wildcard import of every name in a package, `import pkg::*`.
+    Import(Id),
+    /// A port of an interface type, optionally restricted to a modport,
+    /// i.e. `bus_if.master m`.
+    Interface(Id, Id, Option<Id>),
+}
+
+/// The type introduced by a `typedef`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Typedef {
+    /// Another name for a type, i.e. `typedef logic [7:0] byte_t`.
+    Alias(Ty),
+    /// An enumeration with a base type and named values, a value without
+    /// an expression is the previous one plus one.
+    Enum(Ty, Vec<(Id, Option<Expr>)>),
+    /// A packed struct, the first member holds the most significant bits.
+    Struct(Vec<Decl>),
+}
+
+/// A package of declarations shared by modules.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Package {
+    /// Name of the package.
+    pub name: Id,
+    /// Declarations in this package.
+    pub body: Vec<Decl>,
+}
+
+/// The view of an interface from one side, i.e. `modport master (output
+/// valid, input ready, import send)`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Modport {
+    /// Name of the modport.
+    pub name: Id,
+    /// Signals of the interface and their direction.
+    pub ports: Vec<ModportPort>,
+    /// Tasks and functions of the interface that can be called.
+    pub imports: Vec<Id>,
 }
 
-#[derive(Clone, Debug, Default)]
+/// An interface bundling the signals shared by modules.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Interface {
+    /// Name of the interface.
+    pub name: Id,
+    /// Parameters for the interface.
+    pub params: Vec<Decl>,
+    /// Ports of this interface, i.e. a clock.
+    pub ports: Vec<Port>,
+    /// Signals, tasks and processes of this interface.
+    pub body: Vec<Stmt>,
+    /// Modports of this interface, printed after the body.
+    pub modports: Vec<Modport>,
+}
+
+/// A whole design, its modules, interfaces and packages keyed by name.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Design {
+    /// Modules of the design.
+    pub modules: BTreeMap<Id, Module>,
+    /// Interfaces of the design, they share names with modules.
+    pub interfaces: BTreeMap<Id, Interface>,
+    /// Packages of the design.
+    pub packages: BTreeMap<Id, Package>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct SequentialIfElse {
     pub cond: Option<Expr>,
     pub body: Vec<Sequential>,
@@ -48,7 +165,19 @@ pub struct SequentialIfElse {
     pub unique: bool,
 }
 
-#[derive(Clone, Debug)]
+/// A loop `for (var = init; cond; var = step)`, the loop variable is
+/// declared in the header when it has a type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SequentialFor {
+    pub ty: Option<Ty>,
+    pub var: Id,
+    pub init: Expr,
+    pub cond: Expr,
+    pub step: Expr,
+    pub body: Vec<Sequential>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Sequential {
     Error(String),
     Display(String),
@@ -60,9 +189,16 @@ pub enum Sequential {
     If(SequentialIfElse),
     Assert(Expr, Option<Rc<Sequential>>),
     SeqExpr(Expr),
+    For(SequentialFor),
+    While(Expr, Vec<Sequential>),
+    DoWhile(Vec<Sequential>, Expr),
+    Repeat(Expr, Vec<Sequential>),
+    Forever(Vec<Sequential>),
+    // array and one loop variable per dimension
+    Foreach(Expr, Vec<Id>, Vec<Sequential>),
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ProcessTy {
     AlwaysComb,
     AlwaysFF,
@@ -70,14 +206,14 @@ pub enum ProcessTy {
     Final,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct ParallelProcess {
     pub ty: ProcessTy,
     pub event: Option<Sequential>,
     pub body: Vec<Sequential>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Parallel {
     Inst(Instance),
     ParAssign(Expr, Expr),
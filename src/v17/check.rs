@@ -0,0 +1,509 @@
+use crate::subset;
+use crate::subset::instance::{check_instance, connections, instance_scope, Definition};
+use crate::v17::ast::*;
+use crate::v17::visit::{
+    walk_function, walk_module, walk_parallel, walk_sequential, walk_task, Visitor,
+};
+use crate::Error;
+
+pub type Scope = subset::width::Scope;
+pub type WidthError = subset::width::WidthError;
+
+fn ty_width(scope: &Scope, ty: &Ty) -> Option<u64> {
+    match ty {
+        Ty::Void => None,
+        Ty::Int => Some(32),
+        Ty::Width(w) => Some(*w),
+        Ty::Range(msb, lsb) => scope.range_width(msb, lsb),
+        Ty::Signed(ty) => ty_width(scope, ty),
+        Ty::Packed(dims) => dims.iter().map(|dim| ty_width(scope, dim)).product(),
+        Ty::Named(id) => scope.type_width(id),
+    }
+}
+
+/// Width of a user-defined type, a struct is as wide as all its members.
+fn typedef_width(scope: &Scope, def: &Typedef) -> Option<u64> {
+    match def {
+        Typedef::Alias(ty) | Typedef::Enum(ty, _) => ty_width(scope, ty),
+        Typedef::Struct(members) => members
+            .iter()
+            .map(|member| match member {
+                Decl::Int(_, ty) | Decl::Logic(_, ty) => ty_width(scope, ty),
+                _ => None,
+            })
+            .sum(),
+    }
+}
+
+/// Add a parameter, a value of a vector type is converted to that type.
+fn add_param(scope: &mut Scope, id: &str, ty: &Ty, expr: &Expr) {
+    match (ty, ty_width(scope, ty)) {
+        (Ty::Int, _) | (_, None) => scope.add_param(id, expr),
+        (ty, Some(width)) => scope.add_typed_param(id, expr, width, ty.is_signed()),
+    }
+}
+
+/// Width after each index into packed dimensions, i.e. `[Some(8)]` for
+/// `[3:0][7:0]`.
+fn packed_selects(scope: &Scope, ty: &Ty) -> Vec<Option<u64>> {
+    match ty {
+        Ty::Signed(ty) => packed_selects(scope, ty),
+        Ty::Packed(dims) => (1..dims.len())
+            .map(|i| dims[i..].iter().map(|dim| ty_width(scope, dim)).product())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Add a signal of type `ty`, a range that is not constant gives a signal
+/// of unknown width.
+fn add_typed(scope: &mut Scope, id: &str, ty: &Ty) {
+    match (ty, ty_width(scope, ty)) {
+        (Ty::Void, _) => (),
+        (_, width) => scope.add_dims(id, width, packed_selects(scope, ty)),
+    }
+}
+
+pub(crate) fn decl_id(decl: &Decl) -> &Id {
+    match decl {
+        Decl::Int(id, _)
+        | Decl::Logic(id, _)
+        | Decl::Array(id, _, _)
+        | Decl::Param(id, _, _)
+        | Decl::LocalParam(id, _, _)
+        | Decl::Genvar(id)
+        | Decl::Typedef(id, _)
+        | Decl::Import(id)
+        | Decl::Interface(id, _, _) => id,
+        Decl::Func(func) => &func.name,
+        Decl::Task(task) => &task.name,
+    }
+}
+
+fn add_decl(scope: &mut Scope, decl: &Decl) {
+    match decl {
+        Decl::Int(id, ty) | Decl::Logic(id, ty) => add_typed(scope, id, ty),
+        Decl::Array(id, ty, dims) => {
+            // indexing the unpacked dimensions yields elements
+            let width = ty_width(scope, ty);
+            let mut selects = vec![width; dims.len()];
+            selects.extend(packed_selects(scope, ty));
+            scope.add_dims(id, width, selects);
+        }
+        Decl::Func(func) => {
+            if let Some(width) = ty_width(scope, &func.ret) {
+                scope.add_function(&func.name, width);
+            }
+        }
+        Decl::Task(_) => (),
+        Decl::Param(id, ty, expr) | Decl::LocalParam(id, ty, expr) => {
+            add_param(scope, id, ty, expr)
+        }
+        Decl::Genvar(id) => scope.add_signal(id, 32),
+        Decl::Typedef(id, def) => {
+            if let Typedef::Enum(ty, values) = def {
+                // a value without expression follows the previous one
+                let mut next = Expr::new_int(0);
+                for (name, value) in values {
+                    let value = value.clone().unwrap_or(next);
+                    add_param(scope, name, ty, &value);
+                    next = Expr::new_add(Expr::new_ref(name), Expr::new_int(1));
+                }
+            }
+            scope.add_type(id, typedef_width(scope, def));
+        }
+        // the names of a package are added from the `Context` of `check_widths`
+        Decl::Import(_) => (),
+        // the signals of the interface are added from the `Context` too
+        Decl::Interface(id, _, _) => scope.add_unsized(id),
+    }
+}
+
+fn add_port(scope: &mut Scope, port: &Port) {
+    match port {
+        Port::Input(decl)
+        | Port::Output(decl)
+        | Port::Inout(decl)
+        | Port::Ref(decl)
+        | Port::Interface(decl) => add_decl(scope, decl),
+    }
+}
+
+/// Add the declarations of a module body, declarations in generate blocks
+/// share the scope of the module.
+fn add_stmts(scope: &mut Scope, body: &[Stmt]) {
+    for stmt in body {
+        match stmt {
+            Stmt::Decl(decl) => add_decl(scope, decl),
+            Stmt::GenFor(gen) => add_stmts(scope, gen.block().body()),
+            Stmt::GenIf(gen) => {
+                add_stmts(scope, gen.block().body());
+                if let Some(block) = gen.else_branch() {
+                    add_stmts(scope, block.body());
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+fn add_module(scope: &mut Scope, module: &Module) {
+    for param in module.params() {
+        add_decl(scope, param);
+    }
+    for port in module.ports() {
+        add_port(scope, port);
+    }
+    add_stmts(scope, module.body());
+}
+
+/// Collect the parameters, ports, declarations and functions of a module.
+pub fn module_scope(module: &Module) -> Scope {
+    let mut scope = Scope::new();
+    add_module(&mut scope, module);
+    scope
+}
+
+/// Collect the parameters, types and functions of a package.
+pub fn package_scope(package: &Package) -> Scope {
+    let mut scope = Scope::new();
+    for decl in package.body() {
+        add_decl(&mut scope, decl);
+    }
+    scope
+}
+
+/// Collect the parameters, ports, signals and functions of an interface,
+/// the signals of a port `m` of this interface are `m.name`.
+pub fn interface_scope(interface: &Interface) -> Scope {
+    let mut scope = Scope::new();
+    for param in interface.params() {
+        add_decl(&mut scope, param);
+    }
+    for port in interface.ports() {
+        add_port(&mut scope, port);
+    }
+    add_stmts(&mut scope, interface.body());
+    scope
+}
+
+/// Add the signals of the interface instances in a module body as
+/// `inst.name`, the instance itself has an unknown width.
+fn add_interface_insts(scope: &mut Scope, body: &[Stmt], interfaces: &[&Interface]) {
+    for stmt in body {
+        match stmt {
+            Stmt::Parallel(Parallel::Inst(inst)) => {
+                if let Some(interface) = interfaces.iter().find(|i| i.name == inst.prim) {
+                    scope.add_unsized(&inst.id);
+                    scope.import(&interface_scope(interface), &format!("{}.", inst.id));
+                }
+            }
+            Stmt::GenFor(gen) => add_interface_insts(scope, gen.block().body(), interfaces),
+            Stmt::GenIf(gen) => {
+                add_interface_insts(scope, gen.block().body(), interfaces);
+                if let Some(block) = gen.else_branch() {
+                    add_interface_insts(scope, block.body(), interfaces);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+struct Checker<'a> {
+    scope: Scope,
+    defs: &'a [Module],
+    packages: Vec<&'a Package>,
+    // name of the function being checked, the target of `return`
+    func: Option<Id>,
+    errors: Vec<WidthError>,
+}
+
+impl<'a> Checker<'a> {
+    fn check(&mut self, res: Result<(), WidthError>) {
+        if let Err(err) = res {
+            self.errors.push(err);
+        }
+    }
+}
+
+impl<'a> Visitor for Checker<'a> {
+    fn visit_function(&mut self, func: &Function) {
+        let outer = self.scope.clone();
+        for port in func.ports() {
+            add_port(&mut self.scope, port);
+        }
+        for decl in func.decls() {
+            add_decl(&mut self.scope, decl);
+        }
+        if func.ret != Ty::Void {
+            add_typed(&mut self.scope, &func.name, &func.ret);
+            self.func = Some(func.name.clone());
+        }
+        walk_function(self, func);
+        self.func = None;
+        self.scope = outer;
+    }
+
+    fn visit_task(&mut self, task: &Task) {
+        let outer = self.scope.clone();
+        for port in task.ports() {
+            add_port(&mut self.scope, port);
+        }
+        for decl in task.decls() {
+            add_decl(&mut self.scope, decl);
+        }
+        walk_task(self, task);
+        self.scope = outer;
+    }
+
+    fn visit_parallel(&mut self, par: &Parallel) {
+        match par {
+            Parallel::ParAssign(lexpr, rexpr) => {
+                let res = self.scope.check_assign(lexpr, rexpr);
+                self.check(res);
+            }
+            _ => walk_parallel(self, par),
+        }
+    }
+
+    fn visit_instance(&mut self, inst: &Instance) {
+        for expr in inst.params.values() {
+            self.visit_expr(expr);
+        }
+        let def = self.defs.iter().find(|def| def.name == inst.prim);
+        // the widths of the ports follow the parameter values of the instance
+        let scope = def.map(|def| {
+            instance_scope(def, inst, &self.scope, |def| {
+                unit_scope(def, &self.packages, &[])
+            })
+        });
+        for (port, expr) in connections(inst, def) {
+            let found = match expr.width(&self.scope) {
+                Ok(found) => found,
+                Err(err) => {
+                    self.errors.push(err);
+                    continue;
+                }
+            };
+            // ports that are not declared are reported by `check_instances`
+            let expected = match (port, &scope) {
+                (Some(port), Some(scope)) => Expr::new_ref(port).width(scope).ok().flatten(),
+                _ => None,
+            };
+            if let (Some(port), Some(expected), Some(found)) = (port, expected, found) {
+                if expected != found {
+                    self.errors.push(WidthError::Port {
+                        instance: inst.id.clone(),
+                        port: port.clone(),
+                        expected,
+                        found,
+                    });
+                }
+            }
+        }
+    }
+
+    fn visit_sequential(&mut self, seq: &Sequential) {
+        match seq {
+            Sequential::SeqAssign(lexpr, rexpr, _) => {
+                let res = self.scope.check_assign(lexpr, rexpr);
+                self.check(res);
+            }
+            Sequential::Return(expr) => {
+                let res = match &self.func {
+                    Some(name) => self.scope.check_assign(&Expr::Ref(name.clone()), expr),
+                    None => expr.width(&self.scope).map(|_| ()),
+                };
+                self.check(res);
+            }
+            Sequential::For(seq_for) if seq_for.ty.is_some() => {
+                let outer = self.scope.clone();
+                if let Some(ty) = &seq_for.ty {
+                    add_typed(&mut self.scope, &seq_for.var, ty);
+                }
+                walk_sequential(self, seq);
+                self.scope = outer;
+            }
+            Sequential::Foreach(_, vars, _) => {
+                let outer = self.scope.clone();
+                for var in vars {
+                    self.scope.add_signal(var, 32);
+                }
+                walk_sequential(self, seq);
+                self.scope = outer;
+            }
+            _ => walk_sequential(self, seq),
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        let res = expr.width(&self.scope).map(|_| ());
+        self.check(res);
+    }
+}
+
+/// The design units a module is checked against.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Context<'a> {
+    /// Modules that can be instantiated.
+    pub defs: &'a [Module],
+    /// Packages whose names can be used as `pkg::name`, and without the
+    /// package name once the module imports it.
+    pub packages: &'a [Package],
+    /// Interfaces whose signals can be used as `m.name` through a port or
+    /// an instance `m`.
+    pub interfaces: &'a [Interface],
+}
+
+/// Add the names of `packages` as `pkg::name`, and without the package name
+/// for the packages that `module` imports.
+fn add_packages(scope: &mut Scope, module: &Module, packages: &[&Package]) {
+    let imports: Vec<&Id> = module
+        .body()
+        .iter()
+        .filter_map(|stmt| match stmt {
+            Stmt::Decl(Decl::Import(name)) => Some(name),
+            _ => None,
+        })
+        .collect();
+    for package in packages {
+        let names = package_scope(package);
+        scope.import(&names, &format!("{}::", package.name));
+        if imports.contains(&&package.name) {
+            scope.import(&names, "");
+        }
+    }
+}
+
+/// Collect the scope of a module, with the names of the `packages` and
+/// `interfaces` it uses.
+pub(crate) fn unit_scope(
+    module: &Module,
+    packages: &[&Package],
+    interfaces: &[&Interface],
+) -> Scope {
+    let mut scope = Scope::new();
+    add_packages(&mut scope, module, packages);
+    add_module(&mut scope, module);
+    for port in module.ports() {
+        if let Decl::Interface(id, name, _) = port.decl() {
+            if let Some(interface) = interfaces.iter().find(|i| &i.name == name) {
+                scope.import(&interface_scope(interface), &format!("{}.", id));
+            }
+        }
+    }
+    add_interface_insts(&mut scope, module.body(), interfaces);
+    scope
+}
+
+/// Check the widths of assignments, returns and instance connections in a
+/// module, instance ports are checked against the modules in `ctx` with the
+/// parameter values of the instance.
+pub fn check_widths(module: &Module, ctx: &Context) -> Result<(), Vec<WidthError>> {
+    let packages: Vec<&Package> = ctx.packages.iter().collect();
+    let interfaces: Vec<&Interface> = ctx.interfaces.iter().collect();
+    let mut checker = Checker {
+        scope: unit_scope(module, &packages, &interfaces),
+        defs: ctx.defs,
+        packages,
+        func: None,
+        errors: Vec::new(),
+    };
+    walk_module(&mut checker, module);
+    if checker.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(checker.errors)
+    }
+}
+
+impl Definition for Module {
+    fn name(&self) -> &Id {
+        &self.name
+    }
+
+    fn port_names(&self) -> Vec<&Id> {
+        self.ports()
+            .iter()
+            .map(|port| decl_id(port.decl()))
+            .collect()
+    }
+
+    fn input_names(&self) -> Vec<&Id> {
+        self.ports()
+            .iter()
+            .filter_map(|port| match port {
+                Port::Input(decl) => Some(decl_id(decl)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn param_names(&self) -> Vec<&Id> {
+        let body = self.body().iter().filter_map(|stmt| match stmt {
+            Stmt::Decl(decl) => Some(decl),
+            _ => None,
+        });
+        self.params()
+            .iter()
+            .chain(body)
+            .filter_map(|decl| match decl {
+                Decl::Param(id, _, _) => Some(id),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn set_params(&mut self, values: &Map) {
+        let body = self.body.iter_mut().filter_map(|stmt| match stmt {
+            Stmt::Decl(decl) => Some(decl),
+            _ => None,
+        });
+        for decl in self.params.iter_mut().chain(body) {
+            if let Decl::Param(id, _, expr) = decl {
+                if let Some(value) = values.get(id) {
+                    *expr = value.clone();
+                }
+            }
+        }
+    }
+}
+
+struct InstanceChecker<'a> {
+    defs: &'a [&'a Module],
+    errors: Vec<Error>,
+}
+
+impl<'a> Visitor for InstanceChecker<'a> {
+    fn visit_instance(&mut self, inst: &Instance) {
+        if let Some(def) = self.defs.iter().find(|def| def.name == inst.prim) {
+            self.errors.extend(check_instance(inst, *def));
+        }
+    }
+}
+
+/// Errors of the instances in `module`, instances of modules missing from
+/// `defs` are not checked.
+pub(crate) fn instance_errors(module: &Module, defs: &[&Module]) -> Vec<Error> {
+    let mut checker = InstanceChecker {
+        defs,
+        errors: Vec::new(),
+    };
+    walk_module(&mut checker, module);
+    checker.errors
+}
+
+/// Check the instances of a module against the modules in `defs`: every
+/// connection is a port of the module, every input is connected and every
+/// parameter set is declared. The widths of the connections are checked by
+/// [`check_widths`].
+pub fn check_instances(module: &Module, defs: &[Module]) -> Result<(), Vec<Error>> {
+    let defs: Vec<&Module> = defs.iter().collect();
+    let errors = instance_errors(module, &defs);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
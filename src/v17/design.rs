@@ -0,0 +1,316 @@
+use crate::subset::instance::Definition;
+use crate::util::pretty_print::PrettyPrint;
+use crate::v17::ast::*;
+use crate::v17::check::{decl_id, instance_errors};
+use crate::v17::visit::{walk_decl, walk_expr, walk_instance, walk_ty, Visitor};
+use crate::v17::visit_mut::VisitorMut;
+use crate::Error;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A design unit, borrowed from a `Design`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Unit<'a> {
+    Package(&'a Package),
+    Interface(&'a Interface),
+    Module(&'a Module),
+}
+
+impl<'a> Unit<'a> {
+    pub fn name(&self) -> &'a Id {
+        match self {
+            Unit::Package(package) => &package.name,
+            Unit::Interface(interface) => &interface.name,
+            Unit::Module(module) => &module.name,
+        }
+    }
+
+    /// Names of the modules and interfaces, and of the packages used by this
+    /// unit.
+    fn deps(&self) -> Deps {
+        let mut deps = Deps::default();
+        match self {
+            Unit::Package(package) => deps.visit_package(package),
+            Unit::Interface(interface) => deps.visit_interface(interface),
+            Unit::Module(module) => deps.visit_module(module),
+        }
+        deps
+    }
+}
+
+/// Collect the units a design unit depends on.
+#[derive(Default)]
+struct Deps {
+    defs: BTreeSet<Id>,
+    packages: BTreeSet<Id>,
+}
+
+impl Deps {
+    /// Add the package of a scoped name, i.e. `pkg` for `pkg::name`.
+    fn add_scoped(&mut self, name: &str) {
+        if let Some((package, _)) = name.split_once("::") {
+            self.packages.insert(package.to_string());
+        }
+    }
+}
+
+impl Visitor for Deps {
+    fn visit_decl(&mut self, decl: &Decl) {
+        match decl {
+            Decl::Import(package) => {
+                self.packages.insert(package.clone());
+            }
+            Decl::Interface(_, interface, _) => {
+                self.defs.insert(interface.clone());
+            }
+            _ => (),
+        }
+        walk_decl(self, decl)
+    }
+
+    fn visit_ty(&mut self, ty: &Ty) {
+        if let Ty::Named(name) = ty {
+            self.add_scoped(name);
+        }
+        walk_ty(self, ty)
+    }
+
+    fn visit_instance(&mut self, inst: &Instance) {
+        self.defs.insert(inst.prim.clone());
+        walk_instance(self, inst)
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Ref(name) | Expr::Call(name, _) => self.add_scoped(name),
+            _ => (),
+        }
+        walk_expr(self, expr)
+    }
+}
+
+/// Parameter and port names of each module, in order.
+type Orders = BTreeMap<Id, (Vec<Id>, Vec<Id>)>;
+
+/// Reorder the connections of instances as the parameters and ports of
+/// their modules.
+struct Order<'a> {
+    orders: &'a Orders,
+}
+
+impl<'a> VisitorMut for Order<'a> {
+    fn visit_instance_mut(&mut self, inst: &mut Instance) {
+        if let Some((params, ports)) = self.orders.get(&inst.prim) {
+            inst.params.sort_by_names(params);
+            if let Connections::Named(map) = &mut inst.ports {
+                map.sort_by_names(ports);
+            }
+        }
+    }
+}
+
+impl Design {
+    pub fn new() -> Design {
+        Design::default()
+    }
+
+    pub fn modules(&self) -> &BTreeMap<Id, Module> {
+        &self.modules
+    }
+
+    pub fn interfaces(&self) -> &BTreeMap<Id, Interface> {
+        &self.interfaces
+    }
+
+    pub fn packages(&self) -> &BTreeMap<Id, Package> {
+        &self.packages
+    }
+
+    pub fn module(&self, name: &str) -> Option<&Module> {
+        self.modules.get(name)
+    }
+
+    pub fn interface(&self, name: &str) -> Option<&Interface> {
+        self.interfaces.get(name)
+    }
+
+    pub fn package(&self, name: &str) -> Option<&Package> {
+        self.packages.get(name)
+    }
+
+    /// Add a module, modules and interfaces share the same names.
+    pub fn add_module(&mut self, module: Module) -> Result<&mut Self, Error> {
+        if self.modules.contains_key(&module.name) || self.interfaces.contains_key(&module.name) {
+            return Err(Error::Duplicate(module.name));
+        }
+        self.modules.insert(module.name.clone(), module);
+        Ok(self)
+    }
+
+    pub fn add_interface(&mut self, interface: Interface) -> Result<&mut Self, Error> {
+        if self.modules.contains_key(&interface.name)
+            || self.interfaces.contains_key(&interface.name)
+        {
+            return Err(Error::Duplicate(interface.name));
+        }
+        self.interfaces.insert(interface.name.clone(), interface);
+        Ok(self)
+    }
+
+    pub fn add_package(&mut self, package: Package) -> Result<&mut Self, Error> {
+        if self.packages.contains_key(&package.name) {
+            return Err(Error::Duplicate(package.name));
+        }
+        self.packages.insert(package.name.clone(), package);
+        Ok(self)
+    }
+
+    /// The module an instance refers to, `None` for an unknown module or an
+    /// instance of an interface.
+    pub fn resolve(&self, inst: &Instance) -> Option<&Module> {
+        self.modules.get(&inst.prim)
+    }
+
+    /// Every unit, packages first, then interfaces and modules, each kind
+    /// ordered by name.
+    fn all_units(&self) -> impl Iterator<Item = Unit<'_>> {
+        let packages = self.packages.values().map(Unit::Package);
+        let interfaces = self.interfaces.values().map(Unit::Interface);
+        let modules = self.modules.values().map(Unit::Module);
+        packages.chain(interfaces).chain(modules)
+    }
+
+    /// Check that every instance, interface port and package used by a
+    /// unit is defined in this design.
+    pub fn check_definitions(&self) -> Result<(), Vec<Error>> {
+        let mut errors = Vec::new();
+        for unit in self.all_units() {
+            let deps = unit.deps();
+            let defs = deps.defs.iter().filter(|name| {
+                !self.modules.contains_key(*name) && !self.interfaces.contains_key(*name)
+            });
+            let packages = deps
+                .packages
+                .iter()
+                .filter(|name| !self.packages.contains_key(*name));
+            for name in defs.chain(packages) {
+                errors.push(Error::Undefined {
+                    unit: unit.name().clone(),
+                    name: name.clone(),
+                });
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Check every instance of a module of this design against that module,
+    /// see `check::check_instances`.
+    pub fn check_instances(&self) -> Result<(), Vec<Error>> {
+        let defs: Vec<&Module> = self.modules.values().collect();
+        let errors: Vec<Error> = self
+            .modules
+            .values()
+            .flat_map(|module| instance_errors(module, &defs))
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Reorder the named parameters and ports of every instance of a module
+    /// of this design as they are declared by that module.
+    pub fn order_connections(&mut self) {
+        let orders: Orders = self
+            .modules
+            .values()
+            .map(|module| {
+                let params = module.param_names().into_iter().cloned().collect();
+                let ports = module
+                    .ports()
+                    .iter()
+                    .map(|port| decl_id(port.decl()).clone())
+                    .collect();
+                (module.name.clone(), (params, ports))
+            })
+            .collect();
+        let mut order = Order { orders: &orders };
+        for module in self.modules.values_mut() {
+            order.visit_module_mut(module);
+        }
+    }
+
+    /// Modules that are not instantiated by any other unit, ordered by name.
+    pub fn top_modules(&self) -> Vec<&Module> {
+        let used: BTreeSet<Id> = self.all_units().flat_map(|unit| unit.deps().defs).collect();
+        self.modules
+            .values()
+            .filter(|module| !used.contains(&module.name))
+            .collect()
+    }
+
+    /// Every unit ordered so that a unit comes after the units it depends
+    /// on, ties are broken by kind and name.
+    pub fn units(&self) -> Vec<Unit<'_>> {
+        fn visit<'a>(
+            design: &'a Design,
+            unit: Unit<'a>,
+            done: &mut BTreeSet<(u8, Id)>,
+            order: &mut Vec<Unit<'a>>,
+        ) {
+            let key = match unit {
+                Unit::Package(package) => (0, package.name.clone()),
+                Unit::Interface(interface) => (1, interface.name.clone()),
+                Unit::Module(module) => (2, module.name.clone()),
+            };
+            // units are marked before their dependencies, which breaks
+            // cycles of recursive instances
+            if !done.insert(key) {
+                return;
+            }
+            let deps = unit.deps();
+            for name in &deps.packages {
+                if let Some(package) = design.packages.get(name) {
+                    visit(design, Unit::Package(package), done, order);
+                }
+            }
+            for name in &deps.defs {
+                if let Some(interface) = design.interfaces.get(name) {
+                    visit(design, Unit::Interface(interface), done, order);
+                } else if let Some(module) = design.modules.get(name) {
+                    visit(design, Unit::Module(module), done, order);
+                }
+            }
+            order.push(unit);
+        }
+        let mut done = BTreeSet::new();
+        let mut order = Vec::new();
+        for unit in self.all_units() {
+            visit(self, unit, &mut done, &mut order);
+        }
+        order
+    }
+
+    /// Write every unit to its own file `<name>.sv` in `dir`, returns the
+    /// paths in dependency order. A package can not share its name with a
+    /// module or an interface.
+    pub fn write_files<P: AsRef<Path>>(&self, dir: P) -> Result<Vec<PathBuf>, Error> {
+        self.validate()?;
+        let mut paths = Vec::new();
+        for unit in self.units() {
+            let path = dir.as_ref().join(format!("{}.sv", unit.name()));
+            if paths.contains(&path) {
+                return Err(Error::Duplicate(unit.name().clone()));
+            }
+            fs::write(&path, unit.try_to_pretty()?)?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+}
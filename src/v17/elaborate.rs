@@ -0,0 +1,312 @@
+use crate::subset::elaborate::{
+    self, inlined_params, port_connections, prefixed, AsInstance, Hierarchy,
+};
+use crate::subset::instance::Definition;
+use crate::v17::ast::*;
+use crate::v17::check::{decl_id, unit_scope, Scope};
+use crate::v17::visit::{
+    walk_decl, walk_generate_block, walk_instance, walk_sequential, walk_stmt, Visitor,
+};
+use crate::v17::visit_mut::{
+    walk_decl_mut, walk_expr_mut, walk_generate_block_mut, walk_instance_mut, walk_sequential_mut,
+    walk_stmt_mut, walk_ty_mut, VisitorMut,
+};
+use crate::Error;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Collect the names declared in a module, the members of a struct are
+/// not names of the module.
+#[derive(Default)]
+struct Names {
+    names: BTreeSet<Id>,
+}
+
+impl Visitor for Names {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::GenFor(gen) = stmt {
+            self.names.insert(gen.var.clone());
+        }
+        walk_stmt(self, stmt)
+    }
+
+    fn visit_decl(&mut self, decl: &Decl) {
+        match decl {
+            Decl::Import(_) => (),
+            Decl::Typedef(id, Typedef::Enum(_, values)) => {
+                self.names.insert(id.clone());
+                for (name, _) in values {
+                    self.names.insert(name.clone());
+                }
+            }
+            Decl::Typedef(id, Typedef::Struct(_)) => {
+                self.names.insert(id.clone());
+                return;
+            }
+            decl => {
+                self.names.insert(decl_id(decl).clone());
+            }
+        }
+        walk_decl(self, decl)
+    }
+
+    fn visit_generate_block(&mut self, block: &GenerateBlock) {
+        if let Some(label) = &block.label {
+            self.names.insert(label.clone());
+        }
+        walk_generate_block(self, block)
+    }
+
+    fn visit_instance(&mut self, inst: &Instance) {
+        self.names.insert(inst.id.clone());
+        walk_instance(self, inst)
+    }
+
+    fn visit_sequential(&mut self, seq: &Sequential) {
+        match seq {
+            Sequential::For(seq_for) if seq_for.ty.is_some() => {
+                self.names.insert(seq_for.var.clone());
+            }
+            Sequential::Foreach(_, vars, _) => self.names.extend(vars.iter().cloned()),
+            _ => (),
+        }
+        walk_sequential(self, seq)
+    }
+}
+
+/// Rename the names declared in a module and their uses.
+struct Rename<'a> {
+    names: &'a HashMap<Id, Id>,
+}
+
+impl<'a> Rename<'a> {
+    fn id(&self, id: &mut Id) {
+        if let Some(name) = self.names.get(id) {
+            *id = name.clone();
+        }
+    }
+
+    /// Rename the first part of a dotted name, i.e. `m` in `m.send`.
+    fn path(&self, name: &mut Id) {
+        let (head, tail) = match name.split_once('.') {
+            Some((head, tail)) => (head, format!(".{}", tail)),
+            None => (name.as_str(), String::new()),
+        };
+        if let Some(head) = self.names.get(head) {
+            *name = format!("{}{}", head, tail);
+        }
+    }
+}
+
+impl<'a> VisitorMut for Rename<'a> {
+    fn visit_ty_mut(&mut self, ty: &mut Ty) {
+        if let Ty::Named(id) = ty {
+            self.id(id);
+        }
+        walk_ty_mut(self, ty)
+    }
+
+    fn visit_stmt_mut(&mut self, stmt: &mut Stmt) {
+        if let Stmt::GenFor(gen) = stmt {
+            self.id(&mut gen.var);
+        }
+        walk_stmt_mut(self, stmt)
+    }
+
+    fn visit_decl_mut(&mut self, decl: &mut Decl) {
+        match decl {
+            Decl::Int(id, _)
+            | Decl::Logic(id, _)
+            | Decl::Array(id, _, _)
+            | Decl::Param(id, _, _)
+            | Decl::LocalParam(id, _, _)
+            | Decl::Genvar(id)
+            | Decl::Interface(id, _, _)
+            | Decl::Typedef(id, Typedef::Alias(_)) => self.id(id),
+            Decl::Import(_) => (),
+            Decl::Func(func) => self.id(&mut func.name),
+            Decl::Task(task) => self.id(&mut task.name),
+            Decl::Typedef(id, Typedef::Enum(_, values)) => {
+                self.id(id);
+                values.iter_mut().for_each(|(name, _)| self.id(name));
+            }
+            // members keep their names, only their types are renamed
+            Decl::Typedef(id, Typedef::Struct(members)) => {
+                self.id(id);
+                for member in members.iter_mut() {
+                    if let Decl::Int(_, ty) | Decl::Logic(_, ty) = member {
+                        self.visit_ty_mut(ty);
+                    }
+                }
+                return;
+            }
+        }
+        walk_decl_mut(self, decl)
+    }
+
+    fn visit_generate_block_mut(&mut self, block: &mut GenerateBlock) {
+        if let Some(label) = &mut block.label {
+            self.id(label);
+        }
+        walk_generate_block_mut(self, block)
+    }
+
+    fn visit_instance_mut(&mut self, inst: &mut Instance) {
+        self.id(&mut inst.id);
+        walk_instance_mut(self, inst)
+    }
+
+    fn visit_sequential_mut(&mut self, seq: &mut Sequential) {
+        match seq {
+            Sequential::For(seq_for) => self.id(&mut seq_for.var),
+            Sequential::Foreach(_, vars, _) => vars.iter_mut().for_each(|var| self.id(var)),
+            _ => (),
+        }
+        walk_sequential_mut(self, seq)
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        match expr {
+            Expr::Ref(id) => self.id(id),
+            Expr::Call(name, _) => self.path(name),
+            Expr::IPath(path, _) => {
+                if let Some(head) = path.path.first_mut() {
+                    self.id(head);
+                }
+            }
+            _ => (),
+        }
+        walk_expr_mut(self, expr)
+    }
+}
+
+/// A parameter of an inlined module, it can not be set anymore.
+fn local(decl: Decl) -> Decl {
+    match decl {
+        Decl::Param(id, ty, expr) => Decl::LocalParam(id, ty, expr),
+        decl => decl,
+    }
+}
+
+/// Statements that replace `inst` of `def`, the names declared by `def` are
+/// prefixed by the name of the instance. Ports that are not inputs or
+/// outputs are replaced by the name they are connected to.
+fn inline(inst: &Instance, def: &Module) -> Result<Vec<Stmt>, Error> {
+    let conns = port_connections(inst, def)?;
+    let mut collect = Names::default();
+    collect.visit_module(def);
+    let mut renames = prefixed(inst, collect.names);
+    for port in def.ports() {
+        let id = decl_id(port.decl());
+        match (port, conns.get(id)) {
+            (Port::Input(_), _) | (Port::Output(_), _) => (),
+            (_, Some(Expr::Ref(name))) => {
+                renames.insert(id.clone(), name.clone());
+            }
+            (Port::Interface(_), None) => {
+                return Err(Error::Invalid(format!(
+                    "instance `{}` leaves interface port `{}` unconnected",
+                    inst.id, id
+                )))
+            }
+            (_, None) => (),
+            (_, Some(expr)) => {
+                return Err(Error::Invalid(format!(
+                    "instance `{}` connects port `{}` to `{}`, which is not a name",
+                    inst.id, id, expr
+                )))
+            }
+        }
+    }
+    let mut module = def.clone();
+    Rename { names: &renames }.visit_module_mut(&mut module);
+    // the values of the instance refer to names outside of the module
+    module.set_params(&inlined_params(inst, &renames));
+    let mut stmts: Vec<Stmt> = module
+        .params
+        .into_iter()
+        .map(|decl| Stmt::Decl(local(decl)))
+        .collect();
+    for (port, renamed) in def.ports().iter().zip(module.ports) {
+        let conn = conns.get(decl_id(port.decl()));
+        let local = Expr::new_ref(decl_id(renamed.decl()));
+        match renamed {
+            Port::Input(decl) => {
+                stmts.push(Stmt::Decl(decl));
+                if let Some(expr) = conn {
+                    stmts.push(Stmt::Parallel(Parallel::ParAssign(local, expr.clone())));
+                }
+            }
+            Port::Output(decl) => {
+                stmts.push(Stmt::Decl(decl));
+                if let Some(expr) = conn {
+                    stmts.push(Stmt::Parallel(Parallel::ParAssign(expr.clone(), local)));
+                }
+            }
+            Port::Inout(decl) | Port::Ref(decl) if conn.is_none() => stmts.push(Stmt::Decl(decl)),
+            _ => (),
+        }
+    }
+    stmts.extend(module.body.into_iter().map(|stmt| match stmt {
+        Stmt::Decl(decl) => Stmt::Decl(local(decl)),
+        stmt => stmt,
+    }));
+    Ok(stmts)
+}
+
+impl AsInstance for Parallel {
+    fn as_instance_mut(&mut self) -> Option<&mut Instance> {
+        match self {
+            Parallel::Inst(inst) => Some(inst),
+            _ => None,
+        }
+    }
+}
+
+impl Hierarchy<Decl, Parallel, Port> for Design {
+    fn modules(&self) -> &BTreeMap<Id, Module> {
+        &self.modules
+    }
+
+    fn scope(&self, module: &Module) -> Scope {
+        let packages: Vec<&Package> = self.packages.values().collect();
+        let interfaces: Vec<&Interface> = self.interfaces.values().collect();
+        unit_scope(module, &packages, &interfaces)
+    }
+
+    fn inline(&self, inst: &Instance, def: &Module) -> Result<Vec<Stmt>, Error> {
+        inline(inst, def)
+    }
+}
+
+impl Design {
+    /// Elaborate the hierarchy below `top` with its parameters set to
+    /// `params`. Every module instantiated is specialized for the values
+    /// of its parameters, a module with parameters is renamed after them,
+    /// i.e. `fifo__WIDTH_16__DEPTH_4`. The result holds `top` and the
+    /// specialized modules, with the interfaces and packages of this design.
+    pub fn elaborate(&self, top: &str, params: &Map) -> Result<Design, Error> {
+        Ok(Design {
+            modules: elaborate::elaborate(self, top, params)?,
+            interfaces: self.interfaces.clone(),
+            packages: self.packages.clone(),
+        })
+    }
+
+    /// Inline the instance `inst` of `module`, together with the hierarchy
+    /// below it. The names of an inlined module are prefixed by the names of
+    /// the instances above them, i.e. `u0__u1__x`, and its parameters are
+    /// turned into local parameters.
+    pub fn flatten(&mut self, module: &str, inst: &str) -> Result<&mut Self, Error> {
+        let flat = elaborate::flatten(self, module, inst)?;
+        self.modules.insert(flat.name.clone(), flat);
+        Ok(self)
+    }
+
+    /// Inline every instance of a module of this design in `module`, see
+    /// `flatten`. The modules that are inlined stay in the design.
+    pub fn flatten_all(&mut self, module: &str) -> Result<&mut Self, Error> {
+        let flat = elaborate::flatten_all(self, module)?;
+        self.modules.insert(flat.name.clone(), flat);
+        Ok(self)
+    }
+}
@@ -1,4 +1,13 @@
 use super::ast::*;
+use super::parser::{parse_design, parse_interface, parse_module, parse_package, ParseError};
+use crate::Error;
+use std::str::FromStr;
+
+impl From<SequentialFor> for Sequential {
+    fn from(seq: SequentialFor) -> Self {
+        Sequential::For(seq)
+    }
+}
 
 impl From<SequentialIfElse> for Sequential {
     fn from(seq: SequentialIfElse) -> Self {
@@ -29,3 +38,59 @@ impl From<Function> for Decl {
         Decl::Func(function)
     }
 }
+
+impl From<Task> for Decl {
+    fn from(task: Task) -> Self {
+        Decl::Task(task)
+    }
+}
+
+impl From<GenerateFor> for Stmt {
+    fn from(gen: GenerateFor) -> Self {
+        Stmt::GenFor(gen)
+    }
+}
+
+impl From<GenerateIf> for Stmt {
+    fn from(gen: GenerateIf) -> Self {
+        Stmt::GenIf(gen)
+    }
+}
+
+impl From<u64> for Ty {
+    fn from(width: u64) -> Self {
+        Ty::Width(width)
+    }
+}
+
+impl FromStr for Module {
+    type Err = ParseError;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        parse_module(src)
+    }
+}
+
+impl FromStr for Package {
+    type Err = ParseError;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        parse_package(src)
+    }
+}
+
+impl FromStr for Interface {
+    type Err = ParseError;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        parse_interface(src)
+    }
+}
+
+impl FromStr for Design {
+    type Err = Error;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        parse_design(src)
+    }
+}
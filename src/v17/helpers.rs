@@ -1,4 +1,5 @@
 use crate::v17::ast::*;
+use crate::Error;
 use std::rc::Rc;
 
 impl Ty {
@@ -6,22 +7,113 @@ impl Ty {
         Ty::Int
     }
 
+    /// # Panics
+    ///
+    /// Panics if `width` is zero, see `try_new_width`.
     pub fn new_width(width: u64) -> Ty {
-        assert!(width > 0, "Error: width must be greater than zero");
-        Ty::Width(width)
+        match Ty::try_new_width(width) {
+            Ok(ty) => ty,
+            Err(err) => panic!("Error: {}", err),
+        }
+    }
+
+    pub fn try_new_width(width: u64) -> Result<Ty, Error> {
+        if width > 0 {
+            Ok(Ty::Width(width))
+        } else {
+            Err(Error::invalid("width must be greater than zero"))
+        }
+    }
+
+    /// Create the range `[msb:lsb]`, a constant range `[n:0]` is the same as
+    /// `Ty::Width(n + 1)`.
+    pub fn new_range<M, L>(msb: M, lsb: L) -> Ty
+    where
+        M: Into<Expr>,
+        L: Into<Expr>,
+    {
+        match (msb.into(), lsb.into()) {
+            (Expr::Int(msb), Expr::Int(0)) if msb >= 0 => Ty::Width(msb as u64 + 1),
+            (msb, lsb) => Ty::Range(msb, lsb),
+        }
+    }
+
+    /// Make `ty` signed, integers are already signed and stay the same.
+    pub fn new_signed<T>(ty: T) -> Ty
+    where
+        T: Into<Ty>,
+    {
+        match ty.into() {
+            ty @ Ty::Int | ty @ Ty::Signed(_) => ty,
+            ty => Ty::Signed(Rc::new(ty)),
+        }
+    }
+
+    /// Create packed dimensions, the first one is the outermost, a single
+    /// dimension is returned as it is.
+    pub fn new_packed<I, T>(dims: I) -> Ty
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<Ty>,
+    {
+        let mut dims: Vec<Ty> = dims.into_iter().map(|d| d.into()).collect();
+        if dims.len() == 1 {
+            dims.remove(0)
+        } else {
+            Ty::Packed(dims)
+        }
+    }
+
+    /// Refer to a type defined by a `typedef`, i.e. `state_t` or
+    /// `pkg::state_t`.
+    pub fn new_named(name: &str) -> Ty {
+        Ty::Named(name.to_string())
+    }
+
+    pub fn is_signed(&self) -> bool {
+        matches!(self, Ty::Int | Ty::Signed(_))
     }
 
     pub fn width(&self) -> u64 {
         match self {
             Ty::Width(w) => *w,
+            Ty::Range(Expr::Int(msb), Expr::Int(lsb)) => (msb - lsb).unsigned_abs() as u64 + 1,
+            Ty::Signed(ty) => ty.width(),
+            Ty::Packed(dims) => dims.iter().map(|d| d.width()).product(),
             _ => panic!("Error: type does not support width"),
         }
     }
 }
 
+impl<T> GenericPort<T> {
+    pub fn decl(&self) -> &T {
+        match self {
+            GenericPort::Input(decl)
+            | GenericPort::Output(decl)
+            | GenericPort::Inout(decl)
+            | GenericPort::Ref(decl)
+            | GenericPort::Interface(decl) => decl,
+        }
+    }
+
+    /// Constructor of a port with the same direction as this one.
+    pub fn direction(&self) -> fn(T) -> GenericPort<T> {
+        match self {
+            GenericPort::Input(_) => GenericPort::Input,
+            GenericPort::Output(_) => GenericPort::Output,
+            GenericPort::Inout(_) => GenericPort::Inout,
+            GenericPort::Ref(_) => GenericPort::Ref,
+            GenericPort::Interface(_) => GenericPort::Interface,
+        }
+    }
+}
+
 impl Port {
-    pub fn new_input(name: &str, width: u64) -> Port {
-        let ty = Ty::Width(width);
+    pub fn new_input<W>(name: &str, width: W) -> Port
+    where
+        W: Into<Ty>,
+    {
+        let ty = width.into();
         let logic = Decl::Logic(name.to_string(), ty);
         Port::Input(logic)
     }
@@ -31,11 +123,75 @@ impl Port {
         Port::Input(inp)
     }
 
-    pub fn new_output(name: &str, width: u64) -> Port {
-        let ty = Ty::Width(width);
+    pub fn new_signed_input<W>(name: &str, width: W) -> Port
+    where
+        W: Into<Ty>,
+    {
+        Port::new_input(name, Ty::new_signed(width))
+    }
+
+    pub fn new_output<W>(name: &str, width: W) -> Port
+    where
+        W: Into<Ty>,
+    {
+        let ty = width.into();
         let logic = Decl::Logic(name.to_string(), ty);
         Port::Output(logic)
     }
+
+    pub fn new_signed_output<W>(name: &str, width: W) -> Port
+    where
+        W: Into<Ty>,
+    {
+        Port::new_output(name, Ty::new_signed(width))
+    }
+
+    pub fn new_input_array<W, I, D>(name: &str, ty: W, dims: I) -> Port
+    where
+        W: Into<Ty>,
+        I: IntoIterator<Item = D>,
+        D: Into<Ty>,
+    {
+        Port::Input(Decl::new_array(name, ty, dims))
+    }
+
+    pub fn new_output_array<W, I, D>(name: &str, ty: W, dims: I) -> Port
+    where
+        W: Into<Ty>,
+        I: IntoIterator<Item = D>,
+        D: Into<Ty>,
+    {
+        Port::Output(Decl::new_array(name, ty, dims))
+    }
+
+    pub fn new_inout<W>(name: &str, width: W) -> Port
+    where
+        W: Into<Ty>,
+    {
+        let ty = width.into();
+        let logic = Decl::Logic(name.to_string(), ty);
+        Port::Inout(logic)
+    }
+
+    pub fn new_ref<W>(name: &str, width: W) -> Port
+    where
+        W: Into<Ty>,
+    {
+        let ty = width.into();
+        let logic = Decl::Logic(name.to_string(), ty);
+        Port::Ref(logic)
+    }
+
+    /// Create a port of an interface type, i.e. `bus_if m`.
+    pub fn new_interface(name: &str, interface: &str) -> Port {
+        Port::Interface(Decl::new_interface(name, interface, None))
+    }
+
+    /// Create a port of an interface type restricted to a modport, i.e.
+    /// `bus_if.master m`.
+    pub fn new_modport(name: &str, interface: &str, modport: &str) -> Port {
+        Port::Interface(Decl::new_interface(name, interface, Some(modport)))
+    }
 }
 
 impl CaseBranch {
@@ -177,6 +333,40 @@ impl Sequential {
         Sequential::SeqCase(case)
     }
 
+    pub fn new_while<E>(cond: E, body: Vec<Sequential>) -> Sequential
+    where
+        E: Into<Expr>,
+    {
+        Sequential::While(cond.into(), body)
+    }
+
+    pub fn new_repeat<E>(times: E, body: Vec<Sequential>) -> Sequential
+    where
+        E: Into<Expr>,
+    {
+        Sequential::Repeat(times.into(), body)
+    }
+
+    pub fn new_forever(body: Vec<Sequential>) -> Sequential {
+        Sequential::Forever(body)
+    }
+
+    pub fn new_do_while<E>(body: Vec<Sequential>, cond: E) -> Sequential
+    where
+        E: Into<Expr>,
+    {
+        Sequential::DoWhile(body, cond.into())
+    }
+
+    /// Loop over `array` with one loop variable per dimension.
+    pub fn new_foreach<E>(array: E, vars: &[&str], body: Vec<Sequential>) -> Sequential
+    where
+        E: Into<Expr>,
+    {
+        let vars = vars.iter().map(|var| var.to_string()).collect();
+        Sequential::Foreach(array.into(), vars, body)
+    }
+
     pub fn new_call<E>(call: E) -> Sequential
     where
         E: Into<Expr>,
@@ -224,6 +414,52 @@ impl SequentialIfElse {
     }
 }
 
+impl SequentialFor {
+    /// Create a loop over `var` from `init` while `cond` holds, `step` is
+    /// the next value of `var`.
+    pub fn new<I, C, S>(var: &str, init: I, cond: C, step: S) -> SequentialFor
+    where
+        I: Into<Expr>,
+        C: Into<Expr>,
+        S: Into<Expr>,
+    {
+        SequentialFor {
+            ty: None,
+            var: var.to_string(),
+            init: init.into(),
+            cond: cond.into(),
+            step: step.into(),
+            body: Vec::new(),
+        }
+    }
+
+    /// Create the usual `for (var = 0; var < bound; var = var + 1)` loop.
+    pub fn new_range<E>(var: &str, bound: E) -> SequentialFor
+    where
+        E: Into<Expr>,
+    {
+        SequentialFor::new(var, 0, Expr::new_lt(var, bound), Expr::new_add(var, 1))
+    }
+
+    /// Declare the loop variable in the loop header.
+    pub fn set_ty(&mut self, ty: Ty) -> &mut Self {
+        self.ty = Some(ty);
+        self
+    }
+
+    pub fn body(&self) -> &Vec<Sequential> {
+        &self.body
+    }
+
+    pub fn add_seq<S>(&mut self, seq: S) -> &mut Self
+    where
+        S: Into<Sequential>,
+    {
+        self.body.push(seq.into());
+        self
+    }
+}
+
 impl ParallelProcess {
     pub fn new_always_comb() -> Self {
         ParallelProcess {
@@ -345,7 +581,10 @@ impl Function {
         &self.ty
     }
 
-    pub fn add_input(&mut self, name: &str, width: u64) -> &mut Self {
+    pub fn add_input<W>(&mut self, name: &str, width: W) -> &mut Self
+    where
+        W: Into<Ty>,
+    {
         self.ports.push(Port::new_input(name, width));
         self
     }
@@ -355,12 +594,18 @@ impl Function {
         self
     }
 
-    pub fn add_output(&mut self, name: &str, width: u64) -> &mut Self {
+    pub fn add_output<W>(&mut self, name: &str, width: W) -> &mut Self
+    where
+        W: Into<Ty>,
+    {
         self.ports.push(Port::new_output(name, width));
         self
     }
 
-    pub fn add_logic(&mut self, name: &str, width: u64) -> &mut Self {
+    pub fn add_logic<W>(&mut self, name: &str, width: W) -> &mut Self
+    where
+        W: Into<Ty>,
+    {
         self.decls.push(Decl::new_logic(name, width));
         self
     }
@@ -386,6 +631,63 @@ impl Function {
     }
 }
 
+impl Task {
+    pub fn new(name: &str) -> Task {
+        Task {
+            ty: FunctionTy::Default,
+            name: name.to_string(),
+            ports: Vec::new(),
+            decls: Vec::new(),
+            body: Vec::new(),
+            ret: (),
+        }
+    }
+
+    pub fn ports(&self) -> &Vec<Port> {
+        &self.ports
+    }
+
+    pub fn decls(&self) -> &Vec<Decl> {
+        &self.decls
+    }
+
+    pub fn body(&self) -> &Vec<Sequential> {
+        &self.body
+    }
+
+    pub fn add_input<W>(&mut self, name: &str, width: W) -> &mut Self
+    where
+        W: Into<Ty>,
+    {
+        self.ports.push(Port::new_input(name, width));
+        self
+    }
+
+    pub fn add_output<W>(&mut self, name: &str, width: W) -> &mut Self
+    where
+        W: Into<Ty>,
+    {
+        self.ports.push(Port::new_output(name, width));
+        self
+    }
+
+    pub fn add_logic<W>(&mut self, name: &str, width: W) -> &mut Self
+    where
+        W: Into<Ty>,
+    {
+        self.decls.push(Decl::new_logic(name, width));
+        self
+    }
+
+    pub fn add_stmt<S>(&mut self, stmt: S) -> &mut Self
+    where
+        S: Into<Sequential>,
+    {
+        self.body.push(stmt.into());
+        self
+    }
+}
+
 impl Decl {
     pub fn new_param_uint(name: &str, value: u32) -> Decl {
         Decl::Param(
@@ -395,11 +697,12 @@ impl Decl {
         )
     }
 
-    pub fn new_logic<S>(name: S, width: u64) -> Decl
+    pub fn new_logic<S, W>(name: S, width: W) -> Decl
     where
         S: AsRef<str>,
+        W: Into<Ty>,
     {
-        Decl::Logic(name.as_ref().to_string(), Ty::new_width(width))
+        Decl::Logic(name.as_ref().to_string(), width.into())
     }
 
     pub fn new_int<S>(name: S) -> Decl
@@ -409,9 +712,226 @@ impl Decl {
         Decl::Int(name.as_ref().to_string(), Ty::Int)
     }
 
+    /// Create an unpacked array of `ty` elements, a width `n` in `dims` is
+    /// the size `[n]`.
+    pub fn new_array<S, W, I, D>(name: S, ty: W, dims: I) -> Decl
+    where
+        S: AsRef<str>,
+        W: Into<Ty>,
+        I: IntoIterator<Item = D>,
+        D: Into<Ty>,
+    {
+        let dims = dims.into_iter().map(|d| d.into()).collect();
+        Decl::Array(name.as_ref().to_string(), ty.into(), dims)
+    }
+
     pub fn new_func(func: Function) -> Decl {
         Decl::Func(func)
     }
+
+    pub fn new_task(task: Task) -> Decl {
+        Decl::Task(task)
+    }
+
+    /// Declare `name` of an interface type, optionally restricted to a
+    /// modport.
+    pub fn new_interface(name: &str, interface: &str, modport: Option<&str>) -> Decl {
+        Decl::Interface(
+            name.to_string(),
+            interface.to_string(),
+            modport.map(|m| m.to_string()),
+        )
+    }
+
+    pub fn new_localparam<W, E>(name: &str, ty: W, value: E) -> Decl
+    where
+        W: Into<Ty>,
+        E: Into<Expr>,
+    {
+        Decl::LocalParam(name.to_string(), ty.into(), value.into())
+    }
+
+    pub fn new_typedef<W>(name: &str, ty: W) -> Decl
+    where
+        W: Into<Ty>,
+    {
+        Decl::Typedef(name.to_string(), Typedef::Alias(ty.into()))
+    }
+
+    /// Create an enumeration whose values are numbered from zero.
+    pub fn new_enum<W>(name: &str, ty: W, values: &[&str]) -> Decl
+    where
+        W: Into<Ty>,
+    {
+        let values = values.iter().map(|v| (v.to_string(), None)).collect();
+        Decl::Typedef(name.to_string(), Typedef::Enum(ty.into(), values))
+    }
+
+    /// Create a packed struct, the first member holds the most significant
+    /// bits.
+    pub fn new_struct(name: &str, members: Vec<Decl>) -> Decl {
+        Decl::Typedef(name.to_string(), Typedef::Struct(members))
+    }
+
+    pub fn new_import(package: &str) -> Decl {
+        Decl::Import(package.to_string())
+    }
+
+    pub fn new_genvar(name: &str) -> Decl {
+        Decl::Genvar(name.to_string())
+    }
+}
+
+impl GenerateBlock {
+    pub fn new() -> GenerateBlock {
+        GenerateBlock {
+            label: None,
+            body: Vec::new(),
+        }
+    }
+
+    pub fn new_labeled(label: &str) -> GenerateBlock {
+        GenerateBlock {
+            label: Some(label.to_string()),
+            body: Vec::new(),
+        }
+    }
+
+    pub fn label(&self) -> Option<&Id> {
+        self.label.as_ref()
+    }
+
+    pub fn body(&self) -> &Vec<Stmt> {
+        &self.body
+    }
+
+    pub fn set_label(&mut self, label: &str) {
+        self.label = Some(label.to_string());
+    }
+
+    pub fn add_decl(&mut self, decl: Decl) -> &mut Self {
+        self.body.push(Stmt::new_decl(decl));
+        self
+    }
+
+    pub fn add_instance(&mut self, inst: Instance) -> &mut Self {
+        self.body.push(Stmt::new_parallel(Parallel::new_inst(inst)));
+        self
+    }
+
+    pub fn add_stmt(&mut self, stmt: Stmt) -> &mut Self {
+        self.body.push(stmt);
+        self
+    }
+}
+
+impl Default for GenerateBlock {
+    fn default() -> GenerateBlock {
+        GenerateBlock::new()
+    }
+}
+
+impl GenerateFor {
+    /// Create a loop over `var` from `init` while `cond` holds, `step` is
+    /// the next value of `var`.
+    pub fn new<I, C, S>(var: &str, init: I, cond: C, step: S) -> GenerateFor
+    where
+        I: Into<Expr>,
+        C: Into<Expr>,
+        S: Into<Expr>,
+    {
+        GenerateFor {
+            var: var.to_string(),
+            init: init.into(),
+            cond: cond.into(),
+            step: step.into(),
+            block: GenerateBlock::new(),
+        }
+    }
+
+    /// Create the usual `for (var = 0; var < bound; var = var + 1)` loop.
+    pub fn new_range<E>(var: &str, bound: E) -> GenerateFor
+    where
+        E: Into<Expr>,
+    {
+        GenerateFor::new(var, 0, Expr::new_lt(var, bound), Expr::new_add(var, 1))
+    }
+
+    pub fn block(&self) -> &GenerateBlock {
+        &self.block
+    }
+
+    pub fn set_label(&mut self, label: &str) -> &mut Self {
+        self.block.set_label(label);
+        self
+    }
+
+    pub fn add_decl(&mut self, decl: Decl) -> &mut Self {
+        self.block.add_decl(decl);
+        self
+    }
+
+    pub fn add_instance(&mut self, inst: Instance) -> &mut Self {
+        self.block.add_instance(inst);
+        self
+    }
+
+    pub fn add_stmt(&mut self, stmt: Stmt) -> &mut Self {
+        self.block.add_stmt(stmt);
+        self
+    }
+}
+
+impl GenerateIf {
+    pub fn new<E>(cond: E) -> GenerateIf
+    where
+        E: Into<Expr>,
+    {
+        GenerateIf {
+            cond: cond.into(),
+            block: GenerateBlock::new(),
+            else_branch: None,
+        }
+    }
+
+    pub fn block(&self) -> &GenerateBlock {
+        &self.block
+    }
+
+    pub fn else_branch(&self) -> Option<&GenerateBlock> {
+        self.else_branch.as_ref()
+    }
+
+    pub fn set_label(&mut self, label: &str) -> &mut Self {
+        self.block.set_label(label);
+        self
+    }
+
+    pub fn add_decl(&mut self, decl: Decl) -> &mut Self {
+        self.block.add_decl(decl);
+        self
+    }
+
+    pub fn add_instance(&mut self, inst: Instance) -> &mut Self {
+        self.block.add_instance(inst);
+        self
+    }
+
+    pub fn add_stmt(&mut self, stmt: Stmt) -> &mut Self {
+        self.block.add_stmt(stmt);
+        self
+    }
+
+    pub fn set_else(&mut self, block: GenerateBlock) {
+        self.else_branch = Some(block);
+    }
+
+    /// Chain another conditional, printed as `else if`.
+    pub fn set_else_if(&mut self, gen: GenerateIf) {
+        let mut block = GenerateBlock::new();
+        block.add_stmt(Stmt::from(gen));
+        self.else_branch = Some(block);
+    }
 }
 
 impl Module {
@@ -445,26 +965,118 @@ impl Module {
         &self.attr
     }
 
-    pub fn add_input(&mut self, name: &str, width: u64) -> &mut Self {
+    /// Collect the values of the parameters declared in the module header
+    /// and body, used to evaluate constant expressions.
+    pub fn param_env(&self) -> Env {
+        let body = self.body.iter().filter_map(|stmt| match stmt {
+            Stmt::Decl(decl) => Some(decl),
+            _ => None,
+        });
+        self.params
+            .iter()
+            .chain(body)
+            .filter_map(|decl| match decl {
+                Decl::Param(id, _, expr) | Decl::LocalParam(id, _, expr) => {
+                    Some((id.clone(), expr.clone()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn add_param(&mut self, param: Decl) -> &mut Self {
+        self.params.push(param);
+        self
+    }
+
+    pub fn add_port(&mut self, port: Port) -> &mut Self {
+        self.ports.push(port);
+        self
+    }
+
+    pub fn add_input<W>(&mut self, name: &str, width: W) -> &mut Self
+    where
+        W: Into<Ty>,
+    {
         self.ports.push(Port::new_input(name, width));
         self
     }
 
-    pub fn add_output(&mut self, name: &str, width: u64) -> &mut Self {
+    pub fn add_output<W>(&mut self, name: &str, width: W) -> &mut Self
+    where
+        W: Into<Ty>,
+    {
         self.ports.push(Port::new_output(name, width));
         self
     }
 
+    pub fn add_signed_input<W>(&mut self, name: &str, width: W) -> &mut Self
+    where
+        W: Into<Ty>,
+    {
+        self.ports.push(Port::new_signed_input(name, width));
+        self
+    }
+
+    pub fn add_signed_output<W>(&mut self, name: &str, width: W) -> &mut Self
+    where
+        W: Into<Ty>,
+    {
+        self.ports.push(Port::new_signed_output(name, width));
+        self
+    }
+
+    pub fn add_inout<W>(&mut self, name: &str, width: W) -> &mut Self
+    where
+        W: Into<Ty>,
+    {
+        self.ports.push(Port::new_inout(name, width));
+        self
+    }
+
+    pub fn add_ref<W>(&mut self, name: &str, width: W) -> &mut Self
+    where
+        W: Into<Ty>,
+    {
+        self.ports.push(Port::new_ref(name, width));
+        self
+    }
+
     pub fn add_decl(&mut self, decl: Decl) -> &mut Self {
         self.body.push(Stmt::new_decl(decl));
         self
     }
 
+    pub fn add_import(&mut self, package: &str) -> &mut Self {
+        self.body.push(Stmt::new_decl(Decl::new_import(package)));
+        self
+    }
+
+    pub fn add_genvar(&mut self, name: &str) -> &mut Self {
+        self.body.push(Stmt::new_decl(Decl::new_genvar(name)));
+        self
+    }
+
+    pub fn add_generate_for(&mut self, gen: GenerateFor) -> &mut Self {
+        self.body.push(Stmt::from(gen));
+        self
+    }
+
+    pub fn add_generate_if(&mut self, gen: GenerateIf) -> &mut Self {
+        self.body.push(Stmt::from(gen));
+        self
+    }
+
     pub fn add_function(&mut self, func: Function) -> &mut Self {
         self.body.push(Stmt::new_decl(Decl::new_func(func)));
         self
     }
 
+    pub fn add_task(&mut self, task: Task) -> &mut Self {
+        self.body.push(Stmt::new_decl(Decl::new_task(task)));
+        self
+    }
+
     pub fn add_instance(&mut self, inst: Instance) -> &mut Self {
         self.body.push(Stmt::new_parallel(Parallel::new_inst(inst)));
         self
@@ -485,3 +1097,165 @@ impl Module {
         self.attr = attr;
     }
 }
+
+impl Package {
+    pub fn new(name: &str) -> Package {
+        Package {
+            name: name.to_string(),
+            body: Vec::new(),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        self.name.to_string()
+    }
+
+    pub fn body(&self) -> &Vec<Decl> {
+        &self.body
+    }
+
+    pub fn add_decl(&mut self, decl: Decl) -> &mut Self {
+        self.body.push(decl);
+        self
+    }
+
+    pub fn add_localparam<W, E>(&mut self, name: &str, ty: W, value: E) -> &mut Self
+    where
+        W: Into<Ty>,
+        E: Into<Expr>,
+    {
+        self.body.push(Decl::new_localparam(name, ty, value));
+        self
+    }
+
+    pub fn add_function(&mut self, func: Function) -> &mut Self {
+        self.body.push(Decl::new_func(func));
+        self
+    }
+}
+
+impl Modport {
+    pub fn new(name: &str) -> Modport {
+        Modport {
+            name: name.to_string(),
+            ports: Vec::new(),
+            imports: Vec::new(),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        self.name.to_string()
+    }
+
+    pub fn ports(&self) -> &Vec<ModportPort> {
+        &self.ports
+    }
+
+    pub fn imports(&self) -> &Vec<Id> {
+        &self.imports
+    }
+
+    pub fn add_input(&mut self, name: &str) -> &mut Self {
+        self.ports.push(ModportPort::Input(name.to_string()));
+        self
+    }
+
+    pub fn add_output(&mut self, name: &str) -> &mut Self {
+        self.ports.push(ModportPort::Output(name.to_string()));
+        self
+    }
+
+    pub fn add_inout(&mut self, name: &str) -> &mut Self {
+        self.ports.push(ModportPort::Inout(name.to_string()));
+        self
+    }
+
+    /// Allow calling the task or function `name` of the interface.
+    pub fn add_import(&mut self, name: &str) -> &mut Self {
+        self.imports.push(name.to_string());
+        self
+    }
+}
+
+impl Interface {
+    pub fn new(name: &str) -> Interface {
+        Interface {
+            name: name.to_string(),
+            params: Vec::new(),
+            ports: Vec::new(),
+            body: Vec::new(),
+            modports: Vec::new(),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        self.name.to_string()
+    }
+
+    pub fn params(&self) -> &Vec<Decl> {
+        &self.params
+    }
+
+    pub fn ports(&self) -> &Vec<Port> {
+        &self.ports
+    }
+
+    pub fn body(&self) -> &Vec<Stmt> {
+        &self.body
+    }
+
+    pub fn modports(&self) -> &Vec<Modport> {
+        &self.modports
+    }
+
+    pub fn add_param(&mut self, param: Decl) -> &mut Self {
+        self.params.push(param);
+        self
+    }
+
+    pub fn add_port(&mut self, port: Port) -> &mut Self {
+        self.ports.push(port);
+        self
+    }
+
+    pub fn add_input<W>(&mut self, name: &str, width: W) -> &mut Self
+    where
+        W: Into<Ty>,
+    {
+        self.ports.push(Port::new_input(name, width));
+        self
+    }
+
+    pub fn add_logic<W>(&mut self, name: &str, width: W) -> &mut Self
+    where
+        W: Into<Ty>,
+    {
+        self.body.push(Stmt::new_decl(Decl::new_logic(name, width)));
+        self
+    }
+
+    pub fn add_decl(&mut self, decl: Decl) -> &mut Self {
+        self.body.push(Stmt::new_decl(decl));
+        self
+    }
+
+    pub fn add_function(&mut self, func: Function) -> &mut Self {
+        self.body.push(Stmt::new_decl(Decl::new_func(func)));
+        self
+    }
+
+    pub fn add_task(&mut self, task: Task) -> &mut Self {
+        self.body.push(Stmt::new_decl(Decl::new_task(task)));
+        self
+    }
+
+    pub fn add_stmt(&mut self, stmt: Stmt) -> &mut Self {
+        self.body.push(stmt);
+        self
+    }
+
+    pub fn add_modport(&mut self, modport: Modport) -> &mut Self {
+        self.modports.push(modport);
+        self
+    }
+}
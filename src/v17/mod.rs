@@ -1,5 +1,11 @@
 pub mod ast;
+pub mod check;
+pub mod design;
 pub mod display;
+pub mod elaborate;
 pub mod from;
 pub mod helpers;
+pub mod parser;
 pub mod pretty_print;
+pub mod visit;
+pub mod visit_mut;
@@ -0,0 +1,865 @@
+use crate::subset;
+use crate::subset::parser::Parser;
+use crate::v17::ast::*;
+use crate::Error;
+use std::rc::Rc;
+
+pub type ParseError = subset::parser::ParseError;
+
+/// Parse every module in a SystemVerilog-2017 source text.
+pub fn parse_modules(src: &str) -> Result<Vec<Module>, ParseError> {
+    let mut p = Parser::new(src)?;
+    let mut modules = Vec::new();
+    while !p.is_eof() {
+        let attr = p.parse_attributes()?;
+        modules.push(parse_module_with_attr(&mut p, attr)?);
+    }
+    Ok(modules)
+}
+
+/// Parse every module, interface and package in a SystemVerilog-2017 source
+/// text into a design, a unit defined twice is an `Error::Duplicate`.
+pub fn parse_design(src: &str) -> Result<Design, Error> {
+    let mut p = Parser::new(src)?;
+    let mut design = Design::new();
+    while !p.is_eof() {
+        let attr = p.parse_attributes()?;
+        if p.is_keyword("package") && attr.attrs().is_empty() {
+            design.add_package(parse_package_body(&mut p)?)?;
+        } else if p.is_keyword("interface") && attr.attrs().is_empty() {
+            design.add_interface(parse_interface_body(&mut p)?)?;
+        } else {
+            design.add_module(parse_module_with_attr(&mut p, attr)?)?;
+        }
+    }
+    Ok(design)
+}
+
+/// Parse a SystemVerilog-2017 source text that contains exactly one package.
+pub fn parse_package(src: &str) -> Result<Package, ParseError> {
+    let mut p = Parser::new(src)?;
+    let package = parse_package_body(&mut p)?;
+    if p.is_eof() {
+        Ok(package)
+    } else {
+        Err(p.unexpected("end of input"))
+    }
+}
+
+/// Parse a SystemVerilog-2017 source text that contains exactly one
+/// interface.
+pub fn parse_interface(src: &str) -> Result<Interface, ParseError> {
+    let mut p = Parser::new(src)?;
+    let interface = parse_interface_body(&mut p)?;
+    if p.is_eof() {
+        Ok(interface)
+    } else {
+        Err(p.unexpected("end of input"))
+    }
+}
+
+/// Parse a SystemVerilog-2017 source text that contains exactly one module.
+pub fn parse_module(src: &str) -> Result<Module, ParseError> {
+    let mut p = Parser::new(src)?;
+    let attr = p.parse_attributes()?;
+    let module = parse_module_with_attr(&mut p, attr)?;
+    if p.is_eof() {
+        Ok(module)
+    } else {
+        Err(p.unexpected("end of input"))
+    }
+}
+
+fn parse_module_with_attr(p: &mut Parser, attr: Attribute) -> Result<Module, ParseError> {
+    p.expect_keyword("module")?;
+    let mut module = Module::new(&p.expect_ident()?);
+    module.set_attr(attr);
+    module.params = parse_params(p)?;
+    if p.is_port_names() {
+        let names = p.parse_port_names()?;
+        p.expect_symbol(";")?;
+        let mut ports = Vec::new();
+        while !p.eat_keyword("endmodule") {
+            if p.is_eof() {
+                return Err(p.unexpected("`endmodule`"));
+            } else if ["input", "output", "inout", "ref"]
+                .iter()
+                .any(|kw| p.is_keyword(kw))
+            {
+                parse_port_decls(p, &mut ports)?;
+            } else {
+                parse_item(p, &mut module.body)?;
+            }
+        }
+        module.ports = p.order_ports(&names, ports, port_name)?;
+        return Ok(module);
+    }
+    // a module without ports can leave out the port list, `module foo;`
+    if p.is_symbol("(") {
+        module.ports = parse_ports(p, false)?;
+    }
+    p.expect_symbol(";")?;
+    while !p.eat_keyword("endmodule") {
+        if p.is_eof() {
+            return Err(p.unexpected("`endmodule`"));
+        }
+        parse_item(p, &mut module.body)?;
+    }
+    Ok(module)
+}
+
+/// Parse an optional parameter list `#(...)` of a module or an interface.
+fn parse_params(p: &mut Parser) -> Result<Vec<Decl>, ParseError> {
+    let mut params = Vec::new();
+    if p.eat_symbol("#") {
+        p.expect_symbol("(")?;
+        if !p.is_symbol(")") {
+            loop {
+                params.push(parse_param(p, true)?);
+                if !p.eat_symbol(",") {
+                    break;
+                }
+            }
+        }
+        p.expect_symbol(")")?;
+    }
+    Ok(params)
+}
+
+fn parse_interface_body(p: &mut Parser) -> Result<Interface, ParseError> {
+    p.expect_keyword("interface")?;
+    let mut interface = Interface::new(&p.expect_ident()?);
+    interface.params = parse_params(p)?;
+    if p.is_symbol("(") {
+        interface.ports = parse_ports(p, false)?;
+    }
+    p.expect_symbol(";")?;
+    while !p.eat_keyword("endinterface") {
+        if p.is_eof() {
+            return Err(p.unexpected("`endinterface`"));
+        } else if p.is_keyword("modport") {
+            interface.modports.extend(parse_modports(p)?);
+        } else {
+            parse_item(p, &mut interface.body)?;
+        }
+    }
+    Ok(interface)
+}
+
+/// Parse a `modport` declaration list including the trailing `;`, a name
+/// without direction takes the direction of the previous one.
+fn parse_modports(p: &mut Parser) -> Result<Vec<Modport>, ParseError> {
+    p.expect_keyword("modport")?;
+    let mut modports = Vec::new();
+    loop {
+        let mut modport = Modport::new(&p.expect_ident()?);
+        p.expect_symbol("(")?;
+        let mut dir: Option<fn(Id) -> ModportPort> = None;
+        // names after `import` are tasks and functions
+        let mut import = false;
+        loop {
+            let next: Option<fn(Id) -> ModportPort> = if p.eat_keyword("input") {
+                Some(ModportPort::Input)
+            } else if p.eat_keyword("output") {
+                Some(ModportPort::Output)
+            } else if p.eat_keyword("inout") {
+                Some(ModportPort::Inout)
+            } else if p.eat_keyword("ref") {
+                Some(ModportPort::Ref)
+            } else {
+                None
+            };
+            if next.is_some() {
+                dir = next;
+                import = false;
+            } else if p.eat_keyword("import") {
+                if p.is_keyword("task") || p.is_keyword("function") {
+                    return Err(p.error("only imports by name are supported in modports"));
+                }
+                import = true;
+            }
+            if import {
+                modport.imports.push(p.expect_ident()?);
+            } else if let Some(dir) = dir {
+                modport.ports.push(dir(p.expect_ident()?));
+            } else {
+                return Err(p.unexpected("modport direction"));
+            }
+            if !p.eat_symbol(",") {
+                break;
+            }
+        }
+        p.expect_symbol(")")?;
+        modports.push(modport);
+        if !p.eat_symbol(",") {
+            break;
+        }
+    }
+    p.expect_symbol(";")?;
+    Ok(modports)
+}
+
+/// Parse a parameter declaration, the `parameter` keyword can be omitted
+/// inside a parameter list. Parameters without a type are `int`.
+fn parse_param(p: &mut Parser, in_list: bool) -> Result<Decl, ParseError> {
+    if !p.eat_keyword("parameter") && !in_list {
+        return Err(p.unexpected("`parameter`"));
+    }
+    let ty = if p.eat_keyword("int") || p.eat_keyword("integer") {
+        Ty::Int
+    } else if p.eat_keyword("logic") || p.is_symbol("[") || p.is_keyword("signed") {
+        parse_packed(p)?
+    } else if is_named_ty(p) {
+        parse_named(p)?
+    } else {
+        Ty::Int
+    };
+    let name = p.expect_ident()?;
+    p.expect_symbol("=")?;
+    Ok(Decl::Param(name, ty, p.parse_expr()?))
+}
+
+/// Parse a `localparam` declaration list including the trailing `;`.
+fn parse_localparams(p: &mut Parser) -> Result<Vec<Decl>, ParseError> {
+    p.expect_keyword("localparam")?;
+    let mut decls = Vec::new();
+    loop {
+        if let Decl::Param(name, ty, expr) = parse_param(p, true)? {
+            decls.push(Decl::LocalParam(name, ty, expr));
+        }
+        if !p.eat_symbol(",") {
+            break;
+        }
+    }
+    p.expect_symbol(";")?;
+    Ok(decls)
+}
+
+/// Returns true if the next tokens are a type name followed by a name, i.e.
+/// `state_t state` or `pkg::state_t`.
+fn is_named_ty(p: &Parser) -> bool {
+    p.is_ident() && (p.is_ident_nth(1) || p.is_symbol_nth(1, "::"))
+}
+
+/// Parse the name of a type, possibly scoped by a package.
+fn parse_named(p: &mut Parser) -> Result<Ty, ParseError> {
+    let mut name = p.expect_ident()?;
+    if p.eat_symbol("::") {
+        name = format!("{}::{}", name, p.expect_ident()?);
+    }
+    Ok(Ty::Named(name))
+}
+
+/// Parse optional packed dimensions, no dimension means a width of one.
+fn parse_opt_range(p: &mut Parser) -> Result<Ty, ParseError> {
+    let mut dims = Vec::new();
+    while p.is_symbol("[") {
+        let (msb, lsb) = p.parse_range()?;
+        dims.push(Ty::new_range(msb, lsb));
+    }
+    if dims.is_empty() {
+        Ok(Ty::Width(1))
+    } else {
+        Ok(Ty::new_packed(dims))
+    }
+}
+
+/// Parse the unpacked dimensions after a name, a size `[n]` is a width and
+/// a range keeps its bounds.
+fn parse_unpacked(p: &mut Parser) -> Result<Vec<Ty>, ParseError> {
+    let mut dims = Vec::new();
+    while p.eat_symbol("[") {
+        let first = p.parse_expr()?;
+        let dim = if p.eat_symbol(":") {
+            Ty::Range(first, p.parse_expr()?)
+        } else {
+            match first {
+                Expr::Int(n) if n > 0 => Ty::Width(n as u64),
+                size => Ty::Range(Expr::new_int(0), Expr::new_sub(size, Expr::new_int(1))),
+            }
+        };
+        p.expect_symbol("]")?;
+        dims.push(dim);
+    }
+    Ok(dims)
+}
+
+/// Parse an optional `signed` keyword followed by an optional range.
+fn parse_packed(p: &mut Parser) -> Result<Ty, ParseError> {
+    if p.eat_keyword("signed") {
+        Ok(Ty::new_signed(parse_opt_range(p)?))
+    } else {
+        parse_opt_range(p)
+    }
+}
+
+/// Parse the type of a port or a return value, `None` means there is no
+/// explicit type.
+fn parse_ty(p: &mut Parser) -> Result<Option<Ty>, ParseError> {
+    if p.eat_keyword("int") {
+        Ok(Some(Ty::Int))
+    } else if is_named_ty(p) {
+        Ok(Some(parse_named(p)?))
+    } else if p.eat_keyword("logic")
+        || p.eat_keyword("wire")
+        || p.is_symbol("[")
+        || p.is_keyword("signed")
+    {
+        Ok(Some(parse_packed(p)?))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Parse a parenthesized list of ANSI ports.
+fn parse_ports(p: &mut Parser, func: bool) -> Result<Vec<Port>, ParseError> {
+    let mut ports: Vec<Port> = Vec::new();
+    p.expect_symbol("(")?;
+    if p.eat_symbol(")") {
+        return Ok(ports);
+    }
+    loop {
+        ports.push(parse_port(p, ports.last(), func)?);
+        if !p.eat_symbol(",") {
+            break;
+        }
+    }
+    p.expect_symbol(")")?;
+    Ok(ports)
+}
+
+/// Parse a port declaration, a port without direction inherits the
+/// direction and type of the previous one, the first port of a function
+/// defaults to an input.
+fn parse_port(p: &mut Parser, prev: Option<&Port>, func: bool) -> Result<Port, ParseError> {
+    // a name followed by another name is an interface type, not a type
+    // inheriting the previous direction
+    if !func && p.is_ident() && (p.is_symbol_nth(1, ".") || p.is_ident_nth(1)) {
+        let interface = p.expect_ident()?;
+        let modport = if p.eat_symbol(".") {
+            Some(p.expect_ident()?)
+        } else {
+            None
+        };
+        let name = p.expect_ident()?;
+        return Ok(Port::Interface(Decl::Interface(name, interface, modport)));
+    }
+    if let Some(Port::Interface(Decl::Interface(_, interface, modport))) = prev {
+        if p.is_ident() {
+            let name = p.expect_ident()?;
+            return Ok(Port::Interface(Decl::Interface(
+                name,
+                interface.clone(),
+                modport.clone(),
+            )));
+        }
+    }
+    let dir: Option<fn(Decl) -> Port> = if p.eat_keyword("input") {
+        Some(Port::Input)
+    } else if p.eat_keyword("output") {
+        Some(Port::Output)
+    } else if p.eat_keyword("inout") {
+        Some(Port::Inout)
+    } else if p.eat_keyword("ref") {
+        Some(Port::Ref)
+    } else {
+        None
+    };
+    let (dir, ty) = match (dir, prev) {
+        (Some(dir), _) => (dir, parse_ty(p)?.unwrap_or(Ty::Width(1))),
+        (None, Some(Port::Interface(_))) => return Err(p.unexpected("port direction")),
+        (None, Some(prev)) => (
+            prev.direction(),
+            parse_ty(p)?.unwrap_or(decl_ty(prev.decl())),
+        ),
+        (None, None) if func => (
+            Port::Input as fn(Decl) -> Port,
+            parse_ty(p)?.unwrap_or(Ty::Width(1)),
+        ),
+        (None, None) => return Err(p.unexpected("port direction")),
+    };
+    let name = p.expect_ident()?;
+    let decl = match ty {
+        ty if p.is_symbol("[") => Decl::Array(name, ty, parse_unpacked(p)?),
+        Ty::Int => Decl::Int(name, ty),
+        ty => Decl::Logic(name, ty),
+    };
+    Ok(dir(decl))
+}
+
+/// Parse a port declaration in the body of a non-ANSI module, i.e.
+/// `input logic [7:0] a, b;`.
+fn parse_port_decls(p: &mut Parser, ports: &mut Vec<Port>) -> Result<(), ParseError> {
+    let mut prev = parse_port(p, None, false)?;
+    while p.eat_symbol(",") {
+        ports.push(prev.clone());
+        prev = parse_port(p, Some(&prev), false)?;
+    }
+    ports.push(prev);
+    p.expect_symbol(";")
+}
+
+fn port_name(port: &Port) -> &Id {
+    match port.decl() {
+        Decl::Logic(name, _)
+        | Decl::Int(name, _)
+        | Decl::Array(name, _, _)
+        | Decl::Interface(name, _, _) => name,
+        _ => unreachable!(),
+    }
+}
+
+fn decl_ty(decl: &Decl) -> Ty {
+    match decl {
+        Decl::Int(_, ty) | Decl::Logic(_, ty) | Decl::Array(_, ty, _) => ty.clone(),
+        _ => unreachable!(),
+    }
+}
+
+/// Returns true if the next tokens start a `logic`, `int` or user-defined
+/// type declaration.
+fn is_decl(p: &Parser) -> bool {
+    p.is_keyword("logic")
+        || p.is_keyword("int")
+        || p.is_ident() && p.is_symbol_nth(1, "::")
+        || p.is_ident()
+            && p.is_ident_nth(1)
+            && [";", ",", "["].iter().any(|sym| p.is_symbol_nth(2, sym))
+}
+
+/// Parse a `logic`, `int` or user-defined type declaration list including
+/// the trailing `;`.
+fn parse_decls(p: &mut Parser) -> Result<Vec<Decl>, ParseError> {
+    let mut decls = Vec::new();
+    let ty = if p.eat_keyword("int") {
+        Ty::Int
+    } else if p.eat_keyword("logic") {
+        parse_packed(p)?
+    } else {
+        parse_named(p)?
+    };
+    loop {
+        let name = p.expect_ident()?;
+        let decl = if p.is_symbol("[") {
+            Decl::Array(name, ty.clone(), parse_unpacked(p)?)
+        } else if let Ty::Int = ty {
+            Decl::new_int(name)
+        } else {
+            Decl::new_logic(name, ty.clone())
+        };
+        if p.is_symbol("=") {
+            return Err(p.error("variable initializers are not supported"));
+        }
+        decls.push(decl);
+        if !p.eat_symbol(",") {
+            break;
+        }
+    }
+    p.expect_symbol(";")?;
+    Ok(decls)
+}
+
+fn parse_function(p: &mut Parser) -> Result<Function, ParseError> {
+    let ty = if p.eat_keyword("import") {
+        FunctionTy::Import
+    } else if p.eat_keyword("export") {
+        FunctionTy::Export
+    } else {
+        FunctionTy::Default
+    };
+    if let FunctionTy::Import | FunctionTy::Export = ty {
+        let token = p.peek().clone();
+        if p.expect_str()? != "DPI-C" {
+            return Err(ParseError::new(
+                token.line,
+                token.col,
+                "only \"DPI-C\" functions are supported",
+            ));
+        }
+    }
+    p.expect_keyword("function")?;
+    p.eat_keyword("automatic");
+    let ret = if p.eat_keyword("void") {
+        Ty::Void
+    } else {
+        parse_ty(p)?.unwrap_or(Ty::Width(1))
+    };
+    let mut func = Function::new(&p.expect_ident()?, ret);
+    func.ports = parse_ports(p, true)?;
+    p.expect_symbol(";")?;
+    if let FunctionTy::Import | FunctionTy::Export = ty {
+        func.ty = ty;
+        return Ok(func);
+    }
+    while is_decl(p) {
+        func.decls.extend(parse_decls(p)?);
+    }
+    while !p.eat_keyword("endfunction") {
+        if p.is_eof() {
+            return Err(p.unexpected("`endfunction`"));
+        }
+        func.body.extend(parse_block(p)?);
+    }
+    Ok(func)
+}
+
+fn parse_task(p: &mut Parser) -> Result<Task, ParseError> {
+    p.expect_keyword("task")?;
+    p.eat_keyword("automatic");
+    let mut task = Task::new(&p.expect_ident()?);
+    if p.is_symbol("(") {
+        task.ports = parse_ports(p, true)?;
+    }
+    p.expect_symbol(";")?;
+    while is_decl(p) {
+        task.decls.extend(parse_decls(p)?);
+    }
+    while !p.eat_keyword("endtask") {
+        if p.is_eof() {
+            return Err(p.unexpected("`endtask`"));
+        }
+        task.body.extend(parse_block(p)?);
+    }
+    Ok(task)
+}
+
+/// Parse a wildcard package import, `import pkg::*;`.
+fn parse_import(p: &mut Parser) -> Result<Decl, ParseError> {
+    p.expect_keyword("import")?;
+    let name = p.expect_ident()?;
+    p.expect_symbol("::")?;
+    if !p.eat_symbol("*") {
+        return Err(p.error("only wildcard imports are supported"));
+    }
+    p.expect_symbol(";")?;
+    Ok(Decl::Import(name))
+}
+
+/// Parse a `typedef` of an alias, an enumeration or a packed struct.
+fn parse_typedef(p: &mut Parser) -> Result<Decl, ParseError> {
+    p.expect_keyword("typedef")?;
+    let def = if p.eat_keyword("enum") {
+        let ty = parse_ty(p)?.unwrap_or(Ty::Int);
+        p.expect_symbol("{")?;
+        let mut values = Vec::new();
+        loop {
+            let name = p.expect_ident()?;
+            let value = if p.eat_symbol("=") {
+                Some(p.parse_expr()?)
+            } else {
+                None
+            };
+            values.push((name, value));
+            if !p.eat_symbol(",") {
+                break;
+            }
+        }
+        p.expect_symbol("}")?;
+        Typedef::Enum(ty, values)
+    } else if p.eat_keyword("struct") {
+        if !p.eat_keyword("packed") {
+            return Err(p.error("only packed structs are supported"));
+        }
+        p.expect_symbol("{")?;
+        let mut members = Vec::new();
+        while !p.eat_symbol("}") {
+            if p.is_eof() {
+                return Err(p.unexpected("`}`"));
+            }
+            members.extend(parse_decls(p)?);
+        }
+        Typedef::Struct(members)
+    } else {
+        match parse_ty(p)? {
+            Some(ty) => Typedef::Alias(ty),
+            None => return Err(p.unexpected("type")),
+        }
+    };
+    let name = p.expect_ident()?;
+    p.expect_symbol(";")?;
+    Ok(Decl::Typedef(name, def))
+}
+
+fn parse_package_body(p: &mut Parser) -> Result<Package, ParseError> {
+    p.expect_keyword("package")?;
+    let mut package = Package::new(&p.expect_ident()?);
+    p.expect_symbol(";")?;
+    while !p.eat_keyword("endpackage") {
+        if p.is_eof() {
+            return Err(p.unexpected("`endpackage`"));
+        } else if p.is_keyword("typedef") {
+            package.body.push(parse_typedef(p)?);
+        } else if p.is_keyword("localparam") {
+            package.body.extend(parse_localparams(p)?);
+        } else if p.is_keyword("import") && p.is_ident_nth(1) {
+            package.body.push(parse_import(p)?);
+        } else if p.is_keyword("function") || p.is_keyword("import") || p.is_keyword("export") {
+            package.body.push(Decl::new_func(parse_function(p)?));
+        } else {
+            return Err(p.unexpected("package item"));
+        }
+    }
+    Ok(package)
+}
+
+fn parse_process(p: &mut Parser, mut process: ParallelProcess) -> Result<Stmt, ParseError> {
+    if p.eat_symbol("@") {
+        p.expect_symbol("(")?;
+        let (ty, expr) = p.parse_event()?;
+        process.set_event(Sequential::Event(ty, expr));
+        p.expect_symbol(")")?;
+    }
+    process.body = parse_block(p)?;
+    Ok(Stmt::from(process))
+}
+
+fn parse_item(p: &mut Parser, body: &mut Vec<Stmt>) -> Result<(), ParseError> {
+    let attr = p.parse_attributes()?;
+    if !attr.attrs().is_empty() && !p.is_instance() {
+        return Err(p.unexpected("instance after attribute"));
+    }
+    if is_decl(p) {
+        for decl in parse_decls(p)? {
+            body.push(Stmt::new_decl(decl));
+        }
+        Ok(())
+    } else if p.is_keyword("localparam") {
+        for decl in parse_localparams(p)? {
+            body.push(Stmt::new_decl(decl));
+        }
+        Ok(())
+    } else if p.is_keyword("typedef") {
+        body.push(Stmt::new_decl(parse_typedef(p)?));
+        Ok(())
+    } else if p.is_keyword("import") && p.is_ident_nth(1) {
+        body.push(Stmt::new_decl(parse_import(p)?));
+        Ok(())
+    } else if p.is_keyword("parameter") {
+        let mut in_list = false;
+        loop {
+            body.push(Stmt::new_decl(parse_param(p, in_list)?));
+            in_list = true;
+            if !p.eat_symbol(",") {
+                break;
+            }
+        }
+        p.expect_symbol(";")
+    } else if p.is_keyword("function") || p.is_keyword("import") || p.is_keyword("export") {
+        body.push(Stmt::new_decl(Decl::new_func(parse_function(p)?)));
+        Ok(())
+    } else if p.is_keyword("task") {
+        body.push(Stmt::new_decl(Decl::new_task(parse_task(p)?)));
+        Ok(())
+    } else if p.eat_keyword("assign") {
+        loop {
+            let lexpr = p.parse_lvalue()?;
+            p.expect_symbol("=")?;
+            let rexpr = p.parse_expr()?;
+            body.push(Stmt::from(Parallel::ParAssign(lexpr, rexpr)));
+            if !p.eat_symbol(",") {
+                break;
+            }
+        }
+        p.expect_symbol(";")
+    } else if p.eat_keyword("always_comb") {
+        body.push(parse_process(p, ParallelProcess::new_always_comb())?);
+        Ok(())
+    } else if p.eat_keyword("always_ff") {
+        body.push(parse_process(p, ParallelProcess::new_always_ff())?);
+        Ok(())
+    } else if p.eat_keyword("initial") {
+        body.push(parse_process(p, ParallelProcess::new_initial())?);
+        Ok(())
+    } else if p.eat_keyword("final") {
+        body.push(parse_process(p, ParallelProcess::new_final())?);
+        Ok(())
+    } else if p.eat_keyword("genvar") {
+        loop {
+            body.push(Stmt::new_decl(Decl::new_genvar(&p.expect_ident()?)));
+            if !p.eat_symbol(",") {
+                break;
+            }
+        }
+        p.expect_symbol(";")
+    } else if p.eat_keyword("generate") {
+        while !p.eat_keyword("endgenerate") {
+            if p.is_eof() {
+                return Err(p.unexpected("`endgenerate`"));
+            }
+            parse_item(p, body)?;
+        }
+        Ok(())
+    } else if p.is_keyword("for") {
+        body.push(Stmt::from(p.parse_generate_for(parse_item)?));
+        Ok(())
+    } else if p.is_keyword("if") {
+        body.push(Stmt::from(p.parse_generate_if(parse_item)?));
+        Ok(())
+    } else if p.is_keyword("always") {
+        Err(p.error("plain always blocks are not supported, use always_comb or always_ff"))
+    } else if p.is_instance() {
+        body.push(Stmt::new_parallel(Parallel::new_inst(
+            p.parse_instance(attr)?,
+        )));
+        Ok(())
+    } else {
+        Err(p.unexpected("module item"))
+    }
+}
+
+/// Parse a `begin ... end` block or a single statement.
+fn parse_block(p: &mut Parser) -> Result<Vec<Sequential>, ParseError> {
+    let mut body = Vec::new();
+    if p.eat_keyword("begin") {
+        if p.is_symbol(":") {
+            return Err(p.error("named blocks are not supported"));
+        }
+        while !p.eat_keyword("end") {
+            if p.is_eof() {
+                return Err(p.unexpected("`end`"));
+            } else if p.is_keyword("begin") {
+                body.extend(parse_block(p)?);
+            } else {
+                body.extend(parse_seq(p)?);
+            }
+        }
+    } else {
+        body.extend(parse_seq(p)?);
+    }
+    Ok(body)
+}
+
+/// Parse a block used as a single statement, blocks with more than one
+/// statement are wrapped in an if-else without condition.
+fn parse_branch(p: &mut Parser) -> Result<Sequential, ParseError> {
+    if p.is_keyword("begin") {
+        Ok(Sequential::If(SequentialIfElse {
+            body: parse_block(p)?,
+            ..Default::default()
+        }))
+    } else {
+        parse_seq(p)?.ok_or_else(|| p.unexpected("statement"))
+    }
+}
+
+/// Parse a single statement, the null statement `;` yields nothing.
+fn parse_seq(p: &mut Parser) -> Result<Option<Sequential>, ParseError> {
+    if p.eat_symbol(";") {
+        Ok(None)
+    } else if p.is_keyword("if") || p.is_keyword("unique") {
+        Ok(Some(Sequential::If(parse_if(p)?)))
+    } else if p.eat_keyword("case") {
+        p.expect_symbol("(")?;
+        let mut case = Case::new(p.parse_expr()?);
+        p.expect_symbol(")")?;
+        while !p.eat_keyword("endcase") {
+            if p.eat_keyword("default") {
+                p.eat_symbol(":");
+                case.set_default(CaseDefault {
+                    body: parse_block(p)?,
+                });
+            } else {
+                let mut branch = CaseBranch::new(p.parse_expr()?);
+                if p.is_symbol(",") {
+                    return Err(p.error("case items with multiple expressions are not supported"));
+                }
+                p.expect_symbol(":")?;
+                branch.body = parse_block(p)?;
+                case.add_branch(branch);
+            }
+        }
+        Ok(Some(Sequential::new_case(case)))
+    } else if p.eat_keyword("for") {
+        p.expect_symbol("(")?;
+        let ty = parse_ty(p)?;
+        let (var, init, cond, step) = p.parse_for_header()?;
+        p.expect_symbol(")")?;
+        let mut seq_for = SequentialFor::new(&var, init, cond, step);
+        seq_for.ty = ty;
+        seq_for.body = parse_block(p)?;
+        Ok(Some(Sequential::For(seq_for)))
+    } else if p.eat_keyword("while") {
+        let cond = p.parse_paren_expr()?;
+        Ok(Some(Sequential::While(cond, parse_block(p)?)))
+    } else if p.eat_keyword("repeat") {
+        let times = p.parse_paren_expr()?;
+        Ok(Some(Sequential::Repeat(times, parse_block(p)?)))
+    } else if p.eat_keyword("forever") {
+        Ok(Some(Sequential::Forever(parse_block(p)?)))
+    } else if p.eat_keyword("do") {
+        let body = parse_block(p)?;
+        p.expect_keyword("while")?;
+        let cond = p.parse_paren_expr()?;
+        p.expect_symbol(";")?;
+        Ok(Some(Sequential::DoWhile(body, cond)))
+    } else if p.eat_keyword("foreach") {
+        p.expect_symbol("(")?;
+        let array = Expr::Ref(p.expect_ident()?);
+        p.expect_symbol("[")?;
+        let mut vars = vec![p.expect_ident()?];
+        while p.eat_symbol(",") {
+            vars.push(p.expect_ident()?);
+        }
+        p.expect_symbol("]")?;
+        p.expect_symbol(")")?;
+        Ok(Some(Sequential::Foreach(array, vars, parse_block(p)?)))
+    } else if p.eat_keyword("assert") {
+        p.expect_symbol("(")?;
+        let expr = p.parse_expr()?;
+        p.expect_symbol(")")?;
+        let seq = if p.eat_keyword("else") {
+            Sequential::Assert(expr, Some(Rc::new(parse_branch(p)?)))
+        } else {
+            p.eat_symbol(";");
+            Sequential::Assert(expr, None)
+        };
+        Ok(Some(seq))
+    } else if p.eat_keyword("return") {
+        let seq = Sequential::new_return(p.parse_expr()?);
+        p.expect_symbol(";")?;
+        Ok(Some(seq))
+    } else if p.is_call() {
+        let seq = match p.parse_expr()? {
+            Expr::Call(name, args) => match (name.as_str(), args.as_slice()) {
+                ("$display", [Expr::Str(msg)]) => Sequential::Display(msg.clone()),
+                ("$error", [Expr::Str(msg)]) => Sequential::Error(msg.clone()),
+                _ => Sequential::new_seqexpr(Expr::Call(name, args)),
+            },
+            _ => return Err(p.unexpected("`;`")),
+        };
+        p.expect_symbol(";")?;
+        Ok(Some(seq))
+    } else {
+        let lexpr = p.parse_lvalue()?;
+        let seq = if p.eat_symbol("=") {
+            Sequential::new_blk_assign(lexpr, p.parse_expr()?)
+        } else if p.eat_symbol("<=") {
+            Sequential::new_nonblk_assign(lexpr, p.parse_expr()?)
+        } else {
+            return Err(p.unexpected("`=` or `<=`"));
+        };
+        p.expect_symbol(";")?;
+        Ok(Some(seq))
+    }
+}
+
+fn parse_if(p: &mut Parser) -> Result<SequentialIfElse, ParseError> {
+    let unique = p.eat_keyword("unique");
+    p.expect_keyword("if")?;
+    p.expect_symbol("(")?;
+    let mut ifelse = SequentialIfElse::new(p.parse_expr()?);
+    p.expect_symbol(")")?;
+    ifelse.unique = unique;
+    ifelse.body = parse_block(p)?;
+    if p.eat_keyword("else") {
+        if p.is_keyword("if") {
+            ifelse.set_else(parse_if(p)?);
+        } else {
+            ifelse.set_else(SequentialIfElse {
+                body: parse_block(p)?,
+                ..Default::default()
+            });
+        }
+    }
+    Ok(ifelse)
+}
@@ -1,10 +1,95 @@
 use crate::subset::ast::Terop;
-use crate::util::pretty_print::{block, block_with_parens, intersperse, PrettyHelper, PrettyPrint};
+use crate::util::pretty_print::{
+    block, block_with_braces, block_with_parens, intersperse, PortStyle, PrettyHelper, PrettyPrint,
+    PRETTY_WIDTH,
+};
 use crate::v17::ast::*;
+use crate::v17::check::decl_id;
+use crate::v17::design::Unit;
+use crate::Error;
 use pretty::RcDoc;
+use std::collections::HashSet;
+use std::io;
+
+fn validate_width(name: &str, ty: &Ty) -> Result<(), Error> {
+    match ty {
+        Ty::Width(0) => Err(Error::Invalid(format!("`{}` has a zero width", name))),
+        Ty::Width(_) | Ty::Range(_, _) => Ok(()),
+        Ty::Signed(ty) => validate_width(name, ty),
+        Ty::Named(_) => Ok(()),
+        Ty::Packed(dims) if !dims.is_empty() => {
+            dims.iter().try_for_each(|dim| validate_width(name, dim))
+        }
+        _ => Err(Error::Invalid(format!("`{}` must have a width", name))),
+    }
+}
+
+/// Tasks and functions are printed without a trailing `;`.
+fn decl_item(decl: &Decl) -> RcDoc<'_, ()> {
+    match decl {
+        Decl::Func(_) | Decl::Task(_) => decl.to_doc(),
+        _ => decl.to_doc().append(RcDoc::text(";")),
+    }
+}
+
+/// A data type as used by declarations, vectors are `logic` followed by
+/// their dimensions.
+fn data_ty(ty: &Ty) -> RcDoc<'_, ()> {
+    match ty {
+        Ty::Void | Ty::Int | Ty::Named(_) => ty.to_doc(),
+        Ty::Width(1) => RcDoc::text("logic"),
+        ty => RcDoc::text("logic")
+            .append(RcDoc::space())
+            .append(ty.to_doc()),
+    }
+}
+
+/// A `parameter` or `localparam` declaration.
+fn param<'a>(keyword: &'a str, name: &'a Id, ty: &'a Ty, expr: &'a Expr) -> RcDoc<'a, ()> {
+    RcDoc::text(keyword)
+        .append(RcDoc::space())
+        .append(ty.to_doc())
+        .append(RcDoc::space())
+        .append(RcDoc::as_string(name))
+        .append(RcDoc::space())
+        .append(RcDoc::text("="))
+        .append(RcDoc::space())
+        .append(expr.to_doc())
+}
+
+/// A packed dimension, a width of one is printed as `[0:0]`.
+fn packed_dim(ty: &Ty) -> RcDoc<'_, ()> {
+    match ty {
+        Ty::Width(1) => RcDoc::text("0:0").brackets(),
+        ty => ty.to_doc(),
+    }
+}
+
+/// An unpacked dimension, a width is printed as the size `[n]`.
+fn unpacked_dim(ty: &Ty) -> RcDoc<'_, ()> {
+    match ty {
+        Ty::Width(n) => RcDoc::as_string(n).brackets(),
+        ty => ty.to_doc(),
+    }
+}
+
+/// Body of a loop, empty loops print an empty `begin ... end` block.
+fn loop_body(body: &[Sequential]) -> RcDoc<'_, ()> {
+    if body.is_empty() {
+        RcDoc::text("begin")
+            .append(RcDoc::hardline())
+            .append(RcDoc::text("end"))
+    } else {
+        block(intersperse(
+            body.iter().map(|x| x.to_doc()),
+            RcDoc::hardline(),
+        ))
+        .begin_end()
+    }
+}
 
 impl PrettyPrint for Ty {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         match self {
             Ty::Void => RcDoc::text("void"),
             Ty::Int => RcDoc::text("int"),
@@ -16,12 +101,34 @@ impl PrettyPrint for Ty {
                     .append(RcDoc::text("0"))
                     .brackets(),
             },
+            Ty::Range(msb, lsb) => msb
+                .to_doc()
+                .append(RcDoc::text(":"))
+                .append(lsb.to_doc())
+                .brackets(),
+            Ty::Signed(ty) => match ty.as_ref() {
+                Ty::Width(1) => RcDoc::text("signed"),
+                ty => RcDoc::text("signed")
+                    .append(RcDoc::space())
+                    .append(ty.to_doc()),
+            },
+            Ty::Packed(dims) => RcDoc::concat(dims.iter().map(packed_dim)),
+            Ty::Named(name) => RcDoc::as_string(name),
+        }
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        match self {
+            Ty::Width(0) => Err(Error::invalid("width must be greater than zero")),
+            Ty::Signed(ty) => ty.validate(),
+            Ty::Packed(dims) => dims.iter().try_for_each(|dim| dim.validate()),
+            _ => Ok(()),
         }
     }
 }
 
 impl PrettyPrint for CaseBranch {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         let cond = self
             .cond
             .to_doc()
@@ -42,7 +149,7 @@ impl PrettyPrint for CaseBranch {
 }
 
 impl PrettyPrint for CaseDefault {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         let default = RcDoc::text("default")
             .append(RcDoc::space())
             .append(RcDoc::text(":"))
@@ -61,7 +168,7 @@ impl PrettyPrint for CaseDefault {
 }
 
 impl PrettyPrint for Case {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         let branches = if self.branches().is_empty() {
             RcDoc::nil()
         } else {
@@ -83,7 +190,7 @@ impl PrettyPrint for Case {
 }
 
 impl PrettyPrint for Function {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         let ports = if self.ports().is_empty() {
             RcDoc::nil()
         } else {
@@ -136,42 +243,185 @@ impl PrettyPrint for Function {
             FunctionTy::Default => func,
         }
     }
+
+    fn validate(&self) -> Result<(), Error> {
+        if self.ret.validate().is_err() {
+            return Err(Error::Invalid(format!(
+                "return type of `{}` has a zero width",
+                self.name
+            )));
+        }
+        for port in self.ports() {
+            port.validate()?;
+        }
+        for decl in self.decls() {
+            decl.validate()?;
+        }
+        Ok(())
+    }
+}
+
+impl PrettyPrint for Task {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
+        let ports = intersperse(
+            self.ports().iter().map(|x| x.to_doc()),
+            RcDoc::text(",").append(RcDoc::line()),
+        );
+        let decls = self
+            .decls()
+            .iter()
+            .map(|x| x.to_doc().append(RcDoc::text(";")));
+        let items = intersperse(
+            decls.chain(std::iter::once(loop_body(self.body()))),
+            RcDoc::hardline(),
+        );
+        RcDoc::space()
+            .append(RcDoc::as_string(&self.name))
+            .append(ports.parens())
+            .append(RcDoc::text(";"))
+            .append(block(items))
+            .task_endtask()
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        for port in self.ports() {
+            port.validate()?;
+        }
+        for decl in self.decls() {
+            decl.validate()?;
+        }
+        Ok(())
+    }
 }
 
 impl PrettyPrint for Decl {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         match self {
             Decl::Int(name, ty) => ty
                 .to_doc()
                 .append(RcDoc::space())
                 .append(RcDoc::as_string(name)),
-            Decl::Logic(name, ty) => {
-                let extra_space = match ty.width() {
-                    1 => RcDoc::nil(),
-                    _ => RcDoc::space(),
-                };
-                RcDoc::text("logic")
-                    .append(RcDoc::space())
-                    .append(ty.to_doc())
-                    .append(extra_space)
-                    .append(RcDoc::as_string(name))
-            }
-            Decl::Func(func) => func.to_doc(),
-            Decl::Param(name, ty, expr) => RcDoc::text("parameter")
+            Decl::Logic(name, ty) => data_ty(ty)
                 .append(RcDoc::space())
-                .append(ty.to_doc())
+                .append(RcDoc::as_string(name)),
+            Decl::Array(name, ty, dims) => data_ty(ty)
                 .append(RcDoc::space())
                 .append(RcDoc::as_string(name))
                 .append(RcDoc::space())
-                .append(RcDoc::text("="))
+                .append(RcDoc::concat(dims.iter().map(unpacked_dim))),
+            Decl::Func(func) => func.to_doc(),
+            Decl::Task(task) => task.to_doc(),
+            Decl::Param(name, ty, expr) => param("parameter", name, ty, expr),
+            Decl::LocalParam(name, ty, expr) => param("localparam", name, ty, expr),
+            Decl::Genvar(name) => RcDoc::text("genvar")
+                .append(RcDoc::space())
+                .append(RcDoc::as_string(name)),
+            Decl::Typedef(name, def) => RcDoc::text("typedef")
+                .append(RcDoc::space())
+                .append(def.to_doc())
                 .append(RcDoc::space())
-                .append(expr.to_doc()),
+                .append(RcDoc::as_string(name)),
+            Decl::Import(name) => RcDoc::text("import")
+                .append(RcDoc::space())
+                .append(RcDoc::as_string(name))
+                .append(RcDoc::text("::*")),
+            Decl::Interface(name, interface, modport) => {
+                let modport = match modport {
+                    Some(modport) => RcDoc::text(".").append(RcDoc::as_string(modport)),
+                    None => RcDoc::nil(),
+                };
+                RcDoc::as_string(interface)
+                    .append(modport)
+                    .append(RcDoc::space())
+                    .append(RcDoc::as_string(name))
+            }
+        }
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        match self {
+            Decl::Int(name, ty) | Decl::Param(name, ty, _) | Decl::LocalParam(name, ty, _) => {
+                match ty {
+                    Ty::Void => Err(Error::Invalid(format!("`{}` can not be void", name))),
+                    Ty::Int => Ok(()),
+                    _ => validate_width(name, ty),
+                }
+            }
+            Decl::Logic(name, ty) => validate_width(name, ty),
+            Decl::Array(name, ty, dims) => {
+                if dims.is_empty() {
+                    return Err(Error::Invalid(format!(
+                        "`{}` has no unpacked dimension",
+                        name
+                    )));
+                }
+                if *ty != Ty::Int {
+                    validate_width(name, ty)?;
+                }
+                dims.iter().try_for_each(|dim| validate_width(name, dim))
+            }
+            Decl::Func(func) => func.validate(),
+            Decl::Task(task) => task.validate(),
+            Decl::Typedef(name, def) => match def {
+                Typedef::Alias(ty) => validate_width(name, ty),
+                Typedef::Enum(_, values) if values.is_empty() => {
+                    Err(Error::Invalid(format!("enum `{}` has no values", name)))
+                }
+                Typedef::Struct(members) if members.is_empty() => {
+                    Err(Error::Invalid(format!("struct `{}` has no members", name)))
+                }
+                def => def.validate(),
+            },
+            Decl::Genvar(_) | Decl::Import(_) | Decl::Interface(_, _, _) => Ok(()),
+        }
+    }
+}
+
+impl PrettyPrint for Typedef {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
+        match self {
+            Typedef::Alias(ty) => data_ty(ty),
+            Typedef::Enum(ty, values) => {
+                let base = match ty {
+                    Ty::Int => RcDoc::nil(),
+                    ty => RcDoc::space().append(data_ty(ty)),
+                };
+                let values = values.iter().map(|(name, value)| match value {
+                    Some(value) => RcDoc::as_string(name)
+                        .append(RcDoc::space())
+                        .append(RcDoc::text("="))
+                        .append(RcDoc::space())
+                        .append(value.to_doc()),
+                    None => RcDoc::as_string(name),
+                });
+                RcDoc::text("enum")
+                    .append(base)
+                    .append(RcDoc::space())
+                    .append(intersperse(values, RcDoc::text(",").append(RcDoc::space())).braces())
+            }
+            Typedef::Struct(members) => block_with_braces(
+                RcDoc::text("struct")
+                    .append(RcDoc::space())
+                    .append(RcDoc::text("packed")),
+                intersperse(
+                    members.iter().map(|x| x.to_doc().append(RcDoc::text(";"))),
+                    RcDoc::hardline(),
+                ),
+            ),
+        }
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        match self {
+            Typedef::Alias(ty) => ty.validate(),
+            Typedef::Enum(ty, _) => ty.validate(),
+            Typedef::Struct(members) => members.iter().try_for_each(|x| x.validate()),
         }
     }
 }
 
 impl PrettyPrint for Sequential {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         match self {
             Sequential::SeqExpr(expr) => expr.to_doc().append(RcDoc::text(";")),
             Sequential::Error(msg) => RcDoc::text("$")
@@ -208,12 +458,84 @@ impl PrettyPrint for Sequential {
                 }
             }
             Sequential::If(seq_if) => seq_if.to_doc(),
+            Sequential::For(seq_for) => seq_for.to_doc(),
+            Sequential::While(cond, body) => RcDoc::text("while")
+                .append(cond.to_doc().parens())
+                .append(RcDoc::space())
+                .append(loop_body(body)),
+            Sequential::DoWhile(body, cond) => RcDoc::text("do")
+                .append(RcDoc::space())
+                .append(loop_body(body))
+                .append(RcDoc::space())
+                .append(RcDoc::text("while"))
+                .append(cond.to_doc().parens())
+                .append(RcDoc::text(";")),
+            Sequential::Repeat(times, body) => RcDoc::text("repeat")
+                .append(times.to_doc().parens())
+                .append(RcDoc::space())
+                .append(loop_body(body)),
+            Sequential::Forever(body) => RcDoc::text("forever")
+                .append(RcDoc::space())
+                .append(loop_body(body)),
+            Sequential::Foreach(array, vars, body) => {
+                let vars = intersperse(
+                    vars.iter().map(RcDoc::as_string),
+                    RcDoc::text(",").append(RcDoc::space()),
+                );
+                RcDoc::text("foreach")
+                    .append(array.to_doc().append(vars.brackets()).parens())
+                    .append(RcDoc::space())
+                    .append(loop_body(body))
+            }
         }
     }
 }
 
+impl PrettyPrint for SequentialFor {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
+        let var = || RcDoc::as_string(&self.var);
+        let decl = match &self.ty {
+            Some(ty @ Ty::Width(_))
+            | Some(ty @ Ty::Range(_, _))
+            | Some(ty @ Ty::Signed(_))
+            | Some(ty @ Ty::Packed(_)) => {
+                let extra_space = match ty {
+                    Ty::Width(1) => RcDoc::nil(),
+                    _ => RcDoc::space(),
+                };
+                RcDoc::text("logic")
+                    .append(RcDoc::space())
+                    .append(ty.to_doc())
+                    .append(extra_space)
+            }
+            Some(ty) => ty.to_doc().append(RcDoc::space()),
+            None => RcDoc::nil(),
+        };
+        let header = decl
+            .append(var())
+            .append(RcDoc::space())
+            .append(RcDoc::text("="))
+            .append(RcDoc::space())
+            .append(self.init.to_doc())
+            .append(RcDoc::text(";"))
+            .append(RcDoc::space())
+            .append(self.cond.to_doc())
+            .append(RcDoc::text(";"))
+            .append(RcDoc::space())
+            .append(var())
+            .append(RcDoc::space())
+            .append(RcDoc::text("="))
+            .append(RcDoc::space())
+            .append(self.step.to_doc());
+        RcDoc::text("for")
+            .append(header.parens())
+            .append(RcDoc::space())
+            .append(loop_body(&self.body))
+    }
+}
+
 impl PrettyPrint for SequentialIfElse {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         let ann = if self.unique {
             RcDoc::text("unique").append(RcDoc::space())
         } else {
@@ -248,13 +570,13 @@ impl PrettyPrint for SequentialIfElse {
 }
 
 impl PrettyPrint for Vec<Sequential> {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         RcDoc::intersperse(self.iter().map(PrettyPrint::to_doc), RcDoc::line())
     }
 }
 
 impl PrettyPrint for ProcessTy {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         match self {
             ProcessTy::AlwaysComb => RcDoc::text("always_comb"),
             ProcessTy::AlwaysFF => RcDoc::text("always_ff"),
@@ -265,7 +587,7 @@ impl PrettyPrint for ProcessTy {
 }
 
 impl PrettyPrint for ParallelProcess {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         if self.body().is_empty() {
             return RcDoc::nil();
         }
@@ -290,7 +612,7 @@ impl PrettyPrint for ParallelProcess {
 }
 
 impl PrettyPrint for Parallel {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         match self {
             Parallel::Inst(ty) => ty.to_doc(),
             Parallel::ParAssign(lexpr, rexpr) => RcDoc::text("assign")
@@ -312,23 +634,117 @@ impl PrettyPrint for Parallel {
 }
 
 impl PrettyPrint for Stmt {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         match self {
-            Stmt::Decl(decl) => {
-                if let Decl::Func(_) = decl {
-                    decl.to_doc()
-                } else {
-                    decl.to_doc().append(RcDoc::text(";"))
-                }
-            }
+            Stmt::Decl(decl) => decl_item(decl),
             Stmt::Parallel(par) => par.to_doc(),
             Stmt::RawStr(s) => RcDoc::text(s),
+            Stmt::GenFor(gen) => gen.to_doc(),
+            Stmt::GenIf(gen) => gen.to_doc(),
+        }
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        match self {
+            Stmt::Decl(decl) => decl.validate(),
+            Stmt::GenFor(gen) => gen.validate(),
+            Stmt::GenIf(gen) => gen.validate(),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl PrettyPrint for GenerateBlock {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
+        let begin = if let Some(label) = self.label() {
+            RcDoc::text("begin")
+                .append(RcDoc::space())
+                .append(RcDoc::text(":"))
+                .append(RcDoc::space())
+                .append(RcDoc::as_string(label))
+        } else {
+            RcDoc::text("begin")
+        };
+        let body = if self.body().is_empty() {
+            RcDoc::hardline()
+        } else {
+            block(intersperse(
+                self.body().iter().map(|x| x.to_doc()),
+                RcDoc::hardline(),
+            ))
+        };
+        begin.append(body).append(RcDoc::text("end"))
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        self.body().iter().try_for_each(|stmt| stmt.validate())
+    }
+}
+
+impl PrettyPrint for GenerateFor {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
+        let var = || RcDoc::as_string(&self.var);
+        let header = var()
+            .append(RcDoc::space())
+            .append(RcDoc::text("="))
+            .append(RcDoc::space())
+            .append(self.init.to_doc())
+            .append(RcDoc::text(";"))
+            .append(RcDoc::space())
+            .append(self.cond.to_doc())
+            .append(RcDoc::text(";"))
+            .append(RcDoc::space())
+            .append(var())
+            .append(RcDoc::space())
+            .append(RcDoc::text("="))
+            .append(RcDoc::space())
+            .append(self.step.to_doc());
+        RcDoc::text("for")
+            .append(header.parens())
+            .append(RcDoc::space())
+            .append(self.block.to_doc())
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        self.block.validate()
+    }
+}
+
+impl PrettyPrint for GenerateIf {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
+        let else_branch = match self.else_branch() {
+            Some(GenerateBlock { label: None, body })
+                if matches!(body.as_slice(), [Stmt::GenIf(_)]) =>
+            {
+                RcDoc::space()
+                    .append(RcDoc::text("else"))
+                    .append(RcDoc::space())
+                    .append(body[0].to_doc())
+            }
+            Some(branch) => RcDoc::space()
+                .append(RcDoc::text("else"))
+                .append(RcDoc::space())
+                .append(branch.to_doc()),
+            None => RcDoc::nil(),
+        };
+        RcDoc::text("if")
+            .append(self.cond.to_doc().parens())
+            .append(RcDoc::space())
+            .append(self.block.to_doc())
+            .append(else_branch)
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        self.block.validate()?;
+        match self.else_branch() {
+            Some(branch) => branch.validate(),
+            None => Ok(()),
         }
     }
 }
 
 impl PrettyPrint for Port {
-    fn to_doc(&self) -> RcDoc<()> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
         match self {
             Port::Input(decl) => RcDoc::text("input")
                 .append(RcDoc::space())
@@ -336,59 +752,83 @@ impl PrettyPrint for Port {
             Port::Output(decl) => RcDoc::text("output")
                 .append(RcDoc::space())
                 .append(decl.to_doc()),
+            Port::Inout(decl) => RcDoc::text("inout")
+                .append(RcDoc::space())
+                .append(decl.to_doc()),
+            Port::Ref(decl) => RcDoc::text("ref")
+                .append(RcDoc::space())
+                .append(decl.to_doc()),
+            Port::Interface(decl) => decl.to_doc(),
+        }
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        match self {
+            Port::Input(decl)
+            | Port::Output(decl)
+            | Port::Inout(decl)
+            | Port::Ref(decl)
+            | Port::Interface(decl) => decl.validate(),
         }
     }
 }
 
-impl PrettyPrint for Module {
-    fn to_doc(&self) -> RcDoc<()> {
-        // Parameters for this module.
-        let params = if self.params().is_empty() {
-            RcDoc::nil()
-        } else {
-            intersperse(
-                self.params().iter().map(|x| x.to_doc()),
-                RcDoc::text(",").append(RcDoc::hardline()),
-            )
-        };
-        // Ports for this module.
-        let ports = if self.ports().is_empty() {
-            RcDoc::nil()
-        } else {
-            intersperse(
-                self.ports().iter().map(|x| x.to_doc()),
-                RcDoc::text(",").append(RcDoc::hardline()),
-            )
-        };
-        // Name of the module.
-        let name = if self.params.is_empty() && self.ports.is_empty() {
-            RcDoc::as_string(&self.name)
-                .append(RcDoc::space())
-                .append(RcDoc::nil().parens())
-        } else if self.params.is_empty() {
-            block_with_parens(RcDoc::as_string(&self.name), ports)
-        } else if self.ports.is_empty() {
-            block_with_parens(
-                RcDoc::as_string(&self.name)
-                    .append(RcDoc::space())
-                    .append(RcDoc::text("#")),
-                params,
-            )
+/// Name, parameters and ports of a module or an interface.
+fn header<'a>(
+    name: &'a Id,
+    params: &'a [Decl],
+    ports: impl Iterator<Item = RcDoc<'a, ()>>,
+) -> RcDoc<'a, ()> {
+    let params_doc = || {
+        intersperse(
+            params.iter().map(|x| x.to_doc()),
+            RcDoc::text(",").append(RcDoc::hardline()),
+        )
+    };
+    let mut ports = ports.peekable();
+    let has_ports = ports.peek().is_some();
+    let ports = intersperse(ports, RcDoc::text(",").append(RcDoc::hardline()));
+    if params.is_empty() && !has_ports {
+        RcDoc::as_string(name)
+            .append(RcDoc::space())
+            .append(RcDoc::nil().parens())
+    } else if params.is_empty() {
+        block_with_parens(RcDoc::as_string(name), ports)
+    } else {
+        let header = block_with_parens(
+            RcDoc::as_string(name)
+                .append(RcDoc::space())
+                .append(RcDoc::text("#")),
+            params_doc(),
+        );
+        if has_ports {
+            header.append(block_with_parens(RcDoc::nil(), ports))
         } else {
-            block_with_parens(
-                RcDoc::as_string(&self.name)
-                    .append(RcDoc::space())
-                    .append(RcDoc::text("#")),
-                params,
-            )
-            .append(block_with_parens(RcDoc::nil(), ports))
-        };
+            header
+        }
+    }
+}
+
+impl Module {
+    /// Print the module with its ports declared in the given style.
+    pub fn to_doc_with_style(&self, style: PortStyle) -> RcDoc<'_, ()> {
+        // Name, parameters and ports of the module.
+        let ports = self.ports().iter().map(|x| match style {
+            PortStyle::Ansi => x.to_doc(),
+            PortStyle::NonAnsi => RcDoc::as_string(decl_id(x.decl())),
+        });
+        let name = header(&self.name, self.params(), ports);
         // Body of the module.
-        let body = if self.body().is_empty() {
+        let decls = match style {
+            PortStyle::Ansi => &[],
+            PortStyle::NonAnsi => self.ports().as_slice(),
+        };
+        let body = if self.body().is_empty() && decls.is_empty() {
             RcDoc::hardline()
         } else {
+            let decls = decls.iter().map(|x| x.to_doc().append(RcDoc::text(";")));
             block(intersperse(
-                self.body().iter().map(|x| x.to_doc()),
+                decls.chain(self.body().iter().map(|x| x.to_doc())),
                 RcDoc::hardline(),
             ))
         };
@@ -405,4 +845,251 @@ impl PrettyPrint for Module {
             .append(RcDoc::hardline());
         attr.append(module)
     }
+
+    /// # Panics
+    ///
+    /// Panics if the module fails `validate`.
+    pub fn to_pretty_with_style(&self, style: PortStyle) -> String {
+        match self.try_to_pretty_with_style(style) {
+            Ok(pretty) => pretty,
+            Err(err) => panic!("Error: {}", err),
+        }
+    }
+
+    pub fn try_to_pretty_with_style(&self, style: PortStyle) -> Result<String, Error> {
+        self.validate()?;
+        let mut w = Vec::new();
+        self.to_doc_with_style(style).render(PRETTY_WIDTH, &mut w)?;
+        String::from_utf8(w)
+            .map_err(|err| Error::Io(io::Error::new(io::ErrorKind::InvalidData, err)))
+    }
+}
+
+impl PrettyPrint for Module {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
+        self.to_doc_with_style(PortStyle::Ansi)
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        for port in self.ports() {
+            port.validate()?;
+        }
+        for stmt in self.body() {
+            stmt.validate()?;
+        }
+        // a generate block has its own scope, only the module scope is
+        // checked for duplicates
+        let ports = self.ports().iter().map(|port| port.decl());
+        let body = self.body().iter().filter_map(|stmt| match stmt {
+            Stmt::Decl(decl) => Some(decl),
+            _ => None,
+        });
+        let mut names = HashSet::new();
+        for decl in self.params().iter().chain(ports).chain(body) {
+            decl.validate()?;
+            // an import does not declare its package name
+            if let Decl::Import(_) = decl {
+                continue;
+            }
+            if !names.insert(decl_id(decl)) {
+                return Err(Error::Duplicate(decl_id(decl).clone()));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl PrettyPrint for Package {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
+        let body = if self.body().is_empty() {
+            RcDoc::hardline()
+        } else {
+            block(intersperse(
+                self.body().iter().map(decl_item),
+                RcDoc::hardline(),
+            ))
+        };
+        RcDoc::space()
+            .append(RcDoc::as_string(&self.name))
+            .append(RcDoc::text(";"))
+            .append(body)
+            .package_endpackage()
+            .append(RcDoc::hardline())
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        let mut names = HashSet::new();
+        for decl in self.body() {
+            decl.validate()?;
+            if !names.insert(decl_id(decl)) {
+                return Err(Error::Duplicate(decl_id(decl).clone()));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl PrettyPrint for ModportPort {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
+        let dir = match self {
+            ModportPort::Input(_) => "input",
+            ModportPort::Output(_) => "output",
+            ModportPort::Inout(_) => "inout",
+            ModportPort::Ref(_) => "ref",
+            ModportPort::Interface(_) => "interface",
+        };
+        RcDoc::text(dir)
+            .append(RcDoc::space())
+            .append(RcDoc::as_string(self.decl()))
+    }
+}
+
+impl PrettyPrint for Modport {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
+        let ports = self.ports().iter().map(|x| x.to_doc());
+        let imports = self.imports().iter().map(|x| {
+            RcDoc::text("import")
+                .append(RcDoc::space())
+                .append(RcDoc::as_string(x))
+        });
+        RcDoc::text("modport")
+            .append(RcDoc::space())
+            .append(RcDoc::as_string(&self.name))
+            .append(RcDoc::space())
+            .append(
+                intersperse(
+                    ports.chain(imports),
+                    RcDoc::text(",").append(RcDoc::space()),
+                )
+                .parens(),
+            )
+    }
+}
+
+impl PrettyPrint for Interface {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
+        let name = header(
+            &self.name,
+            self.params(),
+            self.ports().iter().map(|x| x.to_doc()),
+        );
+        let body = if self.body().is_empty() && self.modports().is_empty() {
+            RcDoc::hardline()
+        } else {
+            let modports = self
+                .modports()
+                .iter()
+                .map(|x| x.to_doc().append(RcDoc::text(";")));
+            block(intersperse(
+                self.body().iter().map(|x| x.to_doc()).chain(modports),
+                RcDoc::hardline(),
+            ))
+        };
+        RcDoc::space()
+            .append(name)
+            .append(RcDoc::text(";"))
+            .append(body)
+            .interface_endinterface()
+            .append(RcDoc::hardline())
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        for port in self.ports() {
+            port.validate()?;
+        }
+        let ports = self.ports().iter().map(|port| port.decl());
+        let body = self.body().iter().filter_map(|stmt| match stmt {
+            Stmt::Decl(decl) => Some(decl),
+            _ => None,
+        });
+        let mut names = HashSet::new();
+        let mut subroutines = HashSet::new();
+        for decl in self.params().iter().chain(ports).chain(body) {
+            decl.validate()?;
+            match decl {
+                Decl::Import(_) => continue,
+                Decl::Func(_) | Decl::Task(_) => {
+                    subroutines.insert(decl_id(decl));
+                }
+                _ => (),
+            }
+            if !names.insert(decl_id(decl)) {
+                return Err(Error::Duplicate(decl_id(decl).clone()));
+            }
+        }
+        let mut modports = HashSet::new();
+        for modport in self.modports() {
+            if !modports.insert(&modport.name) {
+                return Err(Error::Duplicate(modport.name.clone()));
+            }
+            for port in modport.ports() {
+                if let ModportPort::Interface(name) = port {
+                    return Err(Error::Invalid(format!(
+                        "`{}` in modport `{}` can not be an interface",
+                        name, modport.name
+                    )));
+                }
+                if !names.contains(port.decl()) {
+                    return Err(Error::Invalid(format!(
+                        "modport `{}` refers to `{}`, which is not declared",
+                        modport.name,
+                        port.decl()
+                    )));
+                }
+            }
+            for name in modport.imports() {
+                if !subroutines.contains(name) {
+                    return Err(Error::Invalid(format!(
+                        "modport `{}` imports `{}`, which is not a task or function",
+                        modport.name, name
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn unit_doc(unit: Unit<'_>) -> RcDoc<'_, ()> {
+    match unit {
+        Unit::Package(package) => package.to_doc(),
+        Unit::Interface(interface) => interface.to_doc(),
+        Unit::Module(module) => module.to_doc(),
+    }
+}
+
+impl<'a> PrettyPrint for Unit<'a> {
+    fn to_doc(&self) -> RcDoc<'_, ()> {
+        unit_doc(*self)
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        match self {
+            Unit::Package(package) => package.validate(),
+            Unit::Interface(interface) => interface.validate(),
+            Unit::Module(module) => module.validate(),
+        }
+    }
+}
+
+impl PrettyPrint for Design {
+    /// Every unit in dependency order, separated by an empty line.
+    fn to_doc(&self) -> RcDoc<'_, ()> {
+        intersperse(self.units().into_iter().map(unit_doc), RcDoc::hardline())
+    }
+
+    /// Check every unit, that every unit used is defined and that instances
+    /// match their modules, the first error is returned.
+    fn validate(&self) -> Result<(), Error> {
+        for unit in self.units() {
+            unit.validate()?;
+        }
+        match self
+            .check_definitions()
+            .and_then(|_| self.check_instances())
+        {
+            Ok(()) => Ok(()),
+            Err(mut errors) => Err(errors.remove(0)),
+        }
+    }
 }
@@ -0,0 +1,321 @@
+use crate::subset;
+use crate::v17::ast::*;
+
+/// Immutable traversal over a SystemVerilog-2017 AST.
+///
+/// Every method defaults to the matching `walk_*` function, which visits the
+/// children of the node. Override the methods for the nodes of interest and
+/// call the `walk_*` function to keep descending.
+pub trait Visitor {
+    fn visit_module(&mut self, module: &Module) {
+        walk_module(self, module)
+    }
+
+    fn visit_package(&mut self, package: &Package) {
+        walk_package(self, package)
+    }
+
+    fn visit_interface(&mut self, interface: &Interface) {
+        walk_interface(self, interface)
+    }
+
+    fn visit_port(&mut self, port: &Port) {
+        walk_port(self, port)
+    }
+
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        walk_stmt(self, stmt)
+    }
+
+    fn visit_decl(&mut self, decl: &Decl) {
+        walk_decl(self, decl)
+    }
+
+    fn visit_ty(&mut self, ty: &Ty) {
+        walk_ty(self, ty)
+    }
+
+    fn visit_generate_block(&mut self, block: &GenerateBlock) {
+        walk_generate_block(self, block)
+    }
+
+    fn visit_function(&mut self, func: &Function) {
+        walk_function(self, func)
+    }
+
+    fn visit_task(&mut self, task: &Task) {
+        walk_task(self, task)
+    }
+
+    fn visit_parallel(&mut self, par: &Parallel) {
+        walk_parallel(self, par)
+    }
+
+    fn visit_instance(&mut self, inst: &Instance) {
+        walk_instance(self, inst)
+    }
+
+    fn visit_process(&mut self, proc: &ParallelProcess) {
+        walk_process(self, proc)
+    }
+
+    fn visit_sequential(&mut self, seq: &Sequential) {
+        walk_sequential(self, seq)
+    }
+
+    fn visit_case(&mut self, case: &Case) {
+        walk_case(self, case)
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        walk_expr(self, expr)
+    }
+}
+
+pub fn walk_module<V: Visitor + ?Sized>(v: &mut V, module: &Module) {
+    for param in module.params() {
+        v.visit_decl(param);
+    }
+    for port in module.ports() {
+        v.visit_port(port);
+    }
+    for stmt in module.body() {
+        v.visit_stmt(stmt);
+    }
+}
+
+pub fn walk_package<V: Visitor + ?Sized>(v: &mut V, package: &Package) {
+    for decl in package.body() {
+        v.visit_decl(decl);
+    }
+}
+
+pub fn walk_interface<V: Visitor + ?Sized>(v: &mut V, interface: &Interface) {
+    for param in interface.params() {
+        v.visit_decl(param);
+    }
+    for port in interface.ports() {
+        v.visit_port(port);
+    }
+    for stmt in interface.body() {
+        v.visit_stmt(stmt);
+    }
+}
+
+pub fn walk_port<V: Visitor + ?Sized>(v: &mut V, port: &Port) {
+    match port {
+        Port::Input(decl)
+        | Port::Output(decl)
+        | Port::Inout(decl)
+        | Port::Ref(decl)
+        | Port::Interface(decl) => v.visit_decl(decl),
+    }
+}
+
+pub fn walk_stmt<V: Visitor + ?Sized>(v: &mut V, stmt: &Stmt) {
+    match stmt {
+        Stmt::Decl(decl) => v.visit_decl(decl),
+        Stmt::Parallel(par) => v.visit_parallel(par),
+        Stmt::RawStr(_) => (),
+        Stmt::GenFor(gen) => {
+            v.visit_expr(&gen.init);
+            v.visit_expr(&gen.cond);
+            v.visit_expr(&gen.step);
+            v.visit_generate_block(gen.block());
+        }
+        Stmt::GenIf(gen) => {
+            v.visit_expr(&gen.cond);
+            v.visit_generate_block(gen.block());
+            if let Some(block) = gen.else_branch() {
+                v.visit_generate_block(block);
+            }
+        }
+    }
+}
+
+pub fn walk_generate_block<V: Visitor + ?Sized>(v: &mut V, block: &GenerateBlock) {
+    for stmt in block.body() {
+        v.visit_stmt(stmt);
+    }
+}
+
+pub fn walk_decl<V: Visitor + ?Sized>(v: &mut V, decl: &Decl) {
+    match decl {
+        Decl::Int(_, ty) | Decl::Logic(_, ty) => v.visit_ty(ty),
+        Decl::Array(_, ty, dims) => {
+            v.visit_ty(ty);
+            for dim in dims {
+                v.visit_ty(dim);
+            }
+        }
+        Decl::Genvar(_) | Decl::Import(_) | Decl::Interface(_, _, _) => (),
+        Decl::Func(func) => v.visit_function(func),
+        Decl::Task(task) => v.visit_task(task),
+        Decl::Param(_, ty, expr) | Decl::LocalParam(_, ty, expr) => {
+            v.visit_ty(ty);
+            v.visit_expr(expr);
+        }
+        Decl::Typedef(_, Typedef::Alias(ty)) => v.visit_ty(ty),
+        Decl::Typedef(_, Typedef::Enum(ty, values)) => {
+            v.visit_ty(ty);
+            for expr in values.iter().filter_map(|(_, value)| value.as_ref()) {
+                v.visit_expr(expr);
+            }
+        }
+        Decl::Typedef(_, Typedef::Struct(members)) => {
+            for member in members {
+                v.visit_decl(member);
+            }
+        }
+    }
+}
+
+pub fn walk_ty<V: Visitor + ?Sized>(v: &mut V, ty: &Ty) {
+    match ty {
+        Ty::Void | Ty::Int | Ty::Width(_) | Ty::Named(_) => (),
+        Ty::Range(msb, lsb) => {
+            v.visit_expr(msb);
+            v.visit_expr(lsb);
+        }
+        Ty::Signed(ty) => v.visit_ty(ty),
+        Ty::Packed(dims) => {
+            for dim in dims {
+                v.visit_ty(dim);
+            }
+        }
+    }
+}
+
+pub fn walk_function<V: Visitor + ?Sized>(v: &mut V, func: &Function) {
+    v.visit_ty(&func.ret);
+    for port in func.ports() {
+        v.visit_port(port);
+    }
+    for decl in func.decls() {
+        v.visit_decl(decl);
+    }
+    for seq in func.body() {
+        v.visit_sequential(seq);
+    }
+}
+
+pub fn walk_task<V: Visitor + ?Sized>(v: &mut V, task: &Task) {
+    for port in task.ports() {
+        v.visit_port(port);
+    }
+    for decl in task.decls() {
+        v.visit_decl(decl);
+    }
+    for seq in task.body() {
+        v.visit_sequential(seq);
+    }
+}
+
+pub fn walk_parallel<V: Visitor + ?Sized>(v: &mut V, par: &Parallel) {
+    match par {
+        Parallel::Inst(inst) => v.visit_instance(inst),
+        Parallel::ParAssign(lexpr, rexpr) => {
+            v.visit_expr(lexpr);
+            v.visit_expr(rexpr);
+        }
+        Parallel::Process(proc) => v.visit_process(proc),
+    }
+}
+
+pub fn walk_instance<V: Visitor + ?Sized>(v: &mut V, inst: &Instance) {
+    subset::visit::walk_instance(inst, |e| v.visit_expr(e))
+}
+
+pub fn walk_process<V: Visitor + ?Sized>(v: &mut V, proc: &ParallelProcess) {
+    if let Some(event) = proc.event() {
+        v.visit_sequential(event);
+    }
+    for seq in proc.body() {
+        v.visit_sequential(seq);
+    }
+}
+
+pub fn walk_sequential<V: Visitor + ?Sized>(v: &mut V, seq: &Sequential) {
+    match seq {
+        Sequential::Error(_) | Sequential::Display(_) => (),
+        Sequential::Return(expr)
+        | Sequential::Call(expr)
+        | Sequential::Event(_, expr)
+        | Sequential::SeqExpr(expr) => v.visit_expr(expr),
+        Sequential::SeqAssign(lexpr, rexpr, _) => {
+            v.visit_expr(lexpr);
+            v.visit_expr(rexpr);
+        }
+        Sequential::SeqCase(case) => v.visit_case(case),
+        Sequential::If(ifelse) => {
+            if let Some(cond) = &ifelse.cond {
+                v.visit_expr(cond);
+            }
+            for seq in &ifelse.body {
+                v.visit_sequential(seq);
+            }
+            if let Some(else_branch) = &ifelse.else_branch {
+                v.visit_sequential(else_branch);
+            }
+        }
+        Sequential::Assert(expr, else_branch) => {
+            v.visit_expr(expr);
+            if let Some(seq) = else_branch {
+                v.visit_sequential(seq);
+            }
+        }
+        Sequential::For(seq_for) => {
+            if let Some(ty) = &seq_for.ty {
+                v.visit_ty(ty);
+            }
+            v.visit_expr(&seq_for.init);
+            v.visit_expr(&seq_for.cond);
+            v.visit_expr(&seq_for.step);
+            for seq in seq_for.body.iter() {
+                v.visit_sequential(seq);
+            }
+        }
+        Sequential::While(cond, body) | Sequential::Repeat(cond, body) => {
+            v.visit_expr(cond);
+            for seq in body {
+                v.visit_sequential(seq);
+            }
+        }
+        Sequential::Forever(body) => {
+            for seq in body {
+                v.visit_sequential(seq);
+            }
+        }
+        Sequential::DoWhile(body, cond) => {
+            for seq in body {
+                v.visit_sequential(seq);
+            }
+            v.visit_expr(cond);
+        }
+        Sequential::Foreach(array, _, body) => {
+            v.visit_expr(array);
+            for seq in body {
+                v.visit_sequential(seq);
+            }
+        }
+    }
+}
+
+pub fn walk_case<V: Visitor + ?Sized>(v: &mut V, case: &Case) {
+    v.visit_expr(&case.cond);
+    for branch in case.branches() {
+        v.visit_expr(&branch.cond);
+        for seq in branch.body() {
+            v.visit_sequential(seq);
+        }
+    }
+    if let Some(default) = &case.default {
+        for seq in default.body() {
+            v.visit_sequential(seq);
+        }
+    }
+}
+
+pub fn walk_expr<V: Visitor + ?Sized>(v: &mut V, expr: &Expr) {
+    subset::visit::walk_expr(expr, |e| v.visit_expr(e))
+}
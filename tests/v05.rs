@@ -1,5 +1,10 @@
 use vast::util::file::read_to_string;
+use vast::util::pretty_print::{PortStyle, PrettyPrint};
 use vast::v05::ast::*;
+use vast::v05::check::{check_instances, check_widths};
+use vast::v05::parser::{parse_module, parse_modules};
+use vast::v05::visit::{walk_expr, walk_instance, Visitor};
+use vast::v05::visit_mut::{walk_expr_mut, VisitorMut};
 
 macro_rules! check {
     ($res:expr, $exp:expr) => {
@@ -52,6 +57,52 @@ fn test_expr_ulit_dec() {
     check!(res, exp);
 }
 
+#[test]
+fn test_expr_ulit_wide() {
+    let value = BitVec::from_u128(100, u128::MAX >> 28).unwrap();
+    let expr = Expr::new_ulit(value, Radix::Dec);
+    let exp = "100'd1267650600228229401496703205375".to_string();
+    let res = expr.to_string();
+    check!(res, exp);
+}
+
+#[test]
+#[should_panic]
+fn test_expr_ulit_hex_overflow() {
+    Expr::new_ulit_hex(4, "fff");
+}
+
+#[test]
+fn test_bitvec_constructors() {
+    let a = BitVec::from_u64(12, 0xabc).unwrap();
+    let b = BitVec::from_le_bytes(12, &[0xbc, 0x0a]).unwrap();
+    let c = BitVec::from_str_radix(12, "1010_1011_1100", Radix::Bin).unwrap();
+    assert_eq!(a, b);
+    assert_eq!(a, c);
+    assert_eq!(a.to_u128(), Some(0xabc));
+    assert_eq!(a.to_string_radix(Radix::Dec), "12'd2748");
+    assert_eq!(a.to_string_radix(Radix::Hex), "12'habc");
+    assert_eq!(BitVec::from_u64(8, 256), Err(BitVecError::Overflow(8)));
+    assert_eq!(BitVec::from_u64(0, 0), Err(BitVecError::ZeroWidth));
+    assert_eq!(
+        BitVec::from_str_radix(4, "2", Radix::Bin),
+        Err(BitVecError::InvalidDigit('2', Radix::Bin))
+    );
+}
+
+#[test]
+fn test_bitvec_four_state() {
+    let value = BitVec::from_str_radix(8, "x", Radix::Hex).unwrap();
+    assert_eq!(value.get(7), Some(Bit::X));
+    assert!(!value.is_known());
+    assert_eq!(value.to_string_radix(Radix::Hex), "8'hxx");
+    assert_eq!(value.to_string_radix(Radix::Dec), "8'dx");
+    let value = BitVec::from_str_radix(8, "0z", Radix::Hex).unwrap();
+    assert_eq!(value.to_string_radix(Radix::Hex), "8'h0z");
+    let value = BitVec::from_str_radix(4, "1x0?", Radix::Bin).unwrap();
+    assert_eq!(value.to_string_radix(Radix::Hex), "4'b1x0z");
+}
+
 #[test]
 fn test_expr_ref() {
     let expr = Expr::new_ref("a");
@@ -308,14 +359,14 @@ endmodule
 
 #[test]
 fn test_module_empty() {
-    let exp = read_to_string("regression/v05/module_empty.v");
+    let exp = read_to_string("regression/v05/module_empty.v").unwrap();
     let res = Module::new("empty").to_string();
     check!(res, exp);
 }
 
 #[test]
 fn test_module_attribute() {
-    let exp = read_to_string("regression/v05/module_attribute.v");
+    let exp = read_to_string("regression/v05/module_attribute.v").unwrap();
     let mut attr = Attribute::default();
     attr.add_stmt("use", "yes");
     let mut module = Module::new("attribute");
@@ -326,7 +377,7 @@ fn test_module_attribute() {
 
 #[test]
 fn test_module_one_input() {
-    let exp = read_to_string("regression/v05/module_one_input.v");
+    let exp = read_to_string("regression/v05/module_one_input.v").unwrap();
     let mut module = Module::new("one_input");
     module.add_input("a", 5);
     let res = module.to_string();
@@ -335,7 +386,7 @@ fn test_module_one_input() {
 
 #[test]
 fn test_module_three_inputs() {
-    let exp = read_to_string("regression/v05/module_three_inputs.v");
+    let exp = read_to_string("regression/v05/module_three_inputs.v").unwrap();
     let mut module = Module::new("three_inputs");
     module.add_input("a", 5);
     module.add_input("b", 61);
@@ -346,7 +397,7 @@ fn test_module_three_inputs() {
 
 #[test]
 fn test_module_one_wire() {
-    let exp = read_to_string("regression/v05/module_one_wire.v");
+    let exp = read_to_string("regression/v05/module_one_wire.v").unwrap();
     let mut module = Module::new("one_wire");
     let wire = Decl::new_wire("one_wire", 8);
     module.add_stmt(Stmt::from(wire));
@@ -356,7 +407,7 @@ fn test_module_one_wire() {
 
 #[test]
 fn test_module_one_param() {
-    let exp = read_to_string("regression/v05/module_one_param.v");
+    let exp = read_to_string("regression/v05/module_one_param.v").unwrap();
     let mut module = Module::new("one_param");
     module.add_param_uint("width", 32);
     module.add_input("data", 4);
@@ -366,7 +417,7 @@ fn test_module_one_param() {
 
 #[test]
 fn test_module_two_params() {
-    let exp = read_to_string("regression/v05/module_two_params.v");
+    let exp = read_to_string("regression/v05/module_two_params.v").unwrap();
     let mut module = Module::new("two_params");
     module.add_param_uint("width", 4);
     module.add_param_uint("length", 8);
@@ -377,7 +428,7 @@ fn test_module_two_params() {
 
 #[test]
 fn test_module_mix_params() {
-    let exp = read_to_string("regression/v05/module_mix_params.v");
+    let exp = read_to_string("regression/v05/module_mix_params.v").unwrap();
     let mut module = Module::new("mix_params");
     module.add_param_uint("width", 4);
     module.add_param_uint("length", 8);
@@ -389,7 +440,7 @@ fn test_module_mix_params() {
 
 #[test]
 fn test_module_with_instances() {
-    let exp = read_to_string("regression/v05/module_with_instances.v");
+    let exp = read_to_string("regression/v05/module_with_instances.v").unwrap();
     let mut i0 = Instance::new("i0", "prim");
     let mut i1 = Instance::new("i1", "prim");
     let mut i2 = Instance::new("i2", "prim");
@@ -398,9 +449,9 @@ fn test_module_with_instances() {
     let e2 = Expr::new_ulit_hex(4, "f");
     i1.add_param_str("name", "multiply");
     i2.add_param_uint("WIDTH", 3);
-    i0.connect("port_a", e0);
-    i1.connect("port_a", e1);
-    i2.connect("port_a", e2);
+    i0.connect("port_a", e0).unwrap();
+    i1.connect("port_a", e1).unwrap();
+    i2.connect("port_a", e2).unwrap();
     let mut module = Module::new("module_with_instances");
     module.add_instance(i0);
     module.add_instance(i1);
@@ -411,16 +462,1080 @@ fn test_module_with_instances() {
 
 #[test]
 fn test_module_with_instance_attribute() {
-    let exp = read_to_string("regression/v05/module_with_instance_attribute.v");
+    let exp = read_to_string("regression/v05/module_with_instance_attribute.v").unwrap();
     let mut attr = Attribute::default();
     attr.add_stmt("TYPE", "LUT6");
     attr.add_stmt("LOC", "X0Y0");
     let mut i0 = Instance::new("i0", "prim");
     let e0 = Expr::new_ulit_hex(4, "0");
-    i0.connect("port_a", e0);
+    i0.connect("port_a", e0).unwrap();
     i0.set_attr(attr);
     let mut module = Module::new("module_with_instance_attribute");
     module.add_instance(i0);
     let res = module.to_string();
     check!(res, exp);
 }
+
+#[test]
+fn test_parse_module_with_instances() {
+    let exp = read_to_string("regression/v05/module_with_instances.v").unwrap();
+    let module: Module = exp.parse().unwrap();
+    let res = module.to_string();
+    check!(res, exp);
+}
+
+#[test]
+fn test_parse_module_with_instance_attribute() {
+    let exp = read_to_string("regression/v05/module_with_instance_attribute.v").unwrap();
+    let module: Module = exp.parse().unwrap();
+    let res = module.to_string();
+    check!(res, exp);
+}
+
+#[test]
+fn test_parse_module_mix_params() {
+    let exp = read_to_string("regression/v05/module_mix_params.v").unwrap();
+    let module: Module = exp.parse().unwrap();
+    let res = module.to_string();
+    check!(res, exp);
+}
+
+#[test]
+fn test_parse_module_handwritten() {
+    let src = r#"
+// a small counter
+module counter #(parameter STEP = 1) (
+    input clock, reset,
+    input wire [7:0] load,
+    output reg [7:0] count
+);
+    (* keep *) wire [7:0] next = count + STEP;
+    reg [7:0] mem [3:0];
+    always @(posedge clock)
+        if (reset) count <= 0;
+        else if (load != 8'd0) begin
+            count <= load;
+        end else
+            count <= next;
+    always @* begin
+        case (count)
+            8'd0: mem[0] = {load[3:0], 4'hf};
+            default: mem[1] = 0;
+        endcase
+    end
+endmodule
+"#;
+    let exp = r#"module counter # (
+    parameter STEP = 1
+) (
+    input wire clock,
+    input wire reset,
+    input wire [7:0] load,
+    output reg [7:0] count
+);
+    (*keep*) wire [7:0] next = count + STEP;
+    reg [7:0] mem [3:0];
+    always @(posedge clock) begin
+        if(reset) begin
+            count <= 0;
+        end else if(load != 8'd0) begin
+            count <= load;
+        end else begin
+            count <= next;
+        end
+    end
+    always @(*) begin
+        case (count)
+            8'd0 : begin
+                mem[0] = {load[3:0], 4'hf};
+            end
+            default : begin
+                mem[1] = 0;
+            end
+        endcase
+    end
+endmodule
+"#;
+    let res = src.parse::<Module>().unwrap().to_string();
+    check!(res, exp);
+}
+
+#[test]
+fn test_parse_keeps_source_forms() {
+    let src = r#"module leaf;
+endmodule
+
+module top (
+    input wire [3:0] a
+);
+    wire [3:0] b = a + 4'd1, c;
+    prim i0 (
+        .a(b),
+        .y()
+    );
+endmodule
+"#;
+    let modules = parse_modules(src).unwrap();
+    assert!(modules[0].ports().is_empty());
+    let top = &modules[1];
+    let exp = r#"module top (
+    input wire [3:0] a
+);
+    wire [3:0] b = a + 4'd1;
+    wire [3:0] c;
+    prim i0 (
+        .a(b),
+        .y()
+    );
+endmodule
+"#;
+    check!(top.to_string(), exp);
+    assert_eq!(exp.parse::<Module>().unwrap(), *top);
+    let inst = match &top.body()[2] {
+        Stmt::Parallel(Parallel::Inst(inst)) => inst,
+        _ => panic!("expected an instance"),
+    };
+    let ports = inst.port_map().unwrap();
+    assert!(ports.is_open("y"));
+    assert_eq!(ports.get("y"), None);
+    assert!(check_widths(top, &[]).is_ok());
+    assert!(parse_module("module m; reg r = 1'b0; endmodule").is_err());
+}
+
+#[test]
+fn test_parse_modules() {
+    let src = read_to_string("regression/v05/prim.v").unwrap()
+        + &read_to_string("regression/v05/module_with_instances.v").unwrap();
+    let modules = parse_modules(&src).unwrap();
+    let names: Vec<String> = modules.iter().map(|m| m.name()).collect();
+    assert_eq!(names, vec!["prim", "module_with_instances"]);
+}
+
+#[test]
+fn test_parse_error_location() {
+    let src = "module foo (\n    input wire a\n);\n    assign = a;\nendmodule\n";
+    let err = src.parse::<Module>().unwrap_err();
+    assert_eq!((err.line, err.col), (4, 12));
+    check!(
+        err.to_string(),
+        "4:12: expected assignment target, found `=`"
+    );
+}
+
+#[test]
+fn test_roundtrip_regression() {
+    let mut files: Vec<_> = std::fs::read_dir("regression/v05")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "v"))
+        .collect();
+    files.sort();
+    assert!(!files.is_empty());
+    for file in files {
+        let src = read_to_string(&file).unwrap();
+        let modules =
+            parse_modules(&src).unwrap_or_else(|err| panic!("{}:{}", file.display(), err));
+        for module in modules {
+            let printed = module.to_pretty();
+            let res = parse_module(&printed)
+                .unwrap_or_else(|err| panic!("{}:{}\n{}", file.display(), err, printed));
+            assert_eq!(res, module, "{}", file.display());
+        }
+    }
+}
+
+#[derive(Default)]
+struct RefCollector {
+    refs: Vec<String>,
+    insts: Vec<String>,
+}
+
+impl Visitor for RefCollector {
+    fn visit_instance(&mut self, inst: &Instance) {
+        self.insts.push(inst.prim());
+        walk_instance(self, inst);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        if let Expr::Ref(id) = expr {
+            self.refs.push(id.clone());
+        }
+        walk_expr(self, expr);
+    }
+}
+
+#[test]
+fn test_visitor_collects_refs_and_instances() {
+    let src = r#"module top (input wire clock, input wire [3:0] a, output reg [3:0] y);
+    wire [3:0] t;
+    assign t = a + {2{b[0]}};
+    prim i0 (.port_a(t));
+    always @(posedge clock) begin
+        if (a > 0) y <= t; else y <= 'x;
+    end
+endmodule
+"#;
+    let module: Module = src.parse().unwrap();
+    let mut collector = RefCollector::default();
+    collector.visit_module(&module);
+    let exp = vec!["t", "a", "b", "t", "clock", "a", "y", "t", "y"];
+    assert_eq!(collector.refs, exp);
+    assert_eq!(collector.insts, vec!["prim"]);
+}
+
+struct Rename<'a> {
+    from: &'a str,
+    to: &'a str,
+}
+
+impl VisitorMut for Rename<'_> {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        match expr {
+            Expr::Ref(id) if id == self.from => *expr = Expr::new_ref(self.to),
+            _ => walk_expr_mut(self, expr),
+        }
+    }
+}
+
+#[test]
+fn test_visitor_mut_rename() {
+    let src = r#"module top (input wire [3:0] a, output reg [3:0] y);
+    assign y = a + {2{a[1:0]}};
+    prim i0 (.port_a(a));
+endmodule
+"#;
+    let exp = r#"module top (
+    input wire [3:0] a,
+    output reg [3:0] y
+);
+    assign y = b + {2{b[1:0]}};
+    prim i0 (
+        .port_a(b)
+    );
+endmodule
+"#;
+    let mut module: Module = src.parse().unwrap();
+    let original = module.clone();
+    Rename { from: "a", to: "b" }.visit_module_mut(&mut module);
+    check!(module.to_string(), exp);
+    assert_ne!(module, original);
+}
+
+#[test]
+fn test_visitor_walks_types() {
+    let src = r#"module top #(parameter W = 8) (input wire [W-1:0] a);
+    reg [W-1:0] mem [0:N-1];
+    function [W-1:0] id(input [W-1:0] x);
+        id = x;
+    endfunction
+endmodule
+"#;
+    let mut module: Module = src.parse().unwrap();
+    let mut collector = RefCollector::default();
+    collector.visit_module(&module);
+    let exp = vec!["W", "W", "N", "W", "W", "id", "x"];
+    assert_eq!(collector.refs, exp);
+    Rename {
+        from: "W",
+        to: "WIDTH",
+    }
+    .visit_module_mut(&mut module);
+    let mut collector = RefCollector::default();
+    collector.visit_module(&module);
+    assert!(!collector.refs.contains(&"W".to_string()));
+}
+
+#[test]
+fn test_eval_module_params() {
+    let src = r#"module top #(parameter WIDTH = 8, parameter DEPTH = WIDTH * 4) ();
+    parameter MSB = WIDTH - 1;
+endmodule
+"#;
+    let module: Module = src.parse().unwrap();
+    let env = module.param_env();
+    let msb = Expr::new_ref("MSB").eval(&env).unwrap();
+    assert_eq!(msb.to_i128(), 7);
+    assert_eq!(msb.width(), 32);
+    let depth: Expr = "$clog2(DEPTH)".parse().unwrap();
+    assert_eq!(depth.eval(&env).unwrap().to_i128(), 5);
+    let unbound = Expr::new_ref("ADDR").eval(&env);
+    assert_eq!(unbound, Err(EvalError::Unbound("ADDR".to_string())));
+}
+
+#[test]
+fn test_parse_typed_params() {
+    let src = r#"module top #(parameter [7:0] W = 8, parameter integer N = 4, parameter signed S = 4'sd7) ();
+    localparam signed [3:0] M = -8'sd1, B = 8'sb1010_0101;
+    localparam [0:0] F = 1'b1;
+endmodule
+"#;
+    let exp = r#"module top # (
+    parameter [7:0] W = 8,
+    parameter integer N = 4,
+    parameter S = $signed($signed(4'd7))
+);
+    localparam signed [3:0] M = -$signed(8'd1);
+    localparam signed [3:0] B = $signed(8'b10100101);
+    localparam [0:0] F = 1'b1;
+endmodule
+"#;
+    let module: Module = src.parse().unwrap();
+    let res = module.to_string();
+    check!(res, exp);
+    assert_eq!(res.parse::<Module>().unwrap(), module);
+    let value = Expr::parse("8'sd255 >>> 4").unwrap().eval(&Env::new());
+    assert_eq!(value.unwrap().to_i128(), -1);
+    assert!(Expr::parse("'sx").is_err());
+}
+
+#[test]
+fn test_eval_width_semantics() {
+    let env = Env::new();
+    let eval = |src: &str| src.parse::<Expr>().unwrap().eval(&env).unwrap();
+    assert_eq!(eval("4'hf + 4'h1").to_u128(), 0);
+    assert_eq!(eval("4'hf + 4'h1").width(), 4);
+    assert_eq!(eval("{4'hf, 4'h1}").to_u128(), 0xf1);
+    assert_eq!(eval("{3{2'b10}}").to_u128(), 0b101010);
+    assert_eq!(eval("8'hf0 << 4").to_u128(), 0);
+    assert_eq!(eval("8'ha5[7:4]").to_u128(), 0xa);
+    assert_eq!(eval("-1 < 0").to_u128(), 1);
+    assert_eq!(eval("8'd255 < 0").to_u128(), 0);
+    assert_eq!(eval("(2 > 1) ? 4'd3 : 8'd4").width(), 8);
+    assert_eq!(eval("$signed(4'b1111) + 8'd0").to_i128(), 15);
+    assert_eq!(eval("$signed(4'b1111) + $signed(8'd0)").to_i128(), -1);
+    assert_eq!(eval("$signed(4'b1111) < 8'd0").to_u128(), 0);
+    assert_eq!(eval("$signed(4'b1111) < $signed(8'd0)").to_u128(), 1);
+    assert!(matches!(
+        "4'bx1".parse::<Expr>().unwrap().eval(&env),
+        Err(EvalError::Unknown(_))
+    ));
+}
+
+#[test]
+fn test_simplify_folds_constants() {
+    let mut env = Env::new();
+    env.insert("WIDTH".to_string(), Expr::new_int(32));
+    let expr: Expr = "a[WIDTH - 1:0] + (2 * 3) + b[WIDTH - 1]".parse().unwrap();
+    check!(expr.simplify(&env).to_string(), "a[31:0] + 6 + b[31]");
+    let mux: Expr = "(WIDTH > 16) ? a : b".parse().unwrap();
+    check!(mux.simplify(&env).to_string(), "1'd1 ? a : b");
+    let concat: Expr = "{1'b1 ? a : b, c}".parse().unwrap();
+    check!(concat.simplify(&env).to_string(), "{1'b1 ? a : b, c}");
+    let mux: Expr = "(WIDTH > 16) ? 8'hff : 8'h0f".parse().unwrap();
+    check!(mux.simplify(&env).to_string(), "8'd255");
+    let literal: Expr = "8'hff".parse().unwrap();
+    check!(literal.simplify(&env).to_string(), "8'hff");
+}
+
+#[test]
+fn test_simplify_keeps_context_width() {
+    let env = Env::new();
+    let sum: Expr = "4'hf + 4'h1".parse().unwrap();
+    assert_eq!(sum.eval(&env).unwrap().to_u128(), 0);
+    assert_eq!(sum.eval_at(&env, 8).unwrap().to_u128(), 16);
+    check!(sum.simplify(&env).to_string(), "4'hf + 4'h1");
+    let expr: Expr = "a + (4'hf + 4'h1)".parse().unwrap();
+    check!(expr.simplify(&env).to_string(), "a + (4'hf + 4'h1)");
+    let expr: Expr = "a + ~4'h0 + (4'd3 + 4'd4) + (4'hf + 4'h1 == 5'd16)"
+        .parse()
+        .unwrap();
+    check!(expr.simplify(&env).to_string(), "a + ~4'h0 + 4'd7 + 1'd1");
+}
+
+#[test]
+fn test_check_widths() {
+    let src = r#"module adder (input wire [7:0] a, input wire [7:0] b, output wire [8:0] y);
+    assign y = {1'b0, a} + b;
+endmodule
+
+module top (input wire [7:0] a, output reg [3:0] y, output wire [7:0] z);
+    wire [8:0] sum;
+    reg [7:0] mem [3:0];
+    assign z = mem[1];
+    always @(*) begin
+        y = a;
+        y = a[1:0];
+        y[3:0] = 100;
+        y = c;
+    end
+    adder i0 (.a(a), .b(a[3:0]), .y(sum));
+endmodule
+"#;
+    let modules = parse_modules(src).unwrap();
+    assert_eq!(check_widths(&modules[0], &modules), Ok(()));
+    let errors: Vec<String> = check_widths(&modules[1], &modules)
+        .unwrap_err()
+        .iter()
+        .map(|e| e.to_string())
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            "assigning `a` to `y`: expected 4 bits, found 8",
+            "assigning `100` to `y[3:0]`: expected 4 bits, found 7",
+            "`c` is not declared",
+            "port `b` of instance `i0`: expected 8 bits, found 4",
+        ]
+    );
+}
+
+#[test]
+fn test_try_to_pretty_zero_width() {
+    let mut module = Module::new("foo");
+    module.add_decl(Decl::Wire("a".to_string(), Ty::Width(0)));
+    let err = module.try_to_pretty().unwrap_err();
+    check!(err.to_string(), "`a` has a zero width");
+    assert!(Ty::try_new_width(0).is_err());
+}
+
+#[test]
+#[should_panic(expected = "`a` has a zero width")]
+fn test_to_pretty_validates() {
+    let mut module = Module::new("foo");
+    module.add_decl(Decl::Wire("a".to_string(), Ty::Width(0)));
+    module.to_pretty();
+}
+
+#[test]
+fn test_validate_generate_decl() {
+    let mut module = Module::new("foo");
+    let mut gen_for = GenerateFor::new_range("i", "N");
+    gen_for.add_decl(Decl::Wire("t".to_string(), Ty::Width(0)));
+    module.add_generate_for(gen_for);
+    let err = module.try_to_pretty().unwrap_err();
+    check!(err.to_string(), "`t` has a zero width");
+    let err = module
+        .try_to_pretty_with_style(PortStyle::NonAnsi)
+        .unwrap_err();
+    check!(err.to_string(), "`t` has a zero width");
+    let mut module = Module::new("foo");
+    module.add_function(Function::new("f", Ty::new_signed(Ty::Width(0))));
+    let err = module.try_to_pretty().unwrap_err();
+    check!(err.to_string(), "return type of `f` has a zero width");
+}
+
+#[test]
+fn test_validate_duplicate() {
+    let mut module = Module::new("foo");
+    module.add_input("a", 1);
+    module.add_decl(Decl::new_wire("a", 4));
+    assert!(matches!(module.validate(), Err(vast::Error::Duplicate(id)) if id == "a"));
+}
+
+#[test]
+fn test_read_to_string_missing_file() {
+    assert!(read_to_string("regression/v05/missing.v").is_err());
+}
+
+#[test]
+fn test_eval_binop_all() {
+    let env = Env::new();
+    let eval = |src: &str| src.parse::<Expr>().unwrap().eval(&env).unwrap();
+    assert_eq!(eval("7 / 2").to_i128(), 3);
+    assert_eq!(eval("-7 / 2").to_i128(), -3);
+    assert_eq!(eval("-7 % 2").to_i128(), -1);
+    assert_eq!(eval("8'd7 % 8'd4").to_u128(), 3);
+    assert_eq!(eval("2 ** 10").to_i128(), 1024);
+    assert_eq!(eval("8'hf0 >> 4").to_u128(), 0xf);
+    assert_eq!(eval("$signed(8'hf0) >>> 4").to_i128(), -1);
+    assert_eq!(eval("8'hf0 >>> 4").to_u128(), 0xf);
+    assert_eq!(eval("8'h0f <<< 4").to_u128(), 0xf0);
+    assert_eq!(eval("4'b1100 ^ 4'b1010").to_u128(), 0b0110);
+    assert_eq!(eval("4'b1100 ~^ 4'b1010").to_u128(), 0b1001);
+    assert_eq!(eval("4'd3 === 4'd3").to_u128(), 1);
+    assert_eq!(eval("4'd3 !== 4'd3").to_u128(), 0);
+    assert!(matches!(
+        "1 / 0".parse::<Expr>().unwrap().eval(&env),
+        Err(EvalError::Unknown(_))
+    ));
+}
+
+#[test]
+fn test_module_with_generate() {
+    let exp = read_to_string("regression/v05/module_with_generate.v").unwrap();
+    let mut module = Module::new("module_with_generate");
+    module.add_param_uint("N", 4);
+    module.add_input("a", 4);
+    module.add_output("y", 4);
+    module.add_genvar("i");
+    let mut inst = Instance::new("buf_i", "prim");
+    inst.connect("port_a", Expr::new_index_expr("a", "i"))
+        .unwrap();
+    inst.connect("port_y", Expr::new_index_expr("y", "i"))
+        .unwrap();
+    let mut gen_for = GenerateFor::new_range("i", "N");
+    gen_for.set_label("gen_bit");
+    gen_for.add_decl(Decl::new_wire("t", 1));
+    gen_for.add_instance(inst);
+    module.add_generate_for(gen_for);
+    let mut wide = GenerateIf::new(Expr::new_gt("N", 8));
+    wide.set_label("wide");
+    wide.add_decl(Decl::new_wire("w", 8));
+    let mut narrow = GenerateIf::new(Expr::new_eq("N", 4));
+    narrow.add_decl(Decl::new_wire("n", 4));
+    let mut other = GenerateBlock::new_labeled("other");
+    other.add_decl(Decl::new_wire("o", 2));
+    narrow.set_else(other);
+    wide.set_else_if(narrow);
+    module.add_generate_if(wide);
+    let res = module.to_string();
+    check!(res, exp);
+    let parsed: Module = exp.parse().unwrap();
+    assert_eq!(parsed, module);
+}
+
+#[test]
+fn test_parse_generate_region() {
+    let src = r#"
+module m (input [3:0] a, output [3:0] y);
+    genvar i, j;
+    generate
+        for (i = 0; i < 4; i = i + 1)
+            assign y[i] = a[3 - i];
+    endgenerate
+endmodule
+"#;
+    let module: Module = src.parse().unwrap();
+    let exp = r#"module m (
+    input wire [3:0] a,
+    output wire [3:0] y
+);
+    genvar i;
+    genvar j;
+    for(i = 0; i < 4; i = i + 1) begin
+        assign y[i] = a[3 - i];
+    end
+endmodule
+"#;
+    check!(module.to_string(), exp);
+    assert!(check_widths(&module, &[]).is_ok());
+    let err = "module m (); genvar i; for (i = 0; i < 4; j = i + 1) begin end endmodule"
+        .parse::<Module>()
+        .unwrap_err();
+    assert_eq!(err.msg, "loop must update `i`");
+}
+
+#[test]
+fn test_module_with_loops() {
+    let mut reset = SequentialFor::new_range("i", 4);
+    reset.add_seq(Sequential::new_nonblk_assign(
+        Expr::new_index_expr("mem", "i"),
+        Expr::new_ulit_dec(8, "0"),
+    ));
+    let mut ifelse = SequentialIfElse::new("reset");
+    ifelse.add_seq(reset);
+    let mut always = ParallelProcess::new_always();
+    always.set_event(Sequential::new_posedge("clock"));
+    always.add_seq(ifelse);
+    let mut module = Module::new("loops");
+    module.add_input("clock", 1);
+    module.add_input("reset", 1);
+    module.add_decl(Decl::new_int("i"));
+    module.add_decl(Decl::new_array("mem", 8, 4));
+    module.add_stmt(always);
+    let exp = r#"module loops (
+    input wire clock,
+    input wire reset
+);
+    integer i;
+    reg [7:0] mem [3:0];
+    always @(posedge clock) begin
+        if(reset) begin
+            for(i = 0; i < 4; i = i + 1) begin
+                mem[i] <= 8'd0;
+            end
+        end
+    end
+endmodule
+"#;
+    check!(module.to_string(), exp);
+    assert_eq!(exp.parse::<Module>().unwrap(), module);
+    assert!(check_widths(&module, &[]).is_ok());
+}
+
+#[test]
+fn test_parse_while_repeat_forever() {
+    let src = r#"
+module m (input clock);
+    reg [3:0] n;
+    always @(posedge clock) begin
+        while (n != 0) n = n - 1;
+        repeat (2) n = n + 1;
+        forever ;
+    end
+endmodule
+"#;
+    let module: Module = src.parse().unwrap();
+    let exp = r#"    always @(posedge clock) begin
+        while(n != 0) begin
+            n = n - 1;
+        end
+        repeat(2) begin
+            n = n + 1;
+        end
+        forever begin
+        end
+    end
+"#;
+    assert!(module.to_string().contains(exp), "{}", module);
+    assert_eq!(module.to_string().parse::<Module>().unwrap(), module);
+}
+
+#[test]
+fn test_module_with_function_task() {
+    let mut add = Function::new("add", Ty::new_width(8));
+    add.set_automatic();
+    add.add_input("x", 8).add_input("z", 8);
+    add.add_seq(Sequential::new_blk_assign(
+        "add",
+        Expr::new_add(Expr::new_ref("x"), Expr::new_ref("z")),
+    ));
+    let mut wait = Task::new("wait_cycles");
+    wait.add_input("n", 8)
+        .add_output("done", 1)
+        .add_inout("acc", 8)
+        .add_reg("tmp", 8);
+    wait.add_seq(Sequential::new_blk_assign(
+        "done",
+        Expr::new_ulit_bin(1, "0"),
+    ));
+    wait.add_seq(Sequential::new_repeat(
+        "n",
+        vec![Sequential::new_wait_posedge("clock")],
+    ));
+    wait.add_seq(Sequential::new_delay(10));
+    wait.add_seq(Sequential::new_blk_assign(
+        "tmp",
+        Expr::new_add(Expr::new_ref("acc"), Expr::new_ulit_dec(8, "1")),
+    ));
+    wait.add_seq(Sequential::new_blk_assign("acc", "tmp"));
+    wait.add_seq(Sequential::new_blk_assign(
+        "done",
+        Expr::new_ulit_bin(1, "1"),
+    ));
+    let mut tick = Task::new("tick");
+    tick.add_seq(Sequential::new_delay(Expr::new_mul(
+        Expr::new_ref("a"),
+        Expr::new_int(2),
+    )));
+    let mut always = ParallelProcess::new_always();
+    always.set_event(Sequential::new_posedge("clock"));
+    always.add_seq(Sequential::new_nonblk_assign(
+        "y",
+        Expr::new_call("add", vec![Expr::new_ref("a"), Expr::new_ref("y")]),
+    ));
+    always.add_seq(Sequential::new_call(Expr::new_call("tick", vec![])));
+    let mut module = Module::new("function_task");
+    module.add_input("clock", 1);
+    module.add_input("a", 8);
+    module.add_output_reg("y", 8);
+    module.add_function(add);
+    module.add_task(wait);
+    module.add_task(tick);
+    module.add_stmt(always);
+    let exp = read_to_string("regression/v05/module_with_function_task.v").unwrap();
+    let res = module.to_string();
+    check!(res, exp);
+    assert_eq!(exp.parse::<Module>().unwrap(), module);
+    assert!(check_widths(&module, &[]).is_ok());
+}
+
+#[test]
+fn test_parse_function_task() {
+    let src = r#"
+module m (input clock, inout [7:0] bus);
+    function integer count(input [7:0] x, z);
+        count = x + z;
+    endfunction
+    task automatic pulse(output reg p);
+        begin p = 1; @(negedge clock) p = 0; end
+    endtask
+endmodule
+"#;
+    let module: Module = src.parse().unwrap();
+    let exp = r#"module m (
+    input wire clock,
+    inout wire [7:0] bus
+);
+    function integer count(input reg [7:0] x, input reg [7:0] z);
+        begin
+            count = x + z;
+        end
+    endfunction
+    task automatic pulse(output reg p);
+        begin
+            p = 1;
+            @(negedge clock);
+            p = 0;
+        end
+    endtask
+endmodule
+"#;
+    check!(module.to_string(), exp);
+    let err = parse_module("module m (); function f; input a; f = a; endfunction endmodule");
+    assert!(err.is_err());
+}
+
+#[test]
+fn test_module_non_ansi() {
+    let mut module = Module::new("pad");
+    module.add_param_uint("WIDTH", 8);
+    module.add_input("oe", 1);
+    module.add_input("a", 8);
+    module.add_inout("pad", 8);
+    module.add_output_reg("y", 8);
+    module.add_stmt(Parallel::Assign(
+        Expr::new_ref("pad"),
+        Expr::new_mux(
+            Expr::new_ref("oe"),
+            Expr::new_ref("a"),
+            Expr::new_ulit_hex(8, "zz"),
+        ),
+    ));
+    let exp = read_to_string("regression/v05/module_non_ansi.v").unwrap();
+    let res = module.to_pretty_with_style(PortStyle::NonAnsi);
+    check!(res, exp);
+    let ansi = module.to_string();
+    assert!(ansi.contains("    inout wire [7:0] pad,\n"));
+    assert_eq!(ansi.parse::<Module>().unwrap(), module);
+    let err = parse_module("module m (a, b); input a; endmodule").unwrap_err();
+    assert_eq!(err.msg, "port `b` has no direction");
+}
+
+#[test]
+fn test_module_with_signed() {
+    let mut module = Module::new("mac");
+    module.add_signed_input("a", 8);
+    module.add_signed_input("b", 8);
+    module.add_port(Port::new_input("c", Ty::new_signed(1)));
+    module.add_signed_output("y", 16);
+    module.add_decl(Decl::new_reg("acc", Ty::new_signed(16)));
+    module.add_decl(Decl::new_wire("sign", Ty::new_signed(1)));
+    module.add_stmt(Parallel::Assign(
+        Expr::new_ref("y"),
+        Expr::new_add(
+            Expr::new_mul(Expr::new_ref("a"), Expr::new_ref("b")),
+            Expr::new_ref("acc"),
+        ),
+    ));
+    let exp = read_to_string("regression/v05/module_with_signed.v").unwrap();
+    let res = module.to_string();
+    check!(res, exp);
+    assert_eq!(exp.parse::<Module>().unwrap(), module);
+    assert!(check_widths(&module, &[]).is_ok());
+    assert!(Ty::new_signed(8).is_signed());
+    assert_eq!(Ty::new_signed(Ty::new_signed(8)), Ty::new_signed(8));
+    assert_eq!(Ty::new_signed(Ty::Int), Ty::Int);
+}
+
+#[test]
+fn test_module_with_ranges() {
+    let msb = Expr::new_sub(Expr::new_ref("WIDTH"), Expr::new_int(1));
+    let mut module = Module::new("ranges");
+    module.add_param_uint("WIDTH", 8);
+    module.add_input("a", Ty::new_range(msb.clone(), 0));
+    module.add_input("b", Ty::new_range(0, 7));
+    module.add_output("y", Ty::new_range(msb.clone(), 0));
+    module.add_decl(Decl::new_reg("lsb", Ty::new_range(3, 0)));
+    module.add_decl(Decl::new_array("mem", Ty::new_range(msb, 0), 4));
+    module.add_stmt(Parallel::Assign(
+        Expr::new_ref("y"),
+        Expr::new_add(Expr::new_ref("a"), Expr::new_ref("b")),
+    ));
+    let exp = read_to_string("regression/v05/module_with_ranges.v").unwrap();
+    let res = module.to_string();
+    check!(res, exp);
+    assert_eq!(exp.parse::<Module>().unwrap(), module);
+    assert!(check_widths(&module, &[]).is_ok());
+    let mut narrow = module.clone();
+    narrow.add_stmt(Parallel::Assign(Expr::new_ref("lsb"), Expr::new_ref("a")));
+    assert!(check_widths(&narrow, &[]).is_err());
+}
+
+#[test]
+fn test_design_units() {
+    let mut prim = Module::new("prim");
+    prim.add_param_uint("WIDTH", 4);
+    prim.add_input("port_a", 4);
+    let mut design = Design::new();
+    design
+        .add_module(
+            read_to_string("regression/v05/module_with_instances.v")
+                .unwrap()
+                .parse()
+                .unwrap(),
+        )
+        .unwrap();
+    design.add_module(prim.clone()).unwrap();
+    let names: Vec<&str> = design.units().iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(names, vec!["prim", "module_with_instances"]);
+    let tops: Vec<&str> = design
+        .top_modules()
+        .iter()
+        .map(|m| m.name.as_str())
+        .collect();
+    assert_eq!(tops, vec!["module_with_instances"]);
+    assert!(design.check_definitions().is_ok());
+    assert!(design.add_module(prim).is_err());
+    let res = design.to_string();
+    assert_eq!(res.parse::<Design>().unwrap(), design);
+    let twice = format!("{}{}", res, res);
+    let err = twice.parse::<Design>().unwrap_err();
+    assert!(matches!(err, vast::Error::Duplicate(id) if id == "prim"));
+    let mut missing = Design::new();
+    let mut top = Module::new("top");
+    top.add_instance(Instance::new("c0", "consumer"));
+    missing.add_module(top).unwrap();
+    let errors = missing.check_definitions().unwrap_err();
+    assert_eq!(
+        errors[0].to_string(),
+        "`top` uses `consumer`, which is not defined"
+    );
+}
+
+#[test]
+fn test_check_instances() {
+    let mut prim = Module::new("prim");
+    prim.add_param_uint("WIDTH", 4);
+    prim.add_input(
+        "port_a",
+        Ty::new_range(Expr::new_sub(Expr::new_ref("WIDTH"), Expr::new_int(1)), 0),
+    );
+    let module: Module = read_to_string("regression/v05/module_with_instances.v")
+        .unwrap()
+        .parse()
+        .unwrap();
+    let errors = check_instances(&module, std::slice::from_ref(&prim)).unwrap_err();
+    let messages: Vec<String> = errors.iter().map(|err| err.to_string()).collect();
+    assert_eq!(
+        messages,
+        vec!["instance `i1` of `prim` sets `name`, which is not a parameter"]
+    );
+    let errors = check_widths(&module, std::slice::from_ref(&prim)).unwrap_err();
+    let messages: Vec<String> = errors.iter().map(|err| err.to_string()).collect();
+    assert_eq!(
+        messages,
+        vec!["port `port_a` of instance `i2`: expected 3 bits, found 4"]
+    );
+    // a parameter declared in the body is set by the instance as well
+    let mut body = Module::new("body");
+    body.add_decl(Decl::new_param_uint("W", 4));
+    body.add_input(
+        "b",
+        Ty::new_range(Expr::new_sub(Expr::new_ref("W"), Expr::new_int(1)), 0),
+    );
+    let mut top = Module::new("top");
+    top.add_input("x", 8);
+    let mut inst = Instance::new("u0", "body");
+    inst.add_param_uint("W", 8);
+    inst.connect_ref("b", "x").unwrap();
+    top.add_instance(inst);
+    let defs = [body];
+    assert!(check_instances(&top, &defs).is_ok());
+    assert!(check_widths(&top, &defs).is_ok());
+    let mut missing = Module::new("top");
+    let mut inst = Instance::new("u0", "prim");
+    inst.connect("port_b", Expr::new_ulit_hex(4, "0")).unwrap();
+    missing.add_instance(inst);
+    let mut design = Design::new();
+    design.add_module(prim).unwrap();
+    design.add_module(missing).unwrap();
+    let messages: Vec<String> = design
+        .check_instances()
+        .unwrap_err()
+        .iter()
+        .map(|err| err.to_string())
+        .collect();
+    assert_eq!(
+        messages,
+        vec![
+            "instance `u0` of `prim` connects `port_b`, which is not a port",
+            "instance `u0` of `prim` leaves input `port_a` unconnected",
+        ]
+    );
+}
+
+#[test]
+fn test_instance_connection_order() {
+    let exp = read_to_string("regression/v05/module_with_positional_instance.v").unwrap();
+    let mut i0 = Instance::new("i0", "prim");
+    i0.connect_positional(Expr::new_ref("a")).unwrap();
+    i0.connect_positional(Expr::new_ref("y")).unwrap();
+    let mut module = Module::new("module_with_positional_instance");
+    module.add_input("a", 4);
+    module.add_output("y", 4);
+    module.add_instance(i0.clone());
+    let res = module.to_string();
+    check!(res, exp);
+    assert_eq!(exp.parse::<Module>().unwrap(), module);
+    i0.name_ports(&["port_a", "port_b"]);
+    assert!(i0.connections().positional().is_none());
+    assert_eq!(i0.port_map().unwrap()["port_b"], Expr::new_ref("y"));
+    let mut ports = Map::new();
+    ports.extend(vec![("b".to_string(), Expr::new_ref("y"))]);
+    ports.insert("a".to_string(), Expr::new_ref("x"));
+    let names: Vec<&Id> = (&ports).into_iter().map(|(name, _)| name).collect();
+    assert_eq!(names, vec!["b", "a"]);
+    let mut i1 = Instance::new("i1", "prim");
+    i1.connect_ref("port_b", "y").unwrap();
+    i1.connect_ref("port_a", "a").unwrap();
+    let exp = r#"prim i1 (
+    .port_b(y),
+    .port_a(a)
+);"#;
+    check!(i1.to_string(), exp);
+    let mut prim = Module::new("prim");
+    prim.add_input("port_a", 4);
+    prim.add_output("port_b", 4);
+    let mut top = Module::new("top");
+    top.add_instance(i1);
+    let mut design = Design::new();
+    design.add_module(prim).unwrap();
+    design.add_module(top).unwrap();
+    design.order_connections();
+    let exp = r#"prim i1 (
+    .port_a(a),
+    .port_b(y)
+);"#;
+    let res = match &design.module("top").unwrap().body()[0] {
+        Stmt::Parallel(Parallel::Inst(inst)) => inst.to_string(),
+        _ => panic!("expected an instance"),
+    };
+    check!(res, exp);
+}
+
+#[test]
+fn test_design_elaborate_mangled_names() {
+    let src = r#"module leaf # (
+    parameter S = "a_b"
+) (
+    input wire a
+);
+endmodule
+
+module top (
+    input wire a
+);
+    leaf # (
+        .S("a-b")
+    ) l0 (
+        .a(a)
+    );
+    leaf l1 (
+        .a(a)
+    );
+endmodule
+"#;
+    let mut design: Design = src.parse().unwrap();
+    let elab = design.elaborate("top", &Map::new()).unwrap();
+    let names: Vec<&str> = elab.modules().keys().map(|name| name.as_str()).collect();
+    assert_eq!(
+        names,
+        vec!["leaf__S__22_a_2d_b_22_", "leaf__S__22_a_5f_b_22_", "top"]
+    );
+    let mut taken = Module::new("leaf__S__22_a_5f_b_22_");
+    taken.add_input("a", 1);
+    design.add_module(taken).unwrap();
+    let err = design.elaborate("top", &Map::new()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "`leaf` is specialized as `leaf__S__22_a_5f_b_22_`, which is already a module of the design"
+    );
+    let mut x = Module::new("x");
+    x.add_param_uint("P", 0);
+    x.add_param_uint("Q", 0);
+    let mut x_p = Module::new("x__P_1");
+    x_p.add_param_uint("Q", 0);
+    let mut top = Module::new("top");
+    let mut i0 = Instance::new("i0", "x");
+    i0.add_param_uint("P", 1);
+    i0.add_param_uint("Q", 2);
+    top.add_instance(i0);
+    let mut i1 = Instance::new("i1", "x__P_1");
+    i1.add_param_uint("Q", 2);
+    top.add_instance(i1);
+    let mut design = Design::new();
+    design.add_module(x).unwrap();
+    design.add_module(x_p).unwrap();
+    design.add_module(top).unwrap();
+    let err = design.elaborate("top", &Map::new()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "`x` and `x__P_1` are both specialized as `x__P_1__Q_2`"
+    );
+}
+
+#[test]
+fn test_design_elaborate_and_flatten() {
+    let src = r#"module leaf # (
+    parameter W = 32'd4
+) (
+    input wire [W-1:0] a,
+    output wire [W-1:0] y
+);
+    assign y = ~a;
+endmodule
+
+module top (
+    input wire [7:0] a,
+    output wire [7:0] y
+);
+    leaf # (
+        .W(32'd8)
+    ) l0 (
+        .a(a),
+        .y(y)
+    );
+endmodule
+"#;
+    let design: Design = src.parse().unwrap();
+    let mut elab = design.elaborate("top", &Map::new()).unwrap();
+    let names: Vec<&str> = elab.modules().keys().map(|name| name.as_str()).collect();
+    assert_eq!(names, vec!["leaf__W_8", "top"]);
+    assert!(elab.check_instances().is_ok());
+    elab.flatten_all("top").unwrap();
+    let exp = r#"module top (
+    input wire [7:0] a,
+    output wire [7:0] y
+);
+    localparam l0__W = 32'd8;
+    wire [l0__W - 1:0] l0__a;
+    assign l0__a = a;
+    wire [l0__W - 1:0] l0__y;
+    assign y = l0__y;
+    assign l0__y = ~l0__a;
+endmodule
+"#;
+    let top = elab.module("top").unwrap();
+    check!(top.to_string(), exp);
+    assert_eq!(exp.parse::<Module>().unwrap(), *top);
+    assert!(check_widths(top, &[]).is_ok());
+    assert!(design.elaborate("leaf__W_8", &Map::new()).is_err());
+    let src = r#"module child (
+    input wire [3:0] a
+);
+    parameter W = 4;
+endmodule
+
+module parent (
+    input wire [3:0] a
+);
+    child # (
+        .W(7)
+    ) u0 (
+        .a(a)
+    );
+endmodule
+"#;
+    let mut design: Design = src.parse().unwrap();
+    design.flatten("parent", "u0").unwrap();
+    let exp = r#"module parent (
+    input wire [3:0] a
+);
+    wire [3:0] u0__a;
+    assign u0__a = a;
+    localparam u0__W = 7;
+endmodule
+"#;
+    check!(design.module("parent").unwrap().to_string(), exp);
+}
@@ -1,5 +1,11 @@
+use std::rc::Rc;
 use vast::util::file::read_to_string;
+use vast::util::pretty_print::{PortStyle, PrettyPrint};
 use vast::v17::ast::*;
+use vast::v17::check::{check_instances, check_widths, module_scope, Context};
+use vast::v17::parser::{parse_interface, parse_module, parse_modules};
+use vast::v17::visit::{walk_expr, walk_function, Visitor};
+use vast::v17::visit_mut::{walk_expr_mut, VisitorMut};
 
 macro_rules! check {
     ($res:expr, $exp:expr) => {
@@ -232,6 +238,101 @@ fn test_expr_call() {
     check!(res, exp);
 }
 
+#[test]
+fn test_expr_parse_literals() {
+    assert_eq!(Expr::parse("8'hff").unwrap(), Expr::new_ulit_hex(8, "ff"));
+    assert_eq!(
+        Expr::parse("4'b1000").unwrap(),
+        Expr::new_ulit_bin(4, "1000")
+    );
+    assert_eq!(Expr::parse("'x").unwrap(), Expr::X);
+    assert_eq!(Expr::parse("-3").unwrap(), Expr::new_int(-3));
+    assert_eq!(Expr::parse("\"foo\"").unwrap(), Expr::new_str("foo"));
+    let signed = Expr::parse("8'sd5").unwrap();
+    assert_eq!(signed, Expr::Signed(Rc::new(Expr::new_ulit_dec(8, "5"))));
+    check!(signed.to_string(), "$signed(8'd5)");
+    assert_eq!(Expr::parse(&signed.to_string()).unwrap(), signed);
+}
+
+#[test]
+fn test_expr_parse_precedence() {
+    let res: Expr = "a + b * c == d || e && ~f".parse().unwrap();
+    let mul = Expr::new_mul("b", "c");
+    let eq = Expr::new_eq(Expr::new_add("a", mul), "d");
+    let and = Expr::new_logical_and("e", Expr::new_not("f"));
+    let exp = Expr::new_logical_or(eq, and);
+    assert_eq!(res, exp);
+    let res: Expr = "(a - b) - c".parse().unwrap();
+    assert_eq!(res, Expr::new_sub(Expr::new_sub("a", "b"), "c"));
+    let res: Expr = "s ? a : t ? b : c".parse().unwrap();
+    assert_eq!(res, Expr::new_mux("s", "a", Expr::new_mux("t", "b", "c")));
+}
+
+#[test]
+fn test_expr_parse_negation() {
+    let res: Expr = "-a".parse().unwrap();
+    assert_eq!(res, Expr::new_neg("a"));
+    let res: Expr = "-(x + 1) * 2".parse().unwrap();
+    let exp = Expr::new_mul(Expr::new_neg(Expr::new_add("x", 1)), 2);
+    assert_eq!(res, exp);
+    check!(res.to_string(), "-(x + 1) * 2");
+    let res: Expr = "- -a".parse().unwrap();
+    check!(res.to_string(), "-(-a)");
+    check!(Expr::new_neg(-1).to_string(), "-(-1)");
+    let neg = Expr::new_neg(5);
+    check!(neg.to_string(), "-(5)");
+    assert_eq!(Expr::parse("-(5)").unwrap(), neg);
+    assert_eq!(Expr::parse("-5").unwrap(), Expr::new_int(-5));
+    let min = Expr::new_int(i32::MIN);
+    assert_eq!(Expr::parse(&min.to_string()).unwrap(), min);
+    assert!(Expr::parse("2147483648").is_err());
+    let res: Expr = "a - -1".parse().unwrap();
+    assert_eq!(res, Expr::new_sub("a", -1));
+    let value = Expr::parse("-4'd3").unwrap().eval(&Env::new()).unwrap();
+    assert_eq!(value.to_u128(), 13);
+}
+
+#[test]
+fn test_expr_parse_selects() {
+    let res: Expr = "a[7:0]".parse().unwrap();
+    assert_eq!(res, Expr::new_slice("a", 7, 0));
+    let res: Expr = "a[x * 32 +: 32]".parse().unwrap();
+    let lo = Expr::new_mul("x", 32);
+    assert_eq!(res, Expr::new_index_slice("a", lo, 32));
+    let res: Expr = "a[9]".parse().unwrap();
+    assert_eq!(res, Expr::new_index_bit("a", 9));
+    let res: Expr = "cpu.mem[addr]".parse().unwrap();
+    assert_eq!(res, Expr::new_ipath_with_index("cpu.mem", "addr"));
+}
+
+#[test]
+fn test_expr_parse_concat_repeat_call() {
+    let res: Expr = "{a, b}".parse().unwrap();
+    let mut concat = ExprConcat::default();
+    concat.add_expr("b");
+    concat.add_expr("a");
+    assert_eq!(res, Expr::from(concat));
+    let res: Expr = "{4{a[0]}}".parse().unwrap();
+    assert_eq!(res, Expr::new_repeat(4, Expr::new_index_bit("a", 0)));
+    let res: Expr = "func(a, $signed(b))".parse().unwrap();
+    let exp = Expr::new_call("func", vec!["a".into(), Expr::new_signed_ref("b")]);
+    assert_eq!(res, exp);
+}
+
+#[test]
+fn test_expr_parse_error() {
+    let err = Expr::parse("a + ").unwrap_err();
+    check!(
+        err.to_string(),
+        "1:5: expected expression, found end of input"
+    );
+    let err = Expr::parse("a b").unwrap_err();
+    check!(
+        err.to_string(),
+        "1:3: expected end of expression, found `b`"
+    );
+}
+
 #[test]
 fn test_attr_val() {
     let mut attr = Attribute::default();
@@ -493,14 +594,14 @@ endmodule
 
 #[test]
 fn test_module_empty() {
-    let exp = read_to_string("regression/v17/module_empty.v");
+    let exp = read_to_string("regression/v17/module_empty.v").unwrap();
     let res = Module::new("empty").to_string();
     check!(res, exp);
 }
 
 #[test]
 fn test_module_with_import_function() {
-    let exp = read_to_string("regression/v17/module_with_import_function.v");
+    let exp = read_to_string("regression/v17/module_with_import_function.v").unwrap();
     let mut func = Function::new("foo", Ty::Void);
     func.add_input_int("a");
     func.add_input_int("b");
@@ -519,7 +620,7 @@ fn test_module_with_import_function() {
 
 #[test]
 fn test_module_attribute() {
-    let exp = read_to_string("regression/v17/module_attribute.v");
+    let exp = read_to_string("regression/v17/module_attribute.v").unwrap();
     let mut attr = Attribute::default();
     attr.add_stmt("use", "yes");
     let mut module = Module::new("attribute");
@@ -530,7 +631,7 @@ fn test_module_attribute() {
 
 #[test]
 fn test_module_one_input() {
-    let exp = read_to_string("regression/v17/module_one_input.v");
+    let exp = read_to_string("regression/v17/module_one_input.v").unwrap();
     let mut module = Module::new("one_input");
     module.add_input("a", 5);
     let res = module.to_string();
@@ -539,7 +640,7 @@ fn test_module_one_input() {
 
 #[test]
 fn test_module_four_inputs() {
-    let exp = read_to_string("regression/v17/module_four_inputs.v");
+    let exp = read_to_string("regression/v17/module_four_inputs.v").unwrap();
     let mut module = Module::new("four_inputs");
     module.add_input("a", 2);
     module.add_input("b", 7);
@@ -551,7 +652,7 @@ fn test_module_four_inputs() {
 
 #[test]
 fn test_module_with_instances() {
-    let exp = read_to_string("regression/v17/module_with_instances.v");
+    let exp = read_to_string("regression/v17/module_with_instances.v").unwrap();
     let mut i0 = Instance::new("i0", "prim");
     let mut i1 = Instance::new("i1", "prim");
     let mut i2 = Instance::new("i2", "prim");
@@ -560,9 +661,9 @@ fn test_module_with_instances() {
     let e2 = Expr::new_ulit_hex(4, "f");
     i1.add_param_str("name", "multiply");
     i2.add_param_uint("WIDTH", 3);
-    i0.connect("port_a", e0);
-    i1.connect("port_a", e1);
-    i2.connect("port_a", e2);
+    i0.connect("port_a", e0).unwrap();
+    i1.connect("port_a", e1).unwrap();
+    i2.connect("port_a", e2).unwrap();
     let mut module = Module::new("module_with_instances");
     module.add_instance(i0);
     module.add_instance(i1);
@@ -573,7 +674,7 @@ fn test_module_with_instances() {
 
 #[test]
 fn test_module_with_function() {
-    let exp = read_to_string("regression/v17/module_with_function.v");
+    let exp = read_to_string("regression/v17/module_with_function.v").unwrap();
     let lhs = Expr::new_ref("value");
     let rhs = Expr::new_ulit_hex(32, "badc0ffe");
     let expr = Expr::new_eq(lhs, rhs);
@@ -590,7 +691,7 @@ fn test_module_with_function() {
 
 #[test]
 fn test_module_with_function_add_one() {
-    let exp = read_to_string("regression/v17/module_with_function_add_one.v");
+    let exp = read_to_string("regression/v17/module_with_function_add_one.v").unwrap();
     let var_res = Expr::new_ref("res");
     let var_val = Expr::new_ref("val");
     let con_one = Expr::new_ulit_bin(1, "1");
@@ -608,7 +709,7 @@ fn test_module_with_function_add_one() {
 
 #[test]
 fn test_module_with_always_comb() {
-    let exp = read_to_string("regression/v17/module_with_always_comb.v");
+    let exp = read_to_string("regression/v17/module_with_always_comb.v").unwrap();
     let mut always = ParallelProcess::new_always_comb();
     always.add_seq(Sequential::new_display("hello world"));
     let stmt = Stmt::from(always);
@@ -620,7 +721,7 @@ fn test_module_with_always_comb() {
 
 #[test]
 fn test_module_with_always_ff() {
-    let exp = read_to_string("regression/v17/module_with_always_ff.v");
+    let exp = read_to_string("regression/v17/module_with_always_ff.v").unwrap();
     let event = Sequential::Event(EventTy::Posedge, Expr::Ref("clock".to_string()));
     let mut always = ParallelProcess::new_always_ff();
     always.add_seq(Sequential::new_display("hello sync world"));
@@ -635,7 +736,7 @@ fn test_module_with_always_ff() {
 
 #[test]
 fn test_module_with_initial() {
-    let exp = read_to_string("regression/v17/module_with_initial.v");
+    let exp = read_to_string("regression/v17/module_with_initial.v").unwrap();
     let mut initial = ParallelProcess::new_initial();
     initial.add_seq(Sequential::new_display("initial"));
     let stmt = Stmt::from(initial);
@@ -647,7 +748,7 @@ fn test_module_with_initial() {
 
 #[test]
 fn test_module_with_final() {
-    let exp = read_to_string("regression/v17/module_with_final.v");
+    let exp = read_to_string("regression/v17/module_with_final.v").unwrap();
     let mut fnal = ParallelProcess::new_final();
     fnal.add_seq(Sequential::new_display("final"));
     let stmt = Stmt::from(fnal);
@@ -659,7 +760,7 @@ fn test_module_with_final() {
 
 #[test]
 fn test_module_with_case() {
-    let exp = read_to_string("regression/v17/module_with_case.v");
+    let exp = read_to_string("regression/v17/module_with_case.v").unwrap();
     let mut nop = CaseBranch::new(Expr::new_ulit_dec(5, "0"));
     nop.add_seq(Sequential::new_display("nop"));
     let mut add = CaseBranch::new(Expr::new_ulit_dec(5, "1"));
@@ -685,7 +786,7 @@ fn test_module_with_case() {
 
 #[test]
 fn test_module_with_nested_case() {
-    let exp = read_to_string("regression/v17/module_with_nested_case.v");
+    let exp = read_to_string("regression/v17/module_with_nested_case.v").unwrap();
     let mut id_0 = CaseBranch::new(Expr::new_ulit_dec(1, "0"));
     id_0.add_seq(Sequential::new_display("id 0"));
     let mut id_1 = CaseBranch::new(Expr::new_ulit_dec(1, "1"));
@@ -710,3 +811,1286 @@ fn test_module_with_nested_case() {
     let res = module.to_string();
     check!(res, exp);
 }
+
+#[test]
+fn test_parse_module_with_function_add_one() {
+    let exp = read_to_string("regression/v17/module_with_function_add_one.v").unwrap();
+    let module: Module = exp.parse().unwrap();
+    let res = module.to_string();
+    check!(res, exp);
+}
+
+#[test]
+fn test_parse_module_with_import_function() {
+    let exp = read_to_string("regression/v17/module_with_import_function.v").unwrap();
+    let module: Module = exp.parse().unwrap();
+    let res = module.to_string();
+    check!(res, exp);
+}
+
+#[test]
+fn test_parse_module_with_nested_case() {
+    let exp = read_to_string("regression/v17/module_with_nested_case.v").unwrap();
+    let module: Module = exp.parse().unwrap();
+    let res = module.to_string();
+    check!(res, exp);
+}
+
+#[test]
+fn test_parse_module_handwritten() {
+    let src = r#"
+module fsm #(parameter logic [3:0] INIT = 4'h0) (
+    input logic clock, reset,
+    input logic [3:0] din,
+    output logic [3:0] dout
+);
+    logic [3:0] state;
+    assign dout = state;
+    /* sequential part */
+    always_ff @(posedge clock)
+        unique if (reset) state <= INIT;
+        else state <= din;
+    initial begin
+        assert (INIT == 4'h0) else begin
+            $error("bad init");
+        end
+        $display("state=%d", state);
+    end
+endmodule
+"#;
+    let exp = r#"module fsm # (
+    parameter [3:0] INIT = 4'h0
+) (
+    input logic clock,
+    input logic reset,
+    input logic [3:0] din,
+    output logic [3:0] dout
+);
+    logic [3:0] state;
+    assign dout = state;
+    always_ff @(posedge clock) begin
+        unique if(reset) begin
+            state <= INIT;
+        end else begin
+            state <= din;
+        end
+    end
+    initial begin
+        assert(INIT == 4'h0) else begin
+            $error("bad init");
+        end
+        $display("state=%d", state);
+    end
+endmodule
+"#;
+    let res = src.parse::<Module>().unwrap().to_string();
+    check!(res, exp);
+}
+
+#[test]
+fn test_parse_module_without_ports() {
+    let src = "module foo;\n    prim i0 (\n        .a()\n    );\nendmodule\n";
+    let module: Module = src.parse().unwrap();
+    let exp = "module foo ();\n    prim i0 (\n        .a()\n    );\nendmodule\n";
+    check!(module.to_string(), exp);
+    assert_eq!(exp.parse::<Module>().unwrap(), module);
+}
+
+#[test]
+fn test_parse_error_location() {
+    let src = "module foo ();\n    always @(posedge clock) begin\n    end\nendmodule\n";
+    let err = src.parse::<Module>().unwrap_err();
+    check!(
+        err.to_string(),
+        "2:5: plain always blocks are not supported, use always_comb or always_ff"
+    );
+}
+
+#[test]
+fn test_parse_literal_overflow() {
+    let err = Expr::parse("4'hfff").unwrap_err();
+    check!(err.to_string(), "1:1: value does not fit in 4 bits");
+    let expr = Expr::parse("16'b0000_xxxx_1010_zzzz").unwrap();
+    check!(expr.to_string(), "16'b0xxxx1010zzzz");
+    let hex = match expr {
+        Expr::ULit(value, _) => value.to_string_radix(Radix::Hex),
+        _ => unreachable!(),
+    };
+    check!(hex, "16'h0xaz");
+}
+
+#[test]
+fn test_roundtrip_regression() {
+    let mut files: Vec<_> = std::fs::read_dir("regression/v17")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "v"))
+        .collect();
+    files.sort();
+    assert!(!files.is_empty());
+    for file in files {
+        let src = read_to_string(&file).unwrap();
+        let modules =
+            parse_modules(&src).unwrap_or_else(|err| panic!("{}:{}", file.display(), err));
+        for module in modules {
+            let printed = module.to_pretty();
+            let res = parse_module(&printed)
+                .unwrap_or_else(|err| panic!("{}:{}\n{}", file.display(), err, printed));
+            assert_eq!(res, module, "{}", file.display());
+        }
+    }
+}
+
+#[derive(Default)]
+struct CallCounter {
+    calls: Vec<String>,
+    funcs: usize,
+}
+
+impl Visitor for CallCounter {
+    fn visit_function(&mut self, func: &Function) {
+        self.funcs += 1;
+        walk_function(self, func);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        if let Expr::Call(name, _) = expr {
+            self.calls.push(name.clone());
+        }
+        walk_expr(self, expr);
+    }
+}
+
+#[test]
+fn test_visitor_counts_calls_and_functions() {
+    let src = read_to_string("regression/v17/module_with_import_function.v").unwrap();
+    let module: Module = src.parse().unwrap();
+    let mut counter = CallCounter::default();
+    counter.visit_module(&module);
+    assert_eq!(counter.calls, vec!["foo"]);
+    assert_eq!(counter.funcs, 1);
+}
+
+struct Subst;
+
+impl VisitorMut for Subst {
+    fn visit_instance_mut(&mut self, inst: &mut Instance) {
+        inst.connect_ref("port_a", "data").unwrap();
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        match expr {
+            Expr::Ref(id) if id == "WIDTH" => *expr = Expr::new_int(8),
+            _ => walk_expr_mut(self, expr),
+        }
+    }
+}
+
+#[test]
+fn test_visitor_mut_subst() {
+    let src = r#"module top ();
+    assign y = x[WIDTH - 1:0];
+    prim i0 (.port_a(4'h0));
+    always_comb begin
+        assert (WIDTH > 0) else $error("bad width");
+    end
+endmodule
+"#;
+    let exp = r#"module top ();
+    assign y = x[8 - 1:0];
+    prim i0 (
+        .port_a(data)
+    );
+    always_comb begin
+        assert(8 > 0) else $error("bad width");
+    end
+endmodule
+"#;
+    let mut module: Module = src.parse().unwrap();
+    Subst.visit_module_mut(&mut module);
+    check!(module.to_string(), exp);
+}
+
+#[test]
+fn test_eval_module_params() {
+    let src = r#"module top #(parameter int WIDTH = 16, parameter logic [7:0] INIT = 8'hff) ();
+endmodule
+"#;
+    let module: Module = src.parse().unwrap();
+    let env = module.param_env();
+    let expr: Expr = "{INIT[3:0], WIDTH[4:0]} == 9'h1f0".parse().unwrap();
+    assert!(expr.eval(&env).unwrap().is_true());
+    let range: Expr = "data[WIDTH - 1:WIDTH - 8]".parse().unwrap();
+    check!(range.simplify(&env).to_string(), "data[15:8]");
+}
+
+#[test]
+fn test_check_widths() {
+    let src = r#"module top #(parameter logic [3:0] INIT = 4'hf) (input logic [7:0] a, output logic [3:0] y);
+    function logic [3:0] low(input logic [7:0] x);
+        return x;
+    endfunction
+    logic [3:0] r;
+    always_comb begin
+        r = INIT;
+        y = low(a);
+    end
+    assign y = a[7:4] + INIT;
+    assign y = {a, INIT};
+endmodule
+"#;
+    let module = parse_module(src).unwrap();
+    let errors: Vec<String> = check_widths(&module, &Context::default())
+        .unwrap_err()
+        .iter()
+        .map(|e| e.to_string())
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            "assigning `x` to `low`: expected 4 bits, found 8",
+            "assigning `{a, INIT}` to `y`: expected 4 bits, found 12",
+        ]
+    );
+}
+
+#[test]
+fn test_try_to_pretty_invalid_decl() {
+    let mut module = Module::new("foo");
+    module.add_decl(Decl::Logic("a".to_string(), Ty::Int));
+    let err = module.try_to_pretty().unwrap_err();
+    check!(err.to_string(), "`a` must have a width");
+    let res = Expr::try_new_ulit_str(4, "fff", Radix::Hex);
+    assert!(matches!(res, Err(vast::Error::BitVec(_))));
+    let module: Module = "module foo (input logic [3:0] a);\nendmodule\n"
+        .parse()
+        .unwrap();
+    check!(module.try_to_pretty().unwrap(), module.to_pretty());
+}
+
+#[test]
+fn test_validate_generate_decl() {
+    let mut module = Module::new("foo");
+    let mut gen_for = GenerateFor::new_range("i", "N");
+    gen_for.add_decl(Decl::Logic("t".to_string(), Ty::Width(0)));
+    module.add_generate_for(gen_for);
+    let err = module.try_to_pretty().unwrap_err();
+    check!(err.to_string(), "`t` has a zero width");
+    let err = module
+        .try_to_pretty_with_style(PortStyle::NonAnsi)
+        .unwrap_err();
+    check!(err.to_string(), "`t` has a zero width");
+    let mut module = Module::new("foo");
+    module.add_function(Function::new("f", Ty::new_signed(Ty::Width(0))));
+    let err = module.try_to_pretty().unwrap_err();
+    check!(err.to_string(), "return type of `f` has a zero width");
+}
+
+#[test]
+fn test_expr_binop_all() {
+    let cases = vec![
+        (Expr::new_div("a", "b"), "a / b"),
+        (Expr::new_mod("a", "b"), "a % b"),
+        (Expr::new_pow("a", "b"), "a ** b"),
+        (Expr::new_shift_right("a", "b"), "a >> b"),
+        (Expr::new_arith_shift_left("a", "b"), "a <<< b"),
+        (Expr::new_arith_shift_right("a", "b"), "a >>> b"),
+        (Expr::new_bit_xor("a", "b"), "a ^ b"),
+        (Expr::new_bit_xnor("a", "b"), "a ~^ b"),
+        (Expr::new_case_eq("a", "b"), "a === b"),
+        (Expr::new_case_neq("a", "b"), "a !== b"),
+    ];
+    for (expr, exp) in cases {
+        check!(expr.to_string(), exp);
+        assert_eq!(Expr::parse(exp).unwrap(), expr);
+    }
+}
+
+#[test]
+fn test_expr_binop_precedence() {
+    let sum = Expr::new_add("a", "b");
+    check!(Expr::new_mul(sum.clone(), "c").to_string(), "(a + b) * c");
+    check!(Expr::new_mul("c", sum.clone()).to_string(), "c * (a + b)");
+    check!(Expr::new_add(sum.clone(), "c").to_string(), "a + b + c");
+    check!(Expr::new_sub("c", sum.clone()).to_string(), "c - (a + b)");
+    let xor = Expr::new_bit_xor("a", "b");
+    check!(
+        Expr::new_bit_and(xor.clone(), "c").to_string(),
+        "(a ^ b) & c"
+    );
+    check!(Expr::new_bit_or(xor.clone(), "c").to_string(), "a ^ b | c");
+    let shift = Expr::new_shift_right("a", 1);
+    check!(Expr::new_lt(shift.clone(), "b").to_string(), "a >> 1 < b");
+    check!(
+        Expr::new_shift_right("b", Expr::new_add("a", 1)).to_string(),
+        "b >> a + 1"
+    );
+    let pow = Expr::new_pow(Expr::new_pow("a", "b"), "c");
+    check!(pow.to_string(), "a ** b ** c");
+    check!(
+        Expr::new_pow("a", Expr::new_pow("b", "c")).to_string(),
+        "a ** (b ** c)"
+    );
+    let src = "a ~^ b ^ c == d >>> 2 ** e % f";
+    check!(Expr::parse(src).unwrap().to_string(), src);
+}
+
+/// Deterministic xorshift generator for the printer property test.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self, n: u64) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 % n
+    }
+
+    fn expr(&mut self, depth: u32) -> Expr {
+        let leaf = depth == 0 || self.next(4) == 0;
+        if leaf {
+            return match self.next(7) {
+                0 => Expr::new_int(self.next(100) as i32),
+                1 => Expr::new_int(-(self.next(100) as i32) - 1),
+                2 => {
+                    let width = self.next(16) as u32 + 1;
+                    let value = BitVec::from_u64(width, self.next(1 << width)).unwrap();
+                    let radix = [Radix::Bin, Radix::Dec, Radix::Hex][self.next(3) as usize];
+                    Expr::new_ulit(value, radix)
+                }
+                3 => Expr::X,
+                4 => Expr::new_str("s"),
+                5 => Expr::new_int([i32::MIN, i32::MAX][self.next(2) as usize]),
+                _ => Expr::new_ref(["a", "b", "c"][self.next(3) as usize]),
+            };
+        }
+        let ops = [
+            Binop::LogOr,
+            Binop::LogAnd,
+            Binop::Add,
+            Binop::Sub,
+            Binop::Mul,
+            Binop::Div,
+            Binop::Mod,
+            Binop::Pow,
+            Binop::Gt,
+            Binop::Lt,
+            Binop::Geq,
+            Binop::Leq,
+            Binop::Equal,
+            Binop::NotEqual,
+            Binop::CaseEqual,
+            Binop::CaseNotEqual,
+            Binop::IndexBit,
+            Binop::BitAnd,
+            Binop::BitOr,
+            Binop::BitXor,
+            Binop::BitXnor,
+            Binop::ShiftLeft,
+            Binop::ShiftRight,
+            Binop::ArithShiftLeft,
+            Binop::ArithShiftRight,
+        ];
+        let unops = [
+            Unop::LogNot,
+            Unop::Not,
+            Unop::And,
+            Unop::Nand,
+            Unop::Or,
+            Unop::Nor,
+            Unop::Xor,
+            Unop::Xnor,
+            Unop::Neg,
+        ];
+        let sub = |rng: &mut Rng| Rc::new(rng.expr(depth - 1));
+        match self.next(9) {
+            0..=2 => {
+                let op = ops[self.next(ops.len() as u64) as usize].clone();
+                Expr::Binop(op, sub(self), sub(self))
+            }
+            3 => Expr::Unop(
+                unops[self.next(unops.len() as u64) as usize].clone(),
+                sub(self),
+            ),
+            4 => Expr::Terop(Terop::Mux, sub(self), sub(self), sub(self)),
+            5 => {
+                let ty = if self.next(2) == 0 {
+                    Terop::Slice
+                } else {
+                    Terop::IndexSlice
+                };
+                Expr::Terop(ty, sub(self), sub(self), sub(self))
+            }
+            6 => {
+                let n = self.next(3) + 1;
+                Expr::Concat(ExprConcat {
+                    exprs: (0..n).map(|_| self.expr(depth - 1)).collect(),
+                })
+            }
+            7 => Expr::Repeat(self.next(4), sub(self)),
+            _ => match self.next(3) {
+                0 => Expr::Signed(sub(self)),
+                1 => Expr::new_call(
+                    "f",
+                    (0..self.next(3)).map(|_| self.expr(depth - 1)).collect(),
+                ),
+                _ => Expr::new_call("$clog2", vec![self.expr(depth - 1)]),
+            },
+        }
+    }
+}
+
+#[test]
+fn test_expr_print_parse_property() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    for _ in 0..5000 {
+        let expr = rng.expr(5);
+        let printed = expr.to_string();
+        let parsed = Expr::parse(&printed)
+            .unwrap_or_else(|err| panic!("{}: failed to parse `{}`", err, printed));
+        assert_eq!(parsed, expr, "`{}` changed meaning", printed);
+    }
+}
+
+#[test]
+fn test_expr_print_parens() {
+    let mux = Expr::new_mux("c", "a", "b");
+    check!(Expr::new_add(mux.clone(), 1).to_string(), "(c ? a : b) + 1");
+    check!(
+        Expr::new_mux(mux.clone(), "d", "e").to_string(),
+        "(c ? a : b) ? d : e"
+    );
+    check!(
+        Expr::new_mux("d", mux.clone(), "e").to_string(),
+        "d ? c ? a : b : e"
+    );
+    let sum = Expr::new_add("a", "b");
+    let slice = Expr::Terop(
+        Terop::Slice,
+        Rc::new(sum.clone()),
+        Rc::new(Expr::new_int(3)),
+        Rc::new(Expr::new_int(0)),
+    );
+    check!(slice.to_string(), "(a + b)[3:0]");
+    check!(Expr::new_not(sum.clone()).to_string(), "~(a + b)");
+    let and = Expr::Unop(Unop::And, Rc::new(Expr::new_ref("a")));
+    check!(Expr::new_not(and.clone()).to_string(), "~(&a)");
+    let res = Expr::Unop(Unop::And, Rc::new(and)).to_string();
+    check!(res, "&(&a)");
+    check!(Expr::new_signed(sum.clone()).to_string(), "$signed(a + b)");
+    let res = Expr::new_repeat(2, sum).to_string();
+    check!(res, "{2{a + b}}");
+}
+
+#[test]
+fn test_module_with_generate() {
+    let exp = read_to_string("regression/v17/module_with_generate.v").unwrap();
+    let mut module = Module::new("module_with_generate");
+    module.add_input("a", 8).add_output("y", 8);
+    module.add_genvar("i");
+    let mut gen_for = GenerateFor::new_range("i", 8);
+    let assign = Parallel::ParAssign(
+        Expr::new_index_expr("y", "i"),
+        Expr::new_index_expr("a", Expr::new_sub(7, "i")),
+    );
+    gen_for
+        .set_label("gen_rev")
+        .add_stmt(Stmt::new_parallel(assign));
+    module.add_generate_for(gen_for);
+    let mut gen_if = GenerateIf::new(Expr::new_eq("a", 0));
+    gen_if.add_decl(Decl::new_logic("zero", 1));
+    gen_if.set_else(GenerateBlock::new_labeled("nonzero"));
+    module.add_generate_if(gen_if);
+    let res = module.to_string();
+    check!(res, exp);
+    let parsed: Module = exp.parse().unwrap();
+    assert_eq!(parsed, module);
+}
+
+#[test]
+fn test_module_with_loops() {
+    let exp = read_to_string("regression/v17/module_with_loops.v").unwrap();
+    let mut seq_for = SequentialFor::new_range("i", 8);
+    seq_for.set_ty(Ty::Int).add_seq(Sequential::new_blk_assign(
+        Expr::new_index_expr("mem", "i"),
+        0,
+    ));
+    let inc = Sequential::new_blk_assign("count", Expr::new_add("count", 1));
+    let dec = Sequential::new_blk_assign("count", Expr::new_sub("count", 1));
+    let mut initial = ParallelProcess::new_initial();
+    initial.add_seq(seq_for);
+    initial.add_seq(Sequential::new_foreach(
+        "mem",
+        &["j"],
+        vec![Sequential::new_blk_assign(
+            Expr::new_index_expr("mem", "j"),
+            1,
+        )],
+    ));
+    initial.add_seq(Sequential::new_do_while(
+        vec![inc.clone()],
+        Expr::new_lt("count", 8),
+    ));
+    initial.add_seq(Sequential::new_while(Expr::new_gt("count", 0), vec![dec]));
+    initial.add_seq(Sequential::new_repeat(4, vec![inc]));
+    initial.add_seq(Sequential::new_forever(vec![]));
+    let mut module = Module::new("module_with_loops");
+    module.add_decl(Decl::new_logic("mem", 8));
+    module.add_decl(Decl::new_int("count"));
+    module.add_stmt(Stmt::from(initial));
+    let res = module.to_string();
+    check!(res, exp);
+    let parsed: Module = exp.parse().unwrap();
+    assert_eq!(parsed, module);
+    assert!(check_widths(&module, &Context::default()).is_ok());
+}
+
+#[test]
+fn test_module_with_signed() {
+    let mut module = Module::new("scale");
+    module
+        .add_param(Decl::Param(
+            "OFFSET".to_string(),
+            Ty::new_signed(8),
+            Expr::new_ulit_hex(8, "ff"),
+        ))
+        .add_signed_input("a", 8)
+        .add_input("en", 1)
+        .add_signed_output("y", 16)
+        .add_decl(Decl::new_logic("sign", Ty::new_signed(1)));
+    let exp = r#"module scale # (
+    parameter signed [7:0] OFFSET = 8'hff
+) (
+    input logic signed [7:0] a,
+    input logic en,
+    output logic signed [15:0] y
+);
+    logic signed sign;
+endmodule
+"#;
+    let res = module.to_string();
+    check!(res, exp);
+    assert_eq!(exp.parse::<Module>().unwrap(), module);
+    let scope = module_scope(&module);
+    let offset = Expr::new_ref("OFFSET").eval(scope.env()).unwrap();
+    assert_eq!(offset.to_i128(), -1);
+    assert!(check_widths(&module, &Context::default()).is_ok());
+}
+
+#[test]
+fn test_module_with_arrays() {
+    let word = Ty::new_packed([2, 8]);
+    let mut always = ParallelProcess::new_always_ff();
+    always.set_event(Sequential::Event(EventTy::Posedge, Expr::new_ref("clock")));
+    always.add_seq(Sequential::new_nonblk_assign(
+        Expr::new_index(Expr::new_ref("mem"), Expr::new_ref("addr")),
+        Expr::new_ref("wdata"),
+    ));
+    let mut module = Module::new("regfile");
+    module
+        .add_input("clock", 1)
+        .add_input("addr", 4)
+        .add_input("wdata", word.clone())
+        .add_port(Port::new_input_array("init", 8, [4]))
+        .add_output("rdata", 8)
+        .add_decl(Decl::new_array("mem", word, [16]))
+        .add_decl(Decl::new_array(
+            "count",
+            Ty::Int,
+            [Ty::Range(0.into(), 3.into())],
+        ))
+        .add_stmt(Stmt::from(always))
+        .add_stmt(Stmt::new_parallel(Parallel::ParAssign(
+            Expr::new_ref("rdata"),
+            Expr::new_index(
+                Expr::new_index(Expr::new_ref("mem"), Expr::new_ref("addr")),
+                1,
+            ),
+        )));
+    let exp = read_to_string("regression/v17/module_with_arrays.v").unwrap();
+    let res = module.to_string();
+    check!(res, exp);
+    assert_eq!(exp.parse::<Module>().unwrap(), module);
+    assert!(check_widths(&module, &Context::default()).is_ok());
+    let mut wide = module.clone();
+    wide.add_stmt(Stmt::new_parallel(Parallel::ParAssign(
+        Expr::new_ref("rdata"),
+        Expr::new_index(Expr::new_ref("mem"), Expr::new_ref("addr")),
+    )));
+    let errors: Vec<String> = check_widths(&wide, &Context::default())
+        .unwrap_err()
+        .iter()
+        .map(|e| e.to_string())
+        .collect();
+    assert_eq!(
+        errors,
+        vec!["assigning `mem[addr]` to `rdata`: expected 8 bits, found 16"]
+    );
+}
+
+fn bus_pkg() -> Package {
+    let width = Expr::new_ref("WIDTH");
+    let msb = Expr::new_sub(width.clone(), Expr::new_int(1));
+    let mut states = Decl::new_enum("state_t", 2, &["IDLE", "BUSY", "DONE"]);
+    if let Decl::Typedef(_, Typedef::Enum(_, values)) = &mut states {
+        values[2].1 = Some(Expr::new_ulit_dec(2, "3"));
+    }
+    let mut swap = Function::new("swap", Ty::new_range(msb.clone(), 0));
+    swap.add_input("x", Ty::new_range(msb.clone(), 0));
+    swap.add_stmt(Sequential::new_return(Expr::new_ref("x")));
+    let mut package = Package::new("bus_pkg");
+    package
+        .add_localparam("WIDTH", Ty::Int, 8)
+        .add_decl(states)
+        .add_decl(Decl::new_struct(
+            "payload_t",
+            vec![
+                Decl::new_logic("data", Ty::new_range(msb, 0)),
+                Decl::new_logic("valid", 1),
+            ],
+        ))
+        .add_function(swap);
+    package
+}
+
+#[test]
+fn test_package_with_types() {
+    let package = bus_pkg();
+    let exp = r#"package bus_pkg;
+    localparam int WIDTH = 8;
+    typedef enum logic [1:0] {IDLE, BUSY, DONE = 2'd3} state_t;
+    typedef struct packed {
+        logic [WIDTH - 1:0] data;
+        logic valid;
+    } payload_t;
+    function [WIDTH - 1:0] swap(input logic [WIDTH - 1:0] x);
+        begin
+            return x;
+        end
+    endfunction
+endpackage
+"#;
+    let res = package.to_string();
+    check!(res, exp);
+    assert_eq!(exp.parse::<Package>().unwrap(), package);
+    assert!(package.try_to_pretty().is_ok());
+}
+
+#[test]
+fn test_module_with_package_types() {
+    let mut always = ParallelProcess::new_always_ff();
+    always.set_event(Sequential::Event(EventTy::Posedge, Expr::new_ref("clock")));
+    always.add_seq(Sequential::new_nonblk_assign(
+        Expr::new_ref("state"),
+        Expr::new_ref("BUSY"),
+    ));
+    let mut module = Module::new("fsm");
+    module
+        .add_input("clock", 1)
+        .add_input("req", Ty::new_named("bus_pkg::payload_t"))
+        .add_output("data", 8)
+        .add_import("bus_pkg")
+        .add_decl(Decl::new_logic("state", Ty::new_named("state_t")))
+        .add_stmt(Stmt::from(always))
+        .add_stmt(Stmt::new_parallel(Parallel::ParAssign(
+            Expr::new_ref("data"),
+            "req.data".parse::<Expr>().unwrap(),
+        )));
+    let exp = read_to_string("regression/v17/module_with_package_types.v").unwrap();
+    let res = module.to_string();
+    check!(res, exp);
+    assert_eq!(exp.parse::<Module>().unwrap(), module);
+    let packages = [bus_pkg()];
+    let ctx = Context {
+        packages: &packages,
+        ..Context::default()
+    };
+    assert!(check_widths(&module, &ctx).is_ok());
+    assert!(check_widths(&module, &Context::default()).is_err());
+    let mut narrow = module.clone();
+    narrow.add_stmt(Stmt::new_parallel(Parallel::ParAssign(
+        Expr::new_ref("state"),
+        Expr::new_ref("data"),
+    )));
+    narrow.add_stmt(Stmt::new_parallel(Parallel::ParAssign(
+        Expr::new_ref("state"),
+        Expr::new_ref("bus_pkg::DONE"),
+    )));
+    let errors: Vec<String> = check_widths(&narrow, &ctx)
+        .unwrap_err()
+        .iter()
+        .map(|e| e.to_string())
+        .collect();
+    assert_eq!(
+        errors,
+        vec!["assigning `data` to `state`: expected 2 bits, found 8"]
+    );
+}
+
+fn bus_if() -> Interface {
+    let width = Expr::new_ref("WIDTH");
+    let msb = Expr::new_sub(width, Expr::new_int(1));
+    let mut send = Task::new("send");
+    send.add_input("d", Ty::new_range(msb.clone(), 0))
+        .add_stmt(Sequential::new_blk_assign(
+            Expr::new_ref("data"),
+            Expr::new_ref("d"),
+        ))
+        .add_stmt(Sequential::new_blk_assign(
+            Expr::new_ref("valid"),
+            Expr::new_ulit_bin(1, "1"),
+        ));
+    let mut master = Modport::new("master");
+    master
+        .add_output("valid")
+        .add_output("data")
+        .add_input("ready")
+        .add_import("send");
+    let mut slave = Modport::new("slave");
+    slave
+        .add_input("valid")
+        .add_input("data")
+        .add_output("ready");
+    let mut interface = Interface::new("bus_if");
+    interface
+        .add_param(Decl::Param("WIDTH".to_string(), Ty::Int, Expr::new_int(8)))
+        .add_input("clk", 1)
+        .add_logic("valid", 1)
+        .add_logic("ready", 1)
+        .add_logic("data", Ty::new_range(msb, 0))
+        .add_task(send)
+        .add_modport(master)
+        .add_modport(slave);
+    interface
+}
+
+#[test]
+fn test_interface_with_modports() {
+    let interface = bus_if();
+    let exp = r#"interface bus_if # (
+    parameter int WIDTH = 8
+) (
+    input logic clk
+);
+    logic valid;
+    logic ready;
+    logic [WIDTH - 1:0] data;
+    task send(input logic [WIDTH - 1:0] d);
+        begin
+            data = d;
+            valid = 1'b1;
+        end
+    endtask
+    modport master (output valid, output data, input ready, import send);
+    modport slave (input valid, input data, output ready);
+endinterface
+"#;
+    let res = interface.to_string();
+    check!(res, exp);
+    assert_eq!(parse_interface(exp).unwrap(), interface);
+    assert!(interface.try_to_pretty().is_ok());
+    let mut unknown = interface.clone();
+    unknown.add_modport({
+        let mut monitor = Modport::new("monitor");
+        monitor.add_input("last");
+        monitor
+    });
+    assert_eq!(
+        unknown.try_to_pretty().unwrap_err().to_string(),
+        "modport `monitor` refers to `last`, which is not declared"
+    );
+}
+
+#[test]
+fn test_module_with_interface_port() {
+    let mut cond = SequentialIfElse::new(Expr::new_member("m", "ready"));
+    cond.add_seq(Sequential::new_seqexpr(Expr::new_call(
+        "m.send",
+        vec![Expr::new_add(
+            Expr::new_member("m", "data"),
+            Expr::new_ulit_dec(8, "1"),
+        )],
+    )));
+    let mut always = ParallelProcess::new_always_ff();
+    always.set_event(Sequential::Event(EventTy::Posedge, Expr::new_ref("clk")));
+    always.add_seq(cond);
+    let mut module = Module::new("producer");
+    module
+        .add_input("clk", 1)
+        .add_port(Port::new_modport("m", "bus_if", "master"))
+        .add_stmt(Stmt::from(always));
+    let exp = read_to_string("regression/v17/module_with_interface_port.v").unwrap();
+    let res = module.to_string();
+    check!(res, exp);
+    assert_eq!(exp.parse::<Module>().unwrap(), module);
+    let interfaces = [bus_if()];
+    let ctx = Context {
+        interfaces: &interfaces,
+        ..Context::default()
+    };
+    assert!(check_widths(&module, &ctx).is_ok());
+    let mut top = Module::new("top");
+    let mut bus = Instance::new("bus", "bus_if");
+    bus.connect_ref("clk", "clk").unwrap();
+    let mut producer = Instance::new("u0", "producer");
+    producer.connect_ref("clk", "clk").unwrap();
+    producer.connect_ref("m", "bus").unwrap();
+    top.add_input("clk", 1)
+        .add_output("valid", 1)
+        .add_instance(bus)
+        .add_instance(producer)
+        .add_stmt(Stmt::new_parallel(Parallel::ParAssign(
+            Expr::new_ref("valid"),
+            Expr::new_member("bus", "valid"),
+        )));
+    let defs = [module.clone()];
+    let ctx = Context {
+        defs: &defs,
+        interfaces: &interfaces,
+        ..Context::default()
+    };
+    assert!(check_widths(&top, &ctx).is_ok());
+    top.add_stmt(Stmt::new_parallel(Parallel::ParAssign(
+        Expr::new_ref("valid"),
+        Expr::new_member("bus", "data"),
+    )));
+    let errors: Vec<String> = check_widths(&top, &ctx)
+        .unwrap_err()
+        .iter()
+        .map(|e| e.to_string())
+        .collect();
+    assert_eq!(
+        errors,
+        vec!["assigning `bus.data` to `valid`: expected 1 bits, found 8"]
+    );
+}
+
+#[test]
+fn test_module_with_inout_ref() {
+    let mut module = Module::new("bus");
+    module.add_input("clock", 1);
+    module.add_inout("data", 16);
+    module.add_ref("count", 32);
+    let exp = r#"module bus (
+    input logic clock,
+    inout logic [15:0] data,
+    ref logic [31:0] count
+);
+endmodule
+"#;
+    let res = module.to_string();
+    check!(res, exp);
+    assert_eq!(exp.parse::<Module>().unwrap(), module);
+    let exp = r#"module bus (
+    clock,
+    data,
+    count
+);
+    input logic clock;
+    inout logic [15:0] data;
+    ref logic [31:0] count;
+endmodule
+"#;
+    let res = module.to_pretty_with_style(PortStyle::NonAnsi);
+    check!(res, exp);
+    assert_eq!(exp.parse::<Module>().unwrap(), module);
+}
+
+#[test]
+fn test_module_with_ranges() {
+    let mut module = Module::new("ranges");
+    module.add_param(Decl::new_param_uint("MSB", 15));
+    module.add_param(Decl::new_param_uint("LSB", 8));
+    module.add_input("a", Ty::new_range("MSB", "LSB"));
+    module.add_output("y", Ty::new_range(0, 7));
+    module.add_decl(Decl::new_logic("t", Ty::new_range("MSB", 0)));
+    let exp = r#"module ranges # (
+    parameter int MSB = 32'd15,
+    parameter int LSB = 32'd8
+) (
+    input logic [MSB:LSB] a,
+    output logic [0:7] y
+);
+    logic [MSB:0] t;
+endmodule
+"#;
+    let res = module.to_string();
+    check!(res, exp);
+    assert_eq!(exp.parse::<Module>().unwrap(), module);
+    let mut assign = module.clone();
+    assign.add_stmt(Stmt::new_parallel(Parallel::ParAssign(
+        Expr::new_ref("y"),
+        Expr::new_ref("a"),
+    )));
+    assert!(check_widths(&assign, &Context::default()).is_ok());
+    assign.add_stmt(Stmt::new_parallel(Parallel::ParAssign(
+        Expr::new_ref("t"),
+        Expr::new_ref("a"),
+    )));
+    assert!(check_widths(&assign, &Context::default()).is_ok());
+    assign.add_stmt(Stmt::new_parallel(Parallel::ParAssign(
+        Expr::new_ref("y"),
+        Expr::new_ref("t"),
+    )));
+    assert!(check_widths(&assign, &Context::default()).is_err());
+}
+
+#[test]
+fn test_design_units() {
+    let producer = read_to_string("regression/v17/module_with_interface_port.v").unwrap();
+    let top = r#"module top (
+    input logic clk,
+    output logic [bus_pkg::WIDTH-1:0] y
+);
+    bus_if bus ();
+    producer p0 (
+        .clk(clk),
+        .m(bus)
+    );
+    assign y = bus.data;
+endmodule
+"#;
+    let mut design = Design::new();
+    design.add_module(top.parse().unwrap()).unwrap();
+    design.add_module(producer.parse().unwrap()).unwrap();
+    design.add_interface(bus_if()).unwrap();
+    design.add_package(bus_pkg()).unwrap();
+    let names: Vec<&str> = design
+        .units()
+        .iter()
+        .map(|unit| unit.name().as_str())
+        .collect();
+    assert_eq!(names, vec!["bus_pkg", "bus_if", "producer", "top"]);
+    let tops: Vec<&str> = design
+        .top_modules()
+        .iter()
+        .map(|m| m.name.as_str())
+        .collect();
+    assert_eq!(tops, vec!["top"]);
+    assert!(design.check_definitions().is_ok());
+    let inst = match &design.module("top").unwrap().body[1] {
+        Stmt::Parallel(Parallel::Inst(inst)) => inst,
+        _ => panic!("expected an instance"),
+    };
+    assert_eq!(design.resolve(inst).unwrap().name, "producer");
+    assert!(design.add_interface(Interface::new("producer")).is_err());
+    let res = design.to_string();
+    assert_eq!(res.parse::<Design>().unwrap(), design);
+    let dir = std::env::temp_dir().join("vast_test_design_units");
+    std::fs::create_dir_all(&dir).unwrap();
+    let paths = design.write_files(&dir).unwrap();
+    assert_eq!(paths.len(), 4);
+    assert_eq!(paths[3], dir.join("top.sv"));
+    check!(read_to_string(&paths[2]).unwrap(), producer);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_design_undefined() {
+    let mut design = Design::new();
+    let mut module = Module::new("top");
+    module.add_import("bus_pkg");
+    module.add_instance(Instance::new("c0", "consumer"));
+    design.add_module(module).unwrap();
+    let errors = design.check_definitions().unwrap_err();
+    let messages: Vec<String> = errors.iter().map(|err| err.to_string()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "`top` uses `consumer`, which is not defined",
+            "`top` uses `bus_pkg`, which is not defined",
+        ]
+    );
+    assert!(design.try_to_pretty().is_err());
+    let src = "module a ();\nendmodule\nmodule a ();\nendmodule\n";
+    let err = src.parse::<Design>().unwrap_err();
+    assert!(matches!(err, vast::Error::Duplicate(id) if id == "a"));
+}
+
+#[test]
+fn test_check_instances() {
+    let fifo: Module = r#"module fifo # (
+    parameter int WIDTH = 32'd8
+) (
+    input logic clk,
+    input logic [WIDTH-1:0] din,
+    output logic [WIDTH-1:0] dout
+);
+endmodule
+"#
+    .parse()
+    .unwrap();
+    let top: Module = r#"module top (
+    input logic clk,
+    input logic [15:0] a,
+    output logic [15:0] y,
+    output logic [7:0] z
+);
+    fifo # (
+        .WIDTH(16)
+    ) f0 (
+        .clk(clk),
+        .din(a),
+        .dout(y)
+    );
+    fifo # (
+        .DEPTH(4),
+        .WIDTH(16)
+    ) f1 (
+        .clock(clk),
+        .din(a[7:0]),
+        .dout(z)
+    );
+endmodule
+"#
+    .parse()
+    .unwrap();
+    let errors = check_instances(&top, std::slice::from_ref(&fifo)).unwrap_err();
+    let messages: Vec<String> = errors.iter().map(|err| err.to_string()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "instance `f1` of `fifo` sets `DEPTH`, which is not a parameter",
+            "instance `f1` of `fifo` connects `clock`, which is not a port",
+            "instance `f1` of `fifo` leaves input `clk` unconnected",
+        ]
+    );
+    let ctx = Context {
+        defs: std::slice::from_ref(&fifo),
+        ..Context::default()
+    };
+    let errors = check_widths(&top, &ctx).unwrap_err();
+    let messages: Vec<String> = errors.iter().map(|err| err.to_string()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "port `din` of instance `f1`: expected 16 bits, found 8",
+            "port `dout` of instance `f1`: expected 16 bits, found 8",
+        ]
+    );
+    let mut design = Design::new();
+    design.add_module(fifo).unwrap();
+    design.add_module(top).unwrap();
+    assert_eq!(design.check_instances().unwrap_err().len(), 3);
+    assert!(design.try_to_pretty().is_err());
+}
+
+#[test]
+fn test_instance_connection_order() {
+    let exp = read_to_string("regression/v17/module_with_positional_instance.v").unwrap();
+    let mut inst = Instance::new("f0", "fifo");
+    inst.add_param_uint("WIDTH", 8);
+    inst.add_param_uint("DEPTH", 4);
+    inst.connect_positional(Expr::new_ref("clk")).unwrap();
+    inst.connect_positional(Expr::new_ref("a")).unwrap();
+    inst.connect_positional(Expr::new_ref("y")).unwrap();
+    let mut module = Module::new("module_with_positional_instance");
+    module.add_input("clk", 1);
+    module.add_input("a", 8);
+    module.add_output("y", 8);
+    module.add_instance(inst.clone());
+    let res = module.to_string();
+    check!(res, exp);
+    assert_eq!(exp.parse::<Module>().unwrap(), module);
+    let fifo: Module = r#"module fifo # (
+    parameter int DEPTH = 32'd2,
+    parameter int WIDTH = 32'd8
+) (
+    input logic clk,
+    input logic [WIDTH-1:0] din,
+    output logic [WIDTH-1:0] dout
+);
+endmodule
+"#
+    .parse()
+    .unwrap();
+    assert!(check_instances(&module, std::slice::from_ref(&fifo)).is_ok());
+    let mut design = Design::new();
+    design.add_module(fifo).unwrap();
+    inst.ports = Connections::default();
+    inst.connect_ref("dout", "y").unwrap();
+    inst.connect_ref("din", "a").unwrap();
+    inst.connect_ref("clk", "clk").unwrap();
+    let mut top = Module::new("top");
+    top.add_instance(inst);
+    design.add_module(top).unwrap();
+    design.order_connections();
+    let exp = r#"fifo # (
+    .DEPTH(32'd4),
+    .WIDTH(32'd8)
+) f0 (
+    .clk(clk),
+    .din(a),
+    .dout(y)
+);"#;
+    let res = match &design.module("top").unwrap().body[0] {
+        Stmt::Parallel(Parallel::Inst(inst)) => inst.to_string(),
+        _ => panic!("expected an instance"),
+    };
+    check!(res, exp);
+    let mut extra = Instance::new("f1", "fifo");
+    for name in &["clk", "a", "y", "clk"] {
+        extra.connect_positional(Expr::new_ref(name)).unwrap();
+    }
+    let mut module = module.clone();
+    module.add_instance(extra);
+    let errors = check_instances(
+        &module,
+        &design.modules().values().cloned().collect::<Vec<_>>(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        errors[0].to_string(),
+        "instance `f1` of `fifo` connects 4 ports by position, which is more than 3"
+    );
+}
+
+#[test]
+fn test_instance_connects_one_way() {
+    let mut inst = Instance::new("f0", "fifo");
+    inst.connect_ref("clk", "clk").unwrap();
+    let err = inst.connect_positional(Expr::new_ref("a")).unwrap_err();
+    check!(err.to_string(), "instance `f0` connects ports by name");
+    let mut inst = Instance::new("f1", "fifo");
+    inst.connect_positional(Expr::new_ref("a")).unwrap();
+    let err = inst.connect_ref("clk", "clk").unwrap_err();
+    check!(err.to_string(), "instance `f1` connects ports by position");
+    let mut map = Map::new();
+    map.insert_open("a".to_string());
+    assert_eq!(map.len(), 1);
+    assert!(map.iter().next().is_none());
+    assert_eq!(Connections::Named(map).len(), 1);
+}
+
+fn hierarchy() -> Design {
+    let src = r#"module leaf # (
+    parameter int W = 32'd4
+) (
+    input logic [W-1:0] a,
+    output logic [W-1:0] y
+);
+    logic [W-1:0] t;
+    assign t = ~a;
+    assign y = t;
+endmodule
+
+module mid # (
+    parameter int W = 32'd4
+) (
+    input logic [W-1:0] a,
+    output logic [W-1:0] y
+);
+    leaf # (
+        .W(W)
+    ) l0 (
+        .a(a),
+        .y(y)
+    );
+endmodule
+
+module top # (
+    parameter int N = 32'd8
+) (
+    input logic [N-1:0] a,
+    input logic [3:0] b,
+    output logic [N-1:0] y,
+    output logic [3:0] z
+);
+    mid # (
+        .W(N)
+    ) m0 (
+        .a(a),
+        .y(y)
+    );
+    mid m1 (
+        .a(b),
+        .y(z)
+    );
+endmodule
+"#;
+    src.parse().unwrap()
+}
+
+#[test]
+fn test_design_elaborate() {
+    let design = hierarchy();
+    let mut params = Map::new();
+    params.insert("N".to_string(), Expr::new_int(16));
+    let elab = design.elaborate("top", &params).unwrap();
+    let names: Vec<&str> = elab.modules().keys().map(|name| name.as_str()).collect();
+    assert_eq!(
+        names,
+        vec!["leaf__W_16", "leaf__W_4", "mid__W_16", "mid__W_4", "top"]
+    );
+    assert!(elab.check_definitions().is_ok());
+    assert!(elab.check_instances().is_ok());
+    let exp = r#"module mid__W_16 # (
+    parameter int W = 16
+) (
+    input logic [W - 1:0] a,
+    output logic [W - 1:0] y
+);
+    leaf__W_16 l0 (
+        .a(a),
+        .y(y)
+    );
+endmodule
+"#;
+    let res = elab.module("mid__W_16").unwrap().to_string();
+    check!(res, exp);
+    // both instances of `mid` share one module
+    params.insert("N".to_string(), Expr::new_int(4));
+    let elab = design.elaborate("top", &params).unwrap();
+    assert_eq!(elab.modules().len(), 3);
+    params.insert("M".to_string(), Expr::new_int(1));
+    assert!(design.elaborate("top", &params).is_err());
+    let mut module = Module::new("loose");
+    module.add_input("a", 4);
+    let mut inst = Instance::new("u0", "leaf");
+    inst.add_param("W", Expr::new_ref("a"));
+    module.add_instance(inst);
+    let mut design = hierarchy();
+    design.add_module(module).unwrap();
+    let err = design.elaborate("loose", &Map::new()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "instance `u0` sets `W` to `a`, which is not constant"
+    );
+}
+
+#[test]
+fn test_design_flatten() {
+    let mut design = hierarchy();
+    design.flatten("mid", "l0").unwrap();
+    let exp = r#"module mid # (
+    parameter int W = 32'd4
+) (
+    input logic [W - 1:0] a,
+    output logic [W - 1:0] y
+);
+    localparam int l0__W = W;
+    logic [l0__W - 1:0] l0__a;
+    assign l0__a = a;
+    logic [l0__W - 1:0] l0__y;
+    assign y = l0__y;
+    logic [l0__W - 1:0] l0__t;
+    assign l0__t = ~l0__a;
+    assign l0__y = l0__t;
+endmodule
+"#;
+    let res = design.module("mid").unwrap().to_string();
+    check!(res, exp);
+    assert!(design.flatten("mid", "l0").is_err());
+    let mut elab = hierarchy().elaborate("top", &Map::new()).unwrap();
+    elab.flatten_all("top").unwrap();
+    let top = elab.module("top").unwrap();
+    assert!(top
+        .body()
+        .iter()
+        .all(|stmt| !matches!(stmt, Stmt::Parallel(Parallel::Inst(_)))));
+    assert!(check_widths(top, &Context::default()).is_ok());
+    let res = top.to_string();
+    assert!(res.contains("assign m1__l0__a = m1__a;"));
+    assert!(res.contains("assign z = m1__y;"));
+    assert_eq!(res.parse::<Module>().unwrap(), *top);
+}